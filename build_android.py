@@ -5,6 +5,17 @@ import subprocess
 import sys
 import shutil
 
+def feature_args():
+    # 精简构建：python build_android.py --features mtk,control-socket
+    # 未指定时使用 Cargo.toml 中的默认 feature（全部功能）
+    if "--features" in sys.argv:
+        index = sys.argv.index("--features")
+        if index + 1 >= len(sys.argv):
+            print("错误：--features 需要逗号分隔的 feature 列表")
+            sys.exit(1)
+        return ["--no-default-features", "--features", sys.argv[index + 1]]
+    return []
+
 def check_ndk_path():
     ndk_path = "D:/android-ndk"
     if not os.path.isdir(ndk_path):
@@ -50,7 +61,7 @@ def run_fmt_and_clippy():
     # 运行clippy检查
     print("运行 clippy 检查...")
     try:
-        subprocess.run(["cargo", "clippy", "--target", "aarch64-linux-android", "--", "-D", "warnings"], check=True, cwd=project_root)
+        subprocess.run(["cargo", "clippy", "--target", "aarch64-linux-android", *feature_args(), "--", "-D", "warnings"], check=True, cwd=project_root)
         print("clippy 检查通过")
     except subprocess.CalledProcessError as e:
         print(f"clippy检查失败: {e}")
@@ -64,7 +75,7 @@ def build_android():
     try:
         # 确保在项目根目录执行cargo命令
         project_root = os.path.dirname(os.path.abspath(__file__))
-        subprocess.run(["cargo", "build", "--target", "aarch64-linux-android", "--release"], 
+        subprocess.run(["cargo", "build", "--target", "aarch64-linux-android", "--release", *feature_args()], 
                       check=True, cwd=project_root)
     except subprocess.CalledProcessError as e:
         print(f"构建Android版本失败: {e}")
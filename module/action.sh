@@ -1,21 +1,10 @@
 #!/system/bin/sh
 
 MODDIR=${0%/*}
-FREE_FILE="$MODDIR/free"
 
-if [ -f "$FREE_FILE" ]; then
-    FREE_VALUE=$(cat "$FREE_FILE" | tr -d '[:space:]')
-else
-    FREE_VALUE="0"
-fi
-
-if [ "$FREE_VALUE" = "0" ]; then
-    printf "1" > "$FREE_FILE"
-    echo "✅锁定PPS支持⚡"
-else
-    printf "0" > "$FREE_FILE"
-    echo "⏸️PPS已暂停💤"
-fi
+# 守护进程运行时经控制socket切换，未运行时由二进制直接改写free文件
+"$MODDIR/bin/FreePPS" toggle --module-dir "$MODDIR"
+STATUS=$?
 
 sleep 0.3
-sleep 0.27
+exit $STATUS
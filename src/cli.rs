@@ -0,0 +1,416 @@
+pub mod adapters;
+pub mod diagnostics;
+pub mod doctor;
+pub mod history;
+
+use std::path::Path;
+use std::sync::Arc;
+
+use anyhow::Result;
+#[cfg(feature = "control-socket")]
+use log::warn;
+
+use crate::common::{FreeMode, FreePPSError, PathOverrides, Paths, utils};
+use crate::config::ConfigStore;
+use crate::control::collect_status;
+use crate::control::protocol::{Command, DaemonStatus, FreeState, Reply};
+#[cfg(feature = "control-socket")]
+use crate::control::send_command;
+use crate::monitoring::{ModuleManager, NetlinkUevents};
+use crate::pd;
+use crate::platform::{ShutdownSignal, install_signal_handlers};
+use crate::trace;
+
+// 进程退出码
+pub const EXIT_OK: i32 = 0;
+pub const EXIT_FAILURE: i32 = 1;
+pub const EXIT_USAGE: i32 = 2;
+
+pub const USAGE: &str = "\
+用法: FreePPS [子命令] [选项]
+
+子命令:
+  daemon     以守护进程运行（默认）
+  status     查询当前状态
+  enable     启用（锁定PPS支持）
+  disable    暂停
+  toggle     切换启用/暂停
+  auto       按充电头自动选择（公版PPS解锁，小米充电头走MIPPS）
+  doctor     检测设备兼容性
+  collect-diagnostics [文件]  打包诊断信息用于反馈问题（默认写入 /sdcard/Download）
+  history [条数]  列出最近的充电会话与各充电头汇总（默认10条）
+  adapters   列出充电头规则与当前充电头指纹
+  adapters <unlock|lock|no-forge> [指纹]  记录充电头的决定（缺省为当前充电头，可逗号组合）
+  adapters forget [指纹]  删除充电头规则
+  record <文件>  录制 power_supply uevent 轨迹（Ctrl+C 结束）
+  replay <文件>  回放轨迹并输出决策日志
+  version    显示版本
+  help       显示本帮助
+
+选项:
+  --json               以 JSON 输出（status/enable/disable/toggle/auto/doctor/history/adapters）
+  --module-dir <路径>  模块目录
+  --sysfs-root <路径>  sysfs 根目录
+  --config <路径>      配置文件";
+
+/// 子命令
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Subcommand {
+    Daemon,
+    Status,
+    Enable,
+    Disable,
+    Toggle,
+    Auto,
+    Doctor,
+    CollectDiagnostics,
+    History,
+    Adapters,
+    Record,
+    Replay,
+    Version,
+    Help,
+}
+
+impl Subcommand {
+    fn parse(text: &str) -> Option<Self> {
+        match text {
+            "daemon" => Some(Self::Daemon),
+            "status" => Some(Self::Status),
+            "enable" => Some(Self::Enable),
+            "disable" => Some(Self::Disable),
+            "toggle" => Some(Self::Toggle),
+            "auto" => Some(Self::Auto),
+            "doctor" => Some(Self::Doctor),
+            "collect-diagnostics" => Some(Self::CollectDiagnostics),
+            "history" => Some(Self::History),
+            "adapters" => Some(Self::Adapters),
+            "record" => Some(Self::Record),
+            "replay" => Some(Self::Replay),
+            "version" | "--version" | "-V" => Some(Self::Version),
+            "help" | "--help" | "-h" => Some(Self::Help),
+            _ => None,
+        }
+    }
+
+    /// 对应的控制 socket 命令（仅客户端子命令）
+    fn control_command(&self) -> Option<Command> {
+        match self {
+            Self::Status => Some(Command::Status),
+            Self::Enable => Some(Command::Enable),
+            Self::Disable => Some(Command::Disable),
+            Self::Toggle => Some(Command::Toggle),
+            Self::Auto => Some(Command::Auto),
+            _ => None,
+        }
+    }
+}
+
+/// 解析后的命令行
+#[derive(Debug, Clone)]
+pub struct Cli {
+    pub subcommand: Subcommand,
+    pub overrides: PathOverrides,
+    pub json: bool,
+    /// 子命令的位置参数（record / replay 的轨迹文件，history 的条数，adapters 的决定与指纹，collect-diagnostics 的输出文件）
+    pub args: Vec<String>,
+}
+
+impl Cli {
+    /// 解析命令行参数（不含程序名）；未指定子命令时为 `daemon`，与旧版启动方式兼容
+    pub fn parse<I: IntoIterator<Item = String>>(args: I) -> Result<Self> {
+        let mut subcommand = None;
+        let mut json = false;
+        let mut path_args = Vec::new();
+        let mut positional = Vec::new();
+        let mut args = args.into_iter();
+
+        while let Some(arg) = args.next() {
+            if arg == "--json" {
+                json = true;
+            } else if let Some(parsed) = Subcommand::parse(&arg) {
+                if subcommand.replace(parsed).is_some() {
+                    return Err(
+                        FreePPSError::InvalidArgument(format!("重复的子命令: {}", arg)).into(),
+                    );
+                }
+            } else if arg.starts_with("--") {
+                // 路径选项：`--flag value` 形式需要连同取值一起交给 PathOverrides 解析
+                let needs_value = !arg.contains('=');
+                path_args.push(arg);
+                if needs_value && let Some(value) = args.next() {
+                    path_args.push(value);
+                }
+            } else if matches!(
+                subcommand,
+                Some(
+                    Subcommand::Record
+                        | Subcommand::Replay
+                        | Subcommand::History
+                        | Subcommand::Adapters
+                        | Subcommand::CollectDiagnostics
+                )
+            ) {
+                positional.push(arg);
+            } else {
+                return Err(FreePPSError::InvalidArgument(format!("未知子命令: {}", arg)).into());
+            }
+        }
+
+        let subcommand = subcommand.unwrap_or(Subcommand::Daemon);
+        if matches!(subcommand, Subcommand::Record | Subcommand::Replay) && positional.len() != 1 {
+            return Err(FreePPSError::InvalidArgument("需要指定一个轨迹文件".to_string()).into());
+        }
+        if subcommand == Subcommand::History
+            && (positional.len() > 1 || positional.iter().any(|n| n.parse::<usize>().is_err()))
+        {
+            return Err(
+                FreePPSError::InvalidArgument("history 只接受一个条数参数".to_string()).into(),
+            );
+        }
+
+        if subcommand == Subcommand::CollectDiagnostics && positional.len() > 1 {
+            return Err(FreePPSError::InvalidArgument(
+                "collect-diagnostics 只接受一个输出文件参数".to_string(),
+            )
+            .into());
+        }
+
+        if subcommand == Subcommand::Adapters && positional.len() > 2 {
+            return Err(FreePPSError::InvalidArgument(
+                "adapters 只接受决定与指纹两个参数".to_string(),
+            )
+            .into());
+        }
+
+        Ok(Self {
+            subcommand,
+            overrides: PathOverrides::parse(path_args)?,
+            json,
+            args: positional,
+        })
+    }
+}
+
+/// 执行客户端子命令（daemon 以外），返回进程退出码
+pub fn run(cli: &Cli) -> i32 {
+    match cli.subcommand {
+        Subcommand::Daemon => EXIT_USAGE,
+        Subcommand::Help => {
+            println!("{}", USAGE);
+            EXIT_OK
+        }
+        Subcommand::Version => {
+            println!("FreePPS v{}", env!("CARGO_PKG_VERSION"));
+            EXIT_OK
+        }
+        Subcommand::Doctor => match Paths::resolve(&cli.overrides) {
+            Ok(paths) => doctor::run(Arc::new(paths), cli.json),
+            Err(e) => {
+                eprintln!("错误: {}", e);
+                EXIT_FAILURE
+            }
+        },
+        Subcommand::CollectDiagnostics => match Paths::resolve(&cli.overrides) {
+            Ok(paths) => diagnostics::run(Arc::new(paths), cli.args.first().map(String::as_str)),
+            Err(e) => {
+                eprintln!("错误: {}", e);
+                EXIT_FAILURE
+            }
+        },
+        Subcommand::History => {
+            let limit = cli
+                .args
+                .first()
+                .and_then(|n| n.parse().ok())
+                .unwrap_or(history::DEFAULT_LIMIT);
+            match Paths::resolve(&cli.overrides) {
+                Ok(paths) => history::run(&paths, limit, cli.json),
+                Err(e) => {
+                    eprintln!("错误: {}", e);
+                    EXIT_FAILURE
+                }
+            }
+        }
+        Subcommand::Adapters => match Paths::resolve(&cli.overrides) {
+            Ok(paths) => adapters::run(&paths, &cli.args, cli.json),
+            Err(e) => {
+                eprintln!("错误: {}", e);
+                EXIT_FAILURE
+            }
+        },
+        Subcommand::Record => {
+            match Paths::resolve(&cli.overrides).and_then(|paths| record(&paths, &cli.args[0])) {
+                Ok(count) => {
+                    eprintln!("已录制{}条uevent: {}", count, cli.args[0]);
+                    EXIT_OK
+                }
+                Err(e) => {
+                    eprintln!("错误: {}", e);
+                    EXIT_FAILURE
+                }
+            }
+        }
+        Subcommand::Replay => {
+            match trace::read_trace(Path::new(&cli.args[0]))
+                .and_then(|entries| trace::replay(&entries))
+            {
+                Ok(lines) => {
+                    for line in lines {
+                        println!("{}", line);
+                    }
+                    EXIT_OK
+                }
+                Err(e) => {
+                    eprintln!("错误: {}", e);
+                    EXIT_FAILURE
+                }
+            }
+        }
+        Subcommand::Status
+        | Subcommand::Enable
+        | Subcommand::Disable
+        | Subcommand::Toggle
+        | Subcommand::Auto => {
+            let Some(command) = cli.subcommand.control_command() else {
+                return EXIT_USAGE;
+            };
+            let reply = Paths::resolve(&cli.overrides)
+                .and_then(|paths| request(Arc::new(paths), command))
+                .unwrap_or_else(|e| Reply::failure(Some(command), e.to_string()));
+            render(command, &reply, cli.json)
+        }
+    }
+}
+
+/// 录制到收到 SIGINT / SIGTERM 为止
+fn record(paths: &Paths, output: &str) -> Result<u64> {
+    let shutdown = Arc::new(ShutdownSignal::new()?);
+    install_signal_handlers(&shutdown);
+    trace::record(paths, &NetlinkUevents, Path::new(output), &shutdown)
+}
+
+/// 优先通过控制 socket 请求守护进程；守护进程未运行（或未编译控制 socket）时经 ModuleManager 直接读写 free 文件
+///
+/// 直接写入的 free 文件同样会被运行中的守护进程经 inotify 感知。
+fn request(paths: Arc<Paths>, command: Command) -> Result<Reply> {
+    #[cfg(feature = "control-socket")]
+    {
+        if let Some(reply) = send_command(paths.control_socket(), command)? {
+            return Ok(reply);
+        }
+        warn!("守护进程未运行，直接操作free文件: {}", command.as_str());
+    }
+    let config = Arc::new(ConfigStore::load(&paths)?);
+    let backends = pd::charger_backends(&paths, &config)?;
+    let module_manager = ModuleManager::new(paths, backends)?;
+
+    Ok(match command {
+        Command::Status => {
+            let mut status = collect_status(&module_manager);
+            status.pid = None;
+            Reply::success(command, status)
+        }
+        Command::Enable | Command::Disable | Command::Auto => {
+            let mode = match command {
+                Command::Enable => FreeMode::On,
+                Command::Auto => FreeMode::Auto,
+                _ => FreeMode::Off,
+            };
+            module_manager.set_free_mode(mode)?;
+            Reply::success(command, FreeState::from(mode))
+        }
+        Command::Toggle => {
+            let mode = module_manager.toggle_free()?;
+            Reply::success(command, FreeState::from(mode))
+        }
+        _ if cfg!(feature = "control-socket") => Reply::failure(Some(command), "守护进程未运行"),
+        _ => Reply::failure(
+            Some(command),
+            "编译时未启用控制socket，无法向守护进程发送该命令",
+        ),
+    })
+}
+
+/// 输出回复并返回退出码
+fn render(command: Command, reply: &Reply, json: bool) -> i32 {
+    if json {
+        match serde_json::to_string(reply) {
+            Ok(text) => println!("{}", text),
+            Err(e) => eprintln!("错误: 序列化回复失败: {}", e),
+        }
+    } else if !reply.ok {
+        eprintln!("错误: {}", reply.error.as_deref().unwrap_or("未知错误"));
+    } else {
+        let data = reply.data.clone().unwrap_or_default();
+        match command {
+            Command::Status => match serde_json::from_value::<DaemonStatus>(data) {
+                Ok(status) => print_status(&status),
+                Err(e) => eprintln!("错误: 解析状态失败: {}", e),
+            },
+            _ => match serde_json::from_value::<FreeState>(data) {
+                Ok(state) => println!("{}", state_label(state.mode)),
+                Err(e) => eprintln!("错误: 解析切换结果失败: {}", e),
+            },
+        }
+    }
+
+    if reply.ok { EXIT_OK } else { EXIT_FAILURE }
+}
+
+/// 与 module.prop 描述前缀一致的状态文字
+fn state_label(mode: FreeMode) -> &'static str {
+    match mode {
+        FreeMode::On => "✅锁定PPS支持⚡",
+        FreeMode::Auto => "🔀自动选择PPS/MIPPS",
+        FreeMode::Off => "⏸️PPS已暂停💤",
+    }
+}
+
+fn print_status(status: &DaemonStatus) {
+    match status.pid {
+        Some(pid) => println!("FreePPS v{}（守护进程运行中，pid={}）", status.version, pid),
+        None => println!("FreePPS v{}（守护进程未运行）", status.version),
+    }
+    println!("状态: {}", state_label(status.mode));
+    if !status.holds.is_empty() {
+        println!("解锁已暂停: {}", status.holds.join("，"));
+    }
+    println!("电池: {}", status.battery_status);
+    for backend in &status.backends {
+        let state = match (backend.detected, backend.unlocked) {
+            (false, _) => "节点不存在",
+            (true, Some(true)) => "已解锁",
+            (true, Some(false)) => "未解锁",
+            (true, None) => "取值未知",
+        };
+        println!("{}: {} ({})", backend.name, state, backend.node);
+        if let Some(writes) = backend.writes.as_ref().filter(|writes| writes.reverts > 0) {
+            println!(
+                "  写入{}次，被驱动改回{}次，未生效{}次",
+                writes.writes, writes.reverts, writes.failed
+            );
+            if let Some(until) = writes.backoff_until.filter(|_| writes.is_fighting()) {
+                println!(
+                    "  ⚠️与驱动争夺节点，暂停写入至{}",
+                    utils::format_local_time(until)
+                );
+            }
+        }
+    }
+    if let Some(session) = &status.session {
+        println!(
+            "充电会话#{}: {}，已持续{}秒，充电头 {} svid={} apdo_max={}",
+            session.id,
+            session.state,
+            session.duration_secs,
+            session.adapter.real_type.as_deref().unwrap_or("-"),
+            session.adapter.svid.as_deref().unwrap_or("-"),
+            session
+                .adapter
+                .apdo_max
+                .map(|w| format!("{}W", w))
+                .unwrap_or_else(|| "-".to_string())
+        );
+    }
+}
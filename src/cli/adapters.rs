@@ -0,0 +1,181 @@
+use anyhow::Result;
+use serde::Serialize;
+
+use crate::cli::{EXIT_FAILURE, EXIT_OK, EXIT_USAGE};
+use crate::common::{FreePPSError, Paths};
+use crate::monitoring::FileMonitor;
+use crate::policy::adapters::{self, AdapterRule, AdapterRules};
+use crate::session::AdapterInfo;
+use crate::session::history;
+
+/// `adapters --json` 的输出
+#[derive(Debug, Serialize)]
+struct AdaptersReport {
+    /// 当前插入的充电头指纹（未插电或 SVID 未知时缺省）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    current: Option<String>,
+    rules: Vec<RuleEntry>,
+    /// 会话历史中出现过、尚未设置规则的充电头
+    seen: Vec<String>,
+}
+
+#[derive(Debug, Serialize)]
+struct RuleEntry {
+    fingerprint: String,
+    decision: String,
+    #[serde(flatten)]
+    rule: AdapterRule,
+}
+
+/// 管理充电头规则，返回退出码
+///
+/// - 无参数：列出规则、当前充电头与历史中出现过的充电头
+/// - `<决定> [指纹]`：为指纹（缺省为当前充电头）设置决定
+/// - `forget [指纹]`：删除规则
+pub fn run(paths: &Paths, args: &[String], json: bool) -> i32 {
+    let result = match args.first().map(String::as_str) {
+        None => return list(paths, json),
+        Some("forget") => forget(paths, args.get(1)),
+        Some(decision) => match AdapterRule::parse(decision) {
+            Some(rule) => set(paths, rule, args.get(1)),
+            None => {
+                eprintln!(
+                    "错误: 未知的决定 {}（可用 unlock / lock / no-forge，逗号组合）",
+                    decision
+                );
+                return EXIT_USAGE;
+            }
+        },
+    };
+
+    match result {
+        Ok(message) => {
+            println!("{}", message);
+            EXIT_OK
+        }
+        Err(e) => {
+            eprintln!("错误: {}", e);
+            EXIT_FAILURE
+        }
+    }
+}
+
+/// 当前插入的充电头指纹
+fn current_fingerprint(paths: &Paths) -> Option<String> {
+    let charging = FileMonitor::read_file_content(paths.battery_status())
+        .is_ok_and(|status| status == "Charging");
+    charging
+        .then(|| adapters::fingerprint(&AdapterInfo::read(paths)))
+        .flatten()
+}
+
+/// 命令行指定的指纹，缺省为当前充电头
+fn target(paths: &Paths, fingerprint: Option<&String>) -> Result<String> {
+    match fingerprint {
+        Some(fingerprint) => Ok(fingerprint.clone()),
+        None => current_fingerprint(paths).ok_or_else(|| {
+            FreePPSError::InvalidArgument("当前未插入可识别的充电头，请指定指纹".to_string()).into()
+        }),
+    }
+}
+
+fn set(paths: &Paths, rule: AdapterRule, fingerprint: Option<&String>) -> Result<String> {
+    let fingerprint = target(paths, fingerprint)?;
+    let mut rules = AdapterRules::load(paths.adapter_rules())?;
+    rules.set(&fingerprint, rule);
+    rules.save(paths.adapter_rules())?;
+    Ok(format!(
+        "已记录 {}={}（下次插入或充电状态刷新时生效）",
+        fingerprint,
+        rule.decision()
+    ))
+}
+
+fn forget(paths: &Paths, fingerprint: Option<&String>) -> Result<String> {
+    let fingerprint = target(paths, fingerprint)?;
+    let mut rules = AdapterRules::load(paths.adapter_rules())?;
+    if !rules.remove(&fingerprint) {
+        return Ok(format!("{} 没有规则", fingerprint));
+    }
+    rules.save(paths.adapter_rules())?;
+    Ok(format!("已删除 {} 的规则", fingerprint))
+}
+
+fn list(paths: &Paths, json: bool) -> i32 {
+    let rules = match AdapterRules::load(paths.adapter_rules()) {
+        Ok(rules) => rules,
+        Err(e) => {
+            eprintln!("错误: 读取充电头规则失败: {}", e);
+            return EXIT_FAILURE;
+        }
+    };
+    let records = history::load(paths.session_history()).unwrap_or_default();
+
+    let mut seen: Vec<String> = Vec::new();
+    for summary in history::summarize(&records) {
+        let adapter = AdapterInfo {
+            real_type: summary.real_type,
+            svid: summary.svid,
+            apdo_max: summary.apdo_max,
+        };
+        if let Some(fingerprint) = adapters::fingerprint(&adapter)
+            && rules.get(&fingerprint).is_none()
+            && !seen.contains(&fingerprint)
+        {
+            seen.push(fingerprint);
+        }
+    }
+
+    let report = AdaptersReport {
+        current: current_fingerprint(paths),
+        rules: rules
+            .iter()
+            .map(|(fingerprint, rule)| RuleEntry {
+                fingerprint: fingerprint.clone(),
+                decision: rule.decision(),
+                rule: *rule,
+            })
+            .collect(),
+        seen,
+    };
+
+    if json {
+        match serde_json::to_string_pretty(&report) {
+            Ok(text) => println!("{}", text),
+            Err(e) => eprintln!("错误: 序列化充电头规则失败: {}", e),
+        }
+    } else {
+        print_report(&report);
+    }
+    EXIT_OK
+}
+
+fn print_report(report: &AdaptersReport) {
+    match &report.current {
+        Some(current) => {
+            let decision = report
+                .rules
+                .iter()
+                .find(|entry| &entry.fingerprint == current)
+                .map_or("无规则，按free模式", |entry| entry.decision.as_str());
+            println!("当前充电头: {}（{}）", current, decision);
+        }
+        None => println!("当前充电头: -"),
+    }
+
+    if report.rules.is_empty() {
+        println!("暂无充电头规则");
+    } else {
+        println!("充电头规则:");
+        for entry in &report.rules {
+            println!("  {}={}", entry.fingerprint, entry.decision);
+        }
+    }
+
+    if !report.seen.is_empty() {
+        println!("历史中出现过的充电头（无规则）:");
+        for fingerprint in &report.seen {
+            println!("  {}", fingerprint);
+        }
+    }
+}
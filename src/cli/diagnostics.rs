@@ -0,0 +1,425 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::Arc;
+use std::time::SystemTime;
+
+use anyhow::{Result, anyhow};
+use serde::Serialize;
+
+use crate::cli::doctor;
+use crate::cli::{EXIT_FAILURE, EXIT_OK};
+use crate::common::constants::{DIAGNOSTICS_OUTPUT_DIR, GETPROP_BIN_PATH, LOGCAT_BIN_PATH};
+use crate::common::{FreePPSError, Paths};
+use crate::config::ConfigStore;
+#[cfg(feature = "control-socket")]
+use crate::control::{protocol, send_command};
+use crate::monitoring::NetlinkUevents;
+use crate::session::{history, unix_secs};
+use crate::trace;
+
+// 附带的最近充电会话数
+const SESSION_LIMIT: usize = 20;
+// 附带的最近守护进程日志行数
+const LOG_LINES: usize = 2000;
+// 附带的最近遥测文件数
+#[cfg(feature = "telemetry")]
+const TELEMETRY_FILE_LIMIT: usize = 5;
+
+// 与充电相关的 getprop 前缀（机型、ROM 版本、SoC 平台）
+const PROP_PREFIXES: [&str; 9] = [
+    "ro.product.",
+    "ro.build.",
+    "ro.system.build.",
+    "ro.vendor.build.",
+    "ro.board.",
+    "ro.soc.",
+    "ro.hardware",
+    "ro.miui.",
+    "ro.mi.os.",
+];
+// 属性名包含以下片段时视为个人身份信息：取值不写入诊断包，并从全部文本中抹去
+const SENSITIVE_PROP_PATTERNS: [&str; 13] = [
+    "serial",
+    "imei",
+    "meid",
+    "iccid",
+    "imsi",
+    "msisdn",
+    "phone",
+    "account",
+    "mac_addr",
+    "macaddr",
+    "wifimac",
+    "bt_addr",
+    "bluetooth.address",
+];
+const REDACTED: &str = "<已隐去>";
+
+/// 诊断包说明（`manifest.json`）
+#[derive(Debug, Serialize)]
+struct Manifest {
+    version: String,
+    features: Vec<&'static str>,
+    arch: &'static str,
+    created_at: u64,
+    files: Vec<String>,
+    /// 采集失败的项目及原因
+    errors: Vec<String>,
+    /// 从文本中抹去的个人身份信息取值个数
+    redacted_values: usize,
+}
+
+/// 个人身份信息抹除：敏感属性的取值在全部文本中替换为占位符
+struct Redactor {
+    secrets: Vec<String>,
+}
+
+impl Redactor {
+    fn new(props: &[(String, String)]) -> Self {
+        let mut secrets: Vec<String> = props
+            .iter()
+            .filter(|(key, value)| is_sensitive(key) && value.len() >= 4)
+            .map(|(_, value)| value.clone())
+            .collect();
+        // 先替换长的，避免短取值是长取值的一部分时留下残片
+        secrets.sort_by_key(|secret| std::cmp::Reverse(secret.len()));
+        secrets.dedup();
+        Self { secrets }
+    }
+
+    fn redact(&self, text: &str) -> String {
+        self.secrets.iter().fold(text.to_string(), |text, secret| {
+            text.replace(secret, REDACTED)
+        })
+    }
+}
+
+fn is_sensitive(key: &str) -> bool {
+    let key = key.to_ascii_lowercase();
+    SENSITIVE_PROP_PATTERNS
+        .iter()
+        .any(|pattern| key.contains(pattern))
+}
+
+/// 解析 `getprop` 输出（每行 `[key]: [value]`）
+fn parse_props(output: &str) -> Vec<(String, String)> {
+    output
+        .lines()
+        .filter_map(|line| {
+            let (key, value) = line.trim().split_once("]: [")?;
+            Some((
+                key.strip_prefix('[')?.to_string(),
+                value.strip_suffix(']')?.to_string(),
+            ))
+        })
+        .collect()
+}
+
+/// 与充电相关的属性，敏感属性的取值替换为占位符
+fn relevant_props(props: &[(String, String)]) -> String {
+    props
+        .iter()
+        .filter(|(key, _)| PROP_PREFIXES.iter().any(|prefix| key.starts_with(prefix)))
+        .map(|(key, value)| {
+            let value = if is_sensitive(key) { REDACTED } else { value };
+            format!("{}={}\n", key, value)
+        })
+        .collect()
+}
+
+/// 执行系统命令并取标准输出
+fn command_output(program: &str, args: &[&str]) -> Result<String> {
+    let output = Command::new(program)
+        .args(args)
+        .output()
+        .map_err(|e| anyhow!("执行{}失败: {}", program, e))?;
+    if !output.status.success() {
+        return Err(anyhow!("{}退出码异常: {}", program, output.status));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// 充电相关 sysfs 节点快照：录制轨迹用的节点 + 各 power_supply 的 uevent 属性
+fn sysfs_snapshot(paths: &Paths) -> String {
+    let mut snapshot = String::from("# 节点\n");
+    for (name, value) in trace::capture_values(paths) {
+        snapshot.push_str(&format!("{}={}\n", name, value));
+    }
+
+    let power_supply = paths.battery_status().parent().and_then(Path::parent);
+    let mut supplies: Vec<PathBuf> = power_supply
+        .and_then(|dir| fs::read_dir(dir).ok())
+        .map(|entries| entries.flatten().map(|entry| entry.path()).collect())
+        .unwrap_or_default();
+    supplies.sort();
+    for supply in supplies {
+        if let Ok(uevent) = fs::read_to_string(supply.join("uevent")) {
+            let name = supply.file_name().unwrap_or_default().to_string_lossy();
+            snapshot.push_str(&format!("\n# power_supply/{}\n{}", name, uevent));
+        }
+    }
+    snapshot
+}
+
+/// 最近的遥测文件（按修改时间，新的在前）
+#[cfg(feature = "telemetry")]
+fn recent_telemetry_files(paths: &Paths) -> Vec<PathBuf> {
+    let Ok(entries) = fs::read_dir(paths.telemetry_dir()) else {
+        return Vec::new();
+    };
+    let mut files: Vec<(SystemTime, PathBuf)> = entries
+        .flatten()
+        .filter_map(|entry| {
+            let metadata = entry.metadata().ok()?;
+            metadata.is_file().then(|| {
+                (
+                    metadata.modified().unwrap_or(SystemTime::UNIX_EPOCH),
+                    entry.path(),
+                )
+            })
+        })
+        .collect();
+    files.sort();
+    files
+        .into_iter()
+        .rev()
+        .take(TELEMETRY_FILE_LIMIT)
+        .map(|(_, path)| path)
+        .collect()
+}
+
+/// 诊断包内容，全部文本在写入前经过 [`Redactor`]
+struct Bundle {
+    redactor: Redactor,
+    archive: TarArchive,
+    files: Vec<String>,
+    errors: Vec<String>,
+}
+
+impl Bundle {
+    fn add(&mut self, name: &str, content: &str) {
+        self.archive
+            .append(name, self.redactor.redact(content).as_bytes());
+        self.files.push(name.to_string());
+    }
+
+    fn add_result(&mut self, name: &str, content: Result<String>) {
+        match content {
+            Ok(content) => self.add(name, &content),
+            Err(e) => self.errors.push(format!("{}: {}", name, e)),
+        }
+    }
+}
+
+fn to_json<T: Serialize>(value: &T) -> Result<String> {
+    serde_json::to_string_pretty(value).map_err(|e| anyhow!("序列化失败: {}", e))
+}
+
+/// 采集诊断包并写入 `output`；返回抹去的个人身份信息取值个数
+///
+/// 诊断包内含 doctor 报告（只读采集：不触发 uevent，也不对解锁节点做写入测试）。
+pub fn collect(paths: &Arc<Paths>, output: &Path) -> Result<usize> {
+    let created_at = unix_secs(SystemTime::now());
+    let props = command_output(GETPROP_BIN_PATH, &[]).map(|output| parse_props(&output));
+    let mut bundle = Bundle {
+        redactor: Redactor::new(props.as_deref().unwrap_or_default()),
+        archive: TarArchive::new(&format!("freepps-diagnostics-{}", created_at), created_at),
+        files: Vec::new(),
+        errors: Vec::new(),
+    };
+
+    let report = doctor::collect(paths, &NetlinkUevents, doctor::UEVENT_WAIT, false);
+    bundle.add_result("doctor.json", to_json(&report));
+    #[cfg(feature = "control-socket")]
+    bundle.add_result(
+        "status.json",
+        send_command(paths.control_socket(), protocol::Command::Status)
+            .and_then(|reply| reply.ok_or_else(|| anyhow!("守护进程未运行")))
+            .and_then(|reply| to_json(&reply)),
+    );
+    bundle.add_result(
+        "logcat.txt",
+        command_output(
+            LOGCAT_BIN_PATH,
+            &[
+                "-d",
+                "-v",
+                "threadtime",
+                "-t",
+                &LOG_LINES.to_string(),
+                "-s",
+                "FreePPS",
+            ],
+        ),
+    );
+    bundle.add_result(
+        "sessions.jsonl",
+        history::load(paths.session_history()).and_then(|records| {
+            let start = records.len().saturating_sub(SESSION_LIMIT);
+            records[start..]
+                .iter()
+                .map(|record| {
+                    serde_json::to_string(record)
+                        .map(|line| line + "\n")
+                        .map_err(|e| anyhow!("序列化失败: {}", e))
+                })
+                .collect()
+        }),
+    );
+    #[cfg(feature = "telemetry")]
+    for path in recent_telemetry_files(paths) {
+        let name = format!(
+            "telemetry/{}",
+            path.file_name().unwrap_or_default().to_string_lossy()
+        );
+        bundle.add_result(
+            &name,
+            fs::read_to_string(&path).map_err(|e| FreePPSError::FileOperation(e).into()),
+        );
+    }
+    bundle.add_result("getprop.txt", props.map(|props| relevant_props(&props)));
+    bundle.add("sysfs.txt", &sysfs_snapshot(paths));
+    if paths.config_file().exists() {
+        bundle.add_result(
+            "freepps.conf",
+            fs::read_to_string(paths.config_file())
+                .map_err(|e| FreePPSError::FileOperation(e).into()),
+        );
+    }
+    bundle.add_result(
+        "config-effective.txt",
+        ConfigStore::load(paths).map(|config| format!("{:#?}\n", config.current())),
+    );
+
+    let manifest = Manifest {
+        version: env!("CARGO_PKG_VERSION").to_string(),
+        features: doctor::compiled_features(),
+        arch: std::env::consts::ARCH,
+        created_at,
+        files: bundle.files.clone(),
+        errors: bundle.errors.clone(),
+        redacted_values: bundle.redactor.secrets.len(),
+    };
+    bundle.add_result("manifest.json", to_json(&manifest));
+
+    fs::write(output, bundle.archive.finish()).map_err(FreePPSError::FileOperation)?;
+    Ok(manifest.redacted_values)
+}
+
+/// 执行 collect-diagnostics 子命令：未指定输出文件时写入 [`DIAGNOSTICS_OUTPUT_DIR`]（不存在时为模块目录）
+pub fn run(paths: Arc<Paths>, output: Option<&str>) -> i32 {
+    let output = match output {
+        Some(output) => PathBuf::from(output),
+        None => {
+            let dir = Path::new(DIAGNOSTICS_OUTPUT_DIR);
+            let dir = if dir.is_dir() {
+                dir
+            } else {
+                paths.module_dir()
+            };
+            dir.join(format!(
+                "freepps-diagnostics-{}.tar",
+                unix_secs(SystemTime::now())
+            ))
+        }
+    };
+
+    match collect(&paths, &output) {
+        Ok(redacted) => {
+            println!("已生成诊断包: {}", output.display());
+            if redacted > 0 {
+                println!("已隐去{}项个人身份信息", redacted);
+            }
+            EXIT_OK
+        }
+        Err(e) => {
+            eprintln!("错误: 生成诊断包失败: {}", e);
+            EXIT_FAILURE
+        }
+    }
+}
+
+/// 内存中的 ustar 归档，所有文件放在同一个顶层目录下
+struct TarArchive {
+    data: Vec<u8>,
+    dir: String,
+    mtime: u64,
+}
+
+impl TarArchive {
+    const BLOCK: usize = 512;
+
+    fn new(dir: &str, mtime: u64) -> Self {
+        Self {
+            data: Vec::new(),
+            dir: dir.to_string(),
+            mtime,
+        }
+    }
+
+    fn append(&mut self, name: &str, content: &[u8]) {
+        let path = format!("{}/{}", self.dir, name);
+        let mut header = [0u8; Self::BLOCK];
+        // ustar 的 name 字段为 100 字节，诊断包内的文件名都远短于此
+        let path = &path.as_bytes()[..path.len().min(100)];
+        header[..path.len()].copy_from_slice(path);
+        Self::octal(&mut header[100..108], 0o644);
+        Self::octal(&mut header[108..116], 0);
+        Self::octal(&mut header[116..124], 0);
+        Self::octal(&mut header[124..136], content.len() as u64);
+        Self::octal(&mut header[136..148], self.mtime);
+        header[156] = b'0';
+        header[257..263].copy_from_slice(b"ustar\0");
+        header[263..265].copy_from_slice(b"00");
+
+        // 校验和按校验和字段为 8 个空格计算
+        header[148..156].fill(b' ');
+        let checksum: u64 = header.iter().map(|&byte| u64::from(byte)).sum();
+        Self::octal(&mut header[148..155], checksum);
+
+        self.data.extend_from_slice(&header);
+        self.data.extend_from_slice(content);
+        let padding = (Self::BLOCK - content.len() % Self::BLOCK) % Self::BLOCK;
+        self.data.extend(std::iter::repeat_n(0u8, padding));
+    }
+
+    /// 以 NUL 结尾的定长八进制字段
+    fn octal(field: &mut [u8], value: u64) {
+        let width = field.len() - 1;
+        let digits = format!("{:0width$o}", value, width = width);
+        field[..width].copy_from_slice(&digits.as_bytes()[..width]);
+        field[width] = 0;
+    }
+
+    /// 追加两个全零块作为归档结尾
+    fn finish(mut self) -> Vec<u8> {
+        self.data.extend(std::iter::repeat_n(0u8, Self::BLOCK * 2));
+        self.data
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sensitive_props_are_redacted_everywhere() {
+        let props = parse_props(
+            "[ro.product.model]: [23127PN0CC]\n\
+             [ro.build.version.incremental]: [OS2.0.6.0.VNCCNXM]\n\
+             [ro.serialno]: [8a7f3c21]\n\
+             [ro.build.serial_hint]: [8a7f3c21]\n\
+             [persist.radio.imei]: [861234567890123]\n",
+        );
+        let redactor = Redactor::new(&props);
+
+        let relevant = relevant_props(&props);
+        assert!(relevant.contains("ro.product.model=23127PN0CC"));
+        assert!(relevant.contains("ro.build.serial_hint=<已隐去>"));
+        assert!(!relevant.contains("persist.radio.imei"));
+
+        let log = redactor.redact("adb device 8a7f3c21 imei=861234567890123 ok");
+        assert_eq!(log, "adb device <已隐去> imei=<已隐去> ok");
+    }
+}
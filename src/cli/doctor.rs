@@ -0,0 +1,624 @@
+use std::fs;
+use std::os::raw::c_int;
+use std::path::Path;
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use serde::Serialize;
+
+use crate::cli::{EXIT_FAILURE, EXIT_OK};
+use crate::common::Paths;
+use crate::common::constants::{AM_BIN_PATH, THERMAL_CLASS_DIR};
+use crate::config::{Config, ConfigStore};
+#[cfg(feature = "control-socket")]
+use crate::control::protocol::Command;
+#[cfg(feature = "control-socket")]
+use crate::control::send_command;
+use crate::monitoring::uevent::recv_uevent;
+use crate::monitoring::{FileMonitor, NetlinkUevents, UeventSource};
+use crate::pd::{self, ChargerBackend};
+
+/// 等待 uevent 到达的最长时间
+pub const UEVENT_WAIT: Duration = Duration::from_secs(3);
+// 写入测试后等待驱动处理再回读
+const READBACK_DELAY: Duration = Duration::from_millis(20);
+
+/// 单个节点的检测结果
+#[derive(Debug, Serialize)]
+pub struct NodeCheck {
+    pub name: String,
+    pub path: String,
+    pub exists: bool,
+    pub readable: bool,
+    pub writable: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub value: Option<String>,
+    /// 写入测试结果（仅解锁节点，且当前值可识别时执行）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub write_test: Option<WriteTest>,
+    /// 解锁节点未做写入测试的原因
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub write_test_skipped: Option<String>,
+}
+
+impl NodeCheck {
+    fn probe(name: &str, path: &Path) -> Self {
+        let exists = path.exists();
+        let value = fs::read_to_string(path).ok().map(|v| v.trim().to_string());
+        // 以写方式打开但不写入：sysfs 上没有 store 方法的属性会直接拒绝打开
+        let writable = exists && fs::OpenOptions::new().write(true).open(path).is_ok();
+        Self {
+            name: name.to_string(),
+            path: path.display().to_string(),
+            exists,
+            readable: value.is_some(),
+            writable,
+            value,
+            write_test: None,
+            write_test_skipped: None,
+        }
+    }
+
+    /// 解锁节点：探测后再做一次写入测试；`skip` 为不能写入的原因时跳过测试
+    fn probe_unlock_node(backend: &dyn ChargerBackend, skip: Option<&str>) -> Self {
+        let mut check = Self::probe(backend.name(), backend.node_path());
+        if !check.writable {
+            return check;
+        }
+        match (
+            skip,
+            check.value.as_deref().filter(|v| matches!(*v, "0" | "1")),
+        ) {
+            (Some(reason), _) => check.write_test_skipped = Some(reason.to_string()),
+            (None, Some(original)) => {
+                check.write_test = Some(WriteTest::run(backend.node_path(), original));
+            }
+            (None, None) => {}
+        }
+        check
+    }
+}
+
+/// 解锁节点的写入测试
+///
+/// 写入相反的值再恢复原值，确认驱动是否接受两种取值。切换会影响正在进行的充电协商，
+/// 也会与守护进程的写入冲突，因此只在未连接充电器（`usb/online` 为 0）且守护进程未运行时执行。
+#[derive(Debug, Serialize)]
+pub struct WriteTest {
+    /// 测试写入的值
+    pub value: String,
+    /// 写入后回读是否一致（不一致说明驱动拒绝或立即改回）
+    pub applied: bool,
+    /// 测试结束后节点是否为原值
+    pub restored: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl WriteTest {
+    fn run(path: &Path, original: &str) -> Self {
+        let value = if original == "1" { "0" } else { "1" };
+        let mut test = Self {
+            value: value.to_string(),
+            applied: false,
+            restored: false,
+            error: None,
+        };
+
+        let readback = |path: &Path| {
+            thread::sleep(READBACK_DELAY);
+            FileMonitor::read_file_content(path).unwrap_or_default()
+        };
+        match FileMonitor::write_file_content(path, value) {
+            Ok(()) => test.applied = readback(path) == value,
+            Err(e) => test.error = Some(e.to_string()),
+        }
+
+        let mut current = readback(path);
+        if current != original {
+            if let Err(e) = FileMonitor::write_file_content(path, original) {
+                test.error.get_or_insert_with(|| e.to_string());
+            }
+            current = readback(path);
+        }
+        test.restored = current == original;
+        test
+    }
+}
+
+/// uevent 接收检测
+#[derive(Debug, Default, Serialize)]
+pub struct UeventCheck {
+    /// 是否成功订阅 netlink uevent
+    pub subscribed: bool,
+    /// 是否已通过 battery/uevent 主动触发一条事件
+    pub triggered: bool,
+    /// 等待期间收到的 uevent 数
+    pub received: u32,
+    /// 其中 power_supply 子系统的 uevent 数
+    pub power_supply: u32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl UeventCheck {
+    /// 订阅 uevent，等到收到 power_supply 事件或超时
+    ///
+    /// `trigger` 为 `true` 时先向 battery/uevent 写入 "change" 主动触发一条事件；
+    /// 为 `false` 时只被动等待真实事件（运行中的守护进程也会收到主动触发的事件）。
+    fn probe(paths: &Paths, uevents: &dyn UeventSource, wait: Duration, trigger: bool) -> Self {
+        let mut check = Self::default();
+        let sock = match uevents.open() {
+            Ok(sock) => sock,
+            Err(e) => {
+                check.error = Some(e.to_string());
+                return check;
+            }
+        };
+        check.subscribed = true;
+        check.triggered = trigger
+            && paths.battery_uevent().exists()
+            && FileMonitor::write_file_content(paths.battery_uevent(), "change").is_ok();
+
+        let deadline = Instant::now() + wait;
+        while check.power_supply == 0 {
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            if remaining.is_zero() || !wait_readable(sock, remaining) {
+                break;
+            }
+            loop {
+                match recv_uevent(sock) {
+                    Ok(Some(uevent)) => {
+                        check.received += 1;
+                        if uevent.subsystem() == Some("power_supply") {
+                            check.power_supply += 1;
+                        }
+                    }
+                    Ok(None) => break,
+                    Err(e) => {
+                        check.error = Some(e.to_string());
+                        break;
+                    }
+                }
+            }
+        }
+
+        unsafe {
+            libc::close(sock);
+        }
+        check
+    }
+}
+
+/// 等待 fd 可读，超时返回 false
+fn wait_readable(fd: c_int, timeout: Duration) -> bool {
+    let mut pollfd = libc::pollfd {
+        fd,
+        events: libc::POLLIN,
+        revents: 0,
+    };
+    let result = unsafe { libc::poll(&mut pollfd, 1, timeout.as_millis() as c_int) };
+    result > 0 && pollfd.revents & libc::POLLIN != 0
+}
+
+/// 单个后端的能力
+#[derive(Debug, Serialize)]
+pub struct BackendCapability {
+    pub name: String,
+    pub detected: bool,
+    pub writable: bool,
+    /// 写入测试是否生效（未执行写入测试时为空）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub verified: Option<bool>,
+    /// 守护进程启动后是否会启用该后端
+    pub active: bool,
+}
+
+/// 金标动画广播伪造的能力
+#[derive(Debug, Serialize)]
+pub struct ForgerCapability {
+    /// 编译时是否启用 broadcast-forger feature
+    pub compiled: bool,
+    /// freepps.conf 中 `forge` 开关
+    pub enabled: bool,
+    pub am_available: bool,
+    /// 门控所需节点（real_type / apdo_max / adapter_svid / usb voltage_now）是否全部可读
+    pub nodes_readable: bool,
+    /// 守护进程是否会伪造（还需要 qcom 后端可用）
+    pub active: bool,
+}
+
+/// 机器可读的能力摘要
+#[derive(Debug, Serialize)]
+pub struct Capabilities {
+    pub features: Vec<&'static str>,
+    pub backends: Vec<BackendCapability>,
+    pub active_backends: Vec<String>,
+    pub forger: ForgerCapability,
+    pub uevents: bool,
+}
+
+/// 兼容性检测报告
+#[derive(Debug, Serialize)]
+pub struct DoctorReport {
+    pub version: String,
+    pub module_dir: String,
+    pub sysfs_root: String,
+    pub config_file: String,
+    pub daemon_running: bool,
+    pub nodes: Vec<NodeCheck>,
+    pub thermal_zones: usize,
+    pub uevent: UeventCheck,
+    pub am_available: bool,
+    pub capabilities: Capabilities,
+}
+
+/// 编译时启用的 cargo feature
+pub fn compiled_features() -> Vec<&'static str> {
+    [
+        ("qcom", cfg!(feature = "qcom")),
+        ("mtk", cfg!(feature = "mtk")),
+        ("broadcast-forger", cfg!(feature = "broadcast-forger")),
+        ("telemetry", cfg!(feature = "telemetry")),
+        ("control-socket", cfg!(feature = "control-socket")),
+    ]
+    .into_iter()
+    .filter_map(|(name, enabled)| enabled.then_some(name))
+    .collect()
+}
+
+/// 采集兼容性检测报告
+///
+/// `write_test` 为 `true` 时经 battery/uevent 主动触发一条 uevent，且未连接充电器、守护进程未运行时
+/// 解锁节点会做一次写入测试并恢复原值；为 `false` 时只读采集，不写任何节点（诊断打包使用）。
+/// uevent 检测最多等待 `uevent_wait`。
+pub fn collect(
+    paths: &Arc<Paths>,
+    uevents: &dyn UeventSource,
+    uevent_wait: Duration,
+    write_test: bool,
+) -> DoctorReport {
+    let config = ConfigStore::load(paths).map(Arc::new);
+    let current = config
+        .as_ref()
+        .map(|config| config.current())
+        .unwrap_or_default();
+    let backends = config
+        .and_then(|config| pd::charger_backends(paths, &config))
+        .unwrap_or_default();
+
+    let daemon_running = daemon_running(paths);
+    let skip_write_test = if !write_test {
+        Some("只读采集，不做写入测试")
+    } else if daemon_running {
+        Some("守护进程运行中，避免与其写入冲突")
+    } else {
+        match FileMonitor::read_file_content(paths.usb_online()).as_deref() {
+            Ok("0") => None,
+            Ok(_) => Some("充电器已连接（usb/online不为0），避免影响充电协商"),
+            Err(_) => Some("无法读取usb/online，不能确认未连接充电器"),
+        }
+    };
+    let mut nodes: Vec<NodeCheck> = backends
+        .iter()
+        .map(|backend| NodeCheck::probe_unlock_node(backend.as_ref(), skip_write_test))
+        .collect();
+    nodes.extend([
+        NodeCheck::probe("free", paths.free_file()),
+        NodeCheck::probe("battery_status", paths.battery_status()),
+        NodeCheck::probe("battery_uevent", paths.battery_uevent()),
+        NodeCheck::probe("usb_online", paths.usb_online()),
+        NodeCheck::probe("real_type", paths.real_type()),
+        NodeCheck::probe("apdo_max", paths.apdo_max()),
+        NodeCheck::probe("adapter_svid", paths.adapter_svid()),
+        NodeCheck::probe("usb_voltage_now", paths.usb_voltage_now()),
+        NodeCheck::probe("usb_current_now", paths.usb_current_now()),
+        NodeCheck::probe("battery_current_now", paths.battery_current_now()),
+        NodeCheck::probe("battery_temp", paths.battery_temp()),
+        NodeCheck::probe("battery_capacity", paths.battery_capacity()),
+    ]);
+    for zone in &current.thermal.zones {
+        nodes.push(NodeCheck::probe(zone, &paths.thermal_zone_temp(zone)));
+    }
+
+    let uevent = UeventCheck::probe(paths, uevents, uevent_wait, write_test);
+    let am_available = Path::new(AM_BIN_PATH).exists();
+    let capabilities = capabilities(&current, &backends, &nodes, &uevent, am_available);
+
+    DoctorReport {
+        version: env!("CARGO_PKG_VERSION").to_string(),
+        module_dir: paths.module_dir().display().to_string(),
+        sysfs_root: paths.sysfs_root().display().to_string(),
+        config_file: paths.config_file().display().to_string(),
+        daemon_running,
+        nodes,
+        thermal_zones: count_thermal_zones(paths),
+        uevent,
+        am_available,
+        capabilities,
+    }
+}
+
+fn capabilities(
+    config: &Config,
+    backends: &[Arc<dyn ChargerBackend>],
+    nodes: &[NodeCheck],
+    uevent: &UeventCheck,
+    am_available: bool,
+) -> Capabilities {
+    let node = |name: &str| nodes.iter().find(|node| node.name == name);
+
+    let backends: Vec<BackendCapability> = backends
+        .iter()
+        .map(|backend| {
+            let check = node(backend.name());
+            let detected = backend.detect();
+            let writable = check.is_some_and(|check| check.writable);
+            BackendCapability {
+                name: backend.name().to_string(),
+                detected,
+                writable,
+                verified: check
+                    .and_then(|check| check.write_test.as_ref())
+                    .map(|test| test.applied),
+                active: detected && writable,
+            }
+        })
+        .collect();
+    let active_backends: Vec<String> = backends
+        .iter()
+        .filter(|backend| backend.active)
+        .map(|backend| backend.name.clone())
+        .collect();
+
+    #[cfg(feature = "broadcast-forger")]
+    let enabled = config.forger.enabled;
+    #[cfg(not(feature = "broadcast-forger"))]
+    let enabled = {
+        let _ = config;
+        false
+    };
+    let compiled = cfg!(feature = "broadcast-forger");
+    let nodes_readable = ["real_type", "apdo_max", "adapter_svid", "usb_voltage_now"]
+        .into_iter()
+        .all(|name| node(name).is_some_and(|node| node.readable));
+    let active = compiled
+        && enabled
+        && am_available
+        && nodes_readable
+        && active_backends.iter().any(|name| name == "qcom");
+
+    Capabilities {
+        features: compiled_features(),
+        backends,
+        active_backends,
+        forger: ForgerCapability {
+            compiled,
+            enabled,
+            am_available,
+            nodes_readable,
+            active,
+        },
+        uevents: uevent.received > 0,
+    }
+}
+
+/// 统计 thermal zone 数量（thermal_guard 的 `thermal_zones` 可从中选择）
+fn count_thermal_zones(paths: &Paths) -> usize {
+    fs::read_dir(paths.sysfs_root().join(THERMAL_CLASS_DIR))
+        .map(|entries| {
+            entries
+                .flatten()
+                .filter(|entry| {
+                    entry
+                        .file_name()
+                        .to_string_lossy()
+                        .starts_with("thermal_zone")
+                })
+                .count()
+        })
+        .unwrap_or(0)
+}
+
+/// 守护进程是否在运行（经控制 socket 探测；未编译控制 socket 时无法探测，视为未运行）
+#[cfg(feature = "control-socket")]
+fn daemon_running(paths: &Paths) -> bool {
+    matches!(
+        send_command(paths.control_socket(), Command::Status),
+        Ok(Some(_))
+    )
+}
+
+#[cfg(not(feature = "control-socket"))]
+fn daemon_running(_paths: &Paths) -> bool {
+    false
+}
+
+/// 执行 doctor 子命令：有可用后端时退出码为 0
+pub fn run(paths: Arc<Paths>, json: bool) -> i32 {
+    let report = collect(&paths, &NetlinkUevents, UEVENT_WAIT, true);
+
+    if json {
+        match serde_json::to_string_pretty(&report) {
+            Ok(text) => println!("{}", text),
+            Err(e) => eprintln!("错误: 序列化检测报告失败: {}", e),
+        }
+    } else {
+        print_report(&report);
+    }
+
+    if report.capabilities.active_backends.is_empty() {
+        EXIT_FAILURE
+    } else {
+        EXIT_OK
+    }
+}
+
+fn mark(ok: bool) -> &'static str {
+    if ok { "✓" } else { "✗" }
+}
+
+fn print_report(report: &DoctorReport) {
+    println!("FreePPS v{} 兼容性检测", report.version);
+    println!("模块目录: {}", report.module_dir);
+    println!("sysfs根目录: {}", report.sysfs_root);
+    println!("配置文件: {}", report.config_file);
+    println!(
+        "守护进程: {}",
+        if report.daemon_running {
+            "运行中"
+        } else {
+            "未运行"
+        }
+    );
+    println!();
+    for node in &report.nodes {
+        if !node.exists {
+            println!("✗ {}: 不存在 ({})", node.name, node.path);
+            continue;
+        }
+        println!(
+            "✓ {}: {}{} 值={} ({})",
+            node.name,
+            if node.readable { "可读" } else { "不可读" },
+            if node.writable { "可写" } else { "只读" },
+            node.value.as_deref().unwrap_or("-"),
+            node.path
+        );
+        if let Some(test) = &node.write_test {
+            let result = match (test.applied, &test.error) {
+                (_, Some(error)) => format!("写入失败: {}", error),
+                (true, None) => "生效".to_string(),
+                (false, None) => "未生效（被驱动拒绝或改回）".to_string(),
+            };
+            println!(
+                "  写入测试: 写入{} {}，{}",
+                test.value,
+                result,
+                if test.restored {
+                    "已恢复原值"
+                } else {
+                    "⚠️未能恢复原值"
+                }
+            );
+        }
+        if let Some(reason) = &node.write_test_skipped {
+            println!("  写入测试: 已跳过（{}）", reason);
+        }
+    }
+    println!("  thermal zone: {}个", report.thermal_zones);
+
+    let uevent = &report.uevent;
+    match &uevent.error {
+        Some(error) if !uevent.subscribed => println!("✗ uevent: 订阅失败: {}", error),
+        _ => println!(
+            "{} uevent: {}，收到{}条（power_supply {}条）",
+            mark(uevent.received > 0),
+            if uevent.triggered {
+                "已主动触发"
+            } else {
+                "未主动触发，被动等待"
+            },
+            uevent.received,
+            uevent.power_supply
+        ),
+    }
+    println!("{} am: {}", mark(report.am_available), AM_BIN_PATH);
+
+    let capabilities = &report.capabilities;
+    let forger = &capabilities.forger;
+    println!();
+    if capabilities.active_backends.is_empty() {
+        println!("结论: 未检测到可用的解锁节点，当前设备不受支持");
+    } else {
+        println!(
+            "结论: 支持，可用后端: {}",
+            capabilities.active_backends.join(", ")
+        );
+    }
+    let forger_state = if forger.active {
+        "可用"
+    } else if !forger.compiled {
+        "未编译"
+    } else if !forger.enabled {
+        "已在配置中关闭"
+    } else if !forger.am_available {
+        "缺少am命令"
+    } else if !forger.nodes_readable {
+        "缺少门控节点"
+    } else {
+        "需要qcom后端"
+    };
+    println!("金标动画伪造: {}", forger_state);
+    if !capabilities.uevents {
+        println!("⚠️未收到uevent：插拔充电器时守护进程可能无法及时响应");
+    }
+
+    // 末行为单行 JSON 能力摘要，便于脚本与反馈模板直接截取
+    match serde_json::to_string(capabilities) {
+        Ok(text) => println!("\n能力摘要: {}", text),
+        Err(e) => eprintln!("错误: 序列化能力摘要失败: {}", e),
+    }
+}
+
+// 测试使用高通节点
+#[cfg(all(test, feature = "qcom"))]
+mod tests {
+    use super::*;
+    use crate::testing::{FakeSysfs, UeventInjector};
+
+    #[test]
+    fn write_test_restores_unlock_node() {
+        let sysfs = FakeSysfs::qcom();
+        sysfs.write(sysfs.paths().battery_uevent(), "");
+        sysfs.write(sysfs.paths().usb_online(), "0");
+        let report = collect(sysfs.paths(), &*UeventInjector::new(), Duration::ZERO, true);
+
+        let qcom = report
+            .nodes
+            .iter()
+            .find(|node| node.name == "qcom")
+            .unwrap();
+        let test = qcom.write_test.as_ref().unwrap();
+        assert_eq!(test.value, "0");
+        assert!(test.applied && test.restored);
+        assert_eq!(sysfs.read(sysfs.paths().pd_verified()), "1");
+
+        assert!(report.uevent.subscribed && report.uevent.triggered);
+        assert_eq!(report.capabilities.active_backends, ["qcom"]);
+        assert!(!report.capabilities.forger.active);
+
+        // 已连接充电器：不切换解锁节点
+        sysfs.write(sysfs.paths().usb_online(), "1");
+        let report = collect(sysfs.paths(), &*UeventInjector::new(), Duration::ZERO, true);
+        let qcom = report
+            .nodes
+            .iter()
+            .find(|node| node.name == "qcom")
+            .unwrap();
+        assert!(qcom.write_test.is_none() && qcom.write_test_skipped.is_some());
+    }
+
+    #[test]
+    fn read_only_collect_writes_no_node() {
+        let sysfs = FakeSysfs::qcom();
+        sysfs.write(sysfs.paths().battery_uevent(), "");
+        sysfs.write(sysfs.paths().usb_online(), "0");
+        let report = collect(
+            sysfs.paths(),
+            &*UeventInjector::new(),
+            Duration::ZERO,
+            false,
+        );
+
+        assert_eq!(sysfs.read(sysfs.paths().battery_uevent()), "");
+        assert!(report.uevent.subscribed && !report.uevent.triggered);
+        let qcom = report
+            .nodes
+            .iter()
+            .find(|node| node.name == "qcom")
+            .unwrap();
+        assert!(qcom.write_test.is_none() && qcom.write_test_skipped.is_some());
+    }
+}
@@ -0,0 +1,113 @@
+use serde::Serialize;
+
+use crate::cli::{EXIT_FAILURE, EXIT_OK};
+use crate::common::{Paths, utils};
+use crate::session::history::{self, AdapterSummary, SessionRecord};
+
+/// 未指定条数时列出的最近会话数
+pub const DEFAULT_LIMIT: usize = 10;
+
+/// `history --json` 的输出
+#[derive(Debug, Serialize)]
+struct HistoryReport {
+    /// 历史文件中的会话总数
+    total: usize,
+    /// 最近的会话（新的在前）
+    sessions: Vec<SessionRecord>,
+    /// 按充电头汇总（全部历史）
+    adapters: Vec<AdapterSummary>,
+}
+
+/// 列出最近的充电会话与各充电头的汇总，返回退出码
+pub fn run(paths: &Paths, limit: usize, json: bool) -> i32 {
+    let records = match history::load(paths.session_history()) {
+        Ok(records) => records,
+        Err(e) => {
+            eprintln!("错误: 读取会话历史失败: {}", e);
+            return EXIT_FAILURE;
+        }
+    };
+    let report = HistoryReport {
+        total: records.len(),
+        sessions: records.iter().rev().take(limit).cloned().collect(),
+        adapters: history::summarize(&records),
+    };
+
+    if json {
+        match serde_json::to_string_pretty(&report) {
+            Ok(text) => println!("{}", text),
+            Err(e) => eprintln!("错误: 序列化会话历史失败: {}", e),
+        }
+    } else {
+        print_report(&report);
+    }
+    EXIT_OK
+}
+
+fn print_report(report: &HistoryReport) {
+    if report.total == 0 {
+        println!("暂无充电会话记录");
+        return;
+    }
+
+    println!(
+        "最近{}次充电会话（共{}次）:",
+        report.sessions.len(),
+        report.total
+    );
+    for record in &report.sessions {
+        let soc = match (record.soc_start, record.soc_end, record.soc_gained()) {
+            (Some(start), Some(end), Some(gained)) => {
+                format!("{}%→{}%（{:+}）", start, end, gained)
+            }
+            _ => "-".to_string(),
+        };
+        println!(
+            "{} {}分钟 {} svid={} apdo_max={} 峰值{}W 平均{}W {}Wh 电量{} 解锁{} 广播{}{}",
+            utils::format_local_time(record.started_at),
+            record.duration_secs / 60,
+            record.adapter.real_type.as_deref().unwrap_or("-"),
+            record.adapter.svid.as_deref().unwrap_or("-"),
+            watts(record.adapter.apdo_max),
+            record.peak_w,
+            record.avg_w,
+            record.energy_wh,
+            soc,
+            mark(record.unlock_applied),
+            mark(record.broadcast_forged),
+            if record.ended_at.is_none() {
+                "（守护进程退出时未结束）"
+            } else {
+                ""
+            }
+        );
+    }
+
+    println!();
+    println!("按充电头汇总:");
+    for adapter in &report.adapters {
+        println!(
+            "svid={} apdo_max={} {}: {}次（PPS {}次，解锁 {}次，广播 {}次） 峰值{}W 平均{}W 累计{}Wh",
+            adapter.svid.as_deref().unwrap_or("-"),
+            watts(adapter.apdo_max),
+            adapter.real_type.as_deref().unwrap_or("-"),
+            adapter.sessions,
+            adapter.pps_sessions,
+            adapter.unlock_applied,
+            adapter.broadcast_forged,
+            adapter.peak_w,
+            adapter.avg_w,
+            adapter.energy_wh
+        );
+    }
+}
+
+fn watts(value: Option<u32>) -> String {
+    value
+        .map(|w| format!("{}W", w))
+        .unwrap_or_else(|| "-".to_string())
+}
+
+fn mark(value: bool) -> &'static str {
+    if value { "✓" } else { "✗" }
+}
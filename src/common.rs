@@ -1,6 +1,9 @@
 pub mod constants;
 pub mod error;
+pub mod free_mode;
+pub mod paths;
 pub mod utils;
 
-pub use constants::*;
 pub use error::FreePPSError;
+pub use free_mode::FreeMode;
+pub use paths::{PathOverrides, Paths};
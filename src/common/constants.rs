@@ -1,30 +1,75 @@
 // inotify 相关常量
 #[cfg(unix)]
-pub const IN_MODIFY: u32 = 0x00000002;
-#[cfg(unix)]
 pub const IN_CLOSE_WRITE: u32 = 0x00000008;
 #[cfg(unix)]
-pub const IN_CREATE: u32 = 0x00000100;
+pub const IN_MOVED_FROM: u32 = 0x00000040;
 #[cfg(unix)]
-pub const IN_DELETE: u32 = 0x00000200;
-
-// 文件路径常量
+pub const IN_MOVED_TO: u32 = 0x00000080;
 #[cfg(unix)]
-pub const MODULE_BASE_PATH: &str = "/data/adb/modules/FreePPS";
-pub const FREE_FILE: &str = "/data/adb/modules/FreePPS/free";
-pub const DISABLE_FILE: &str = "/data/adb/modules/FreePPS/disable";
+pub const IN_CREATE: u32 = 0x00000100;
 #[cfg(unix)]
-pub const MODULE_PROP: &str = "/data/adb/modules/FreePPS/module.prop";
-pub const PD_VERIFIED_PATH: &str = "/sys/class/qcom-battery/pd_verifed";
-pub const PD_ADAPTER_VERIFIED_PATH: &str = "/sys/class/Charging_Adapter/pd_adapter/usbpd_verifed";
-pub const BATTERY_STATUS_PATH: &str = "/sys/class/power_supply/battery/status";
-
-// 金标动画广播伪造相关 sysfs 节点
+pub const IN_DELETE: u32 = 0x00000200;
 #[cfg(unix)]
-pub const REAL_TYPE_PATH: &str = "/sys/class/xm_power/charger/charger_common/real_type";
+pub const IN_DELETE_SELF: u32 = 0x00000400;
 #[cfg(unix)]
-pub const APDO_MAX_PATH: &str = "/sys/class/xm_power/typec/apdo_max";
+pub const IN_MOVE_SELF: u32 = 0x00000800;
 #[cfg(unix)]
-pub const ADAPTER_SVID_PATH: &str = "/sys/class/xm_power/typec/strategy_pd_auth/adapter_svid";
+pub const IN_Q_OVERFLOW: u32 = 0x00004000;
 #[cfg(unix)]
-pub const USB_VOLTAGE_NOW_PATH: &str = "/sys/class/power_supply/usb/voltage_now";
+pub const IN_IGNORED: u32 = 0x00008000;
+
+// 默认根路径（可由配置文件 / 环境变量 / 命令行参数覆盖，见 common::paths）
+pub const DEFAULT_MODULE_BASE_PATH: &str = "/data/adb/modules/FreePPS";
+pub const DEFAULT_SYSFS_ROOT: &str = "/sys";
+
+// 路径覆盖用的环境变量
+pub const ENV_MODULE_DIR: &str = "FREEPPS_MODULE_DIR";
+pub const ENV_SYSFS_ROOT: &str = "FREEPPS_SYSFS_ROOT";
+pub const ENV_CONFIG_FILE: &str = "FREEPPS_CONFIG";
+
+// 模块目录下的文件名
+pub const FREE_FILE_NAME: &str = "free";
+// 最近一次启用时的模式（1 或 auto），暂停后重新启用时写回 free 文件
+pub const FREE_RESUME_FILE_NAME: &str = "free_resume";
+pub const DISABLE_FILE_NAME: &str = "disable";
+pub const MODULE_PROP_NAME: &str = "module.prop";
+pub const CONFIG_FILE_NAME: &str = "freepps.conf";
+#[cfg(feature = "control-socket")]
+pub const CONTROL_SOCKET_NAME: &str = "freepps.sock";
+#[cfg(feature = "telemetry")]
+pub const TELEMETRY_DIR_NAME: &str = "telemetry";
+pub const SESSION_HISTORY_NAME: &str = "sessions.jsonl";
+pub const ADAPTER_RULES_NAME: &str = "adapters.conf";
+
+// sysfs 节点（相对 sysfs 根目录）
+pub const PD_VERIFIED_NODE: &str = "class/qcom-battery/pd_verifed";
+pub const PD_ADAPTER_VERIFIED_NODE: &str = "class/Charging_Adapter/pd_adapter/usbpd_verifed";
+pub const BATTERY_STATUS_NODE: &str = "class/power_supply/battery/status";
+// 写入 "change" 时内核为 battery 重新发送一条 uevent（doctor 用于检测 uevent 能否收到）
+pub const BATTERY_UEVENT_NODE: &str = "class/power_supply/battery/uevent";
+
+// 金标动画广播伪造相关 sysfs 节点（相对 sysfs 根目录）
+pub const REAL_TYPE_NODE: &str = "class/xm_power/charger/charger_common/real_type";
+pub const APDO_MAX_NODE: &str = "class/xm_power/typec/apdo_max";
+pub const ADAPTER_SVID_NODE: &str = "class/xm_power/typec/strategy_pd_auth/adapter_svid";
+pub const USB_VOLTAGE_NOW_NODE: &str = "class/power_supply/usb/voltage_now";
+// 充电器是否连接（doctor 仅在为 0 时做解锁节点写入测试）
+pub const USB_ONLINE_NODE: &str = "class/power_supply/usb/online";
+
+// 充电遥测采样用的 sysfs 节点（相对 sysfs 根目录）
+pub const USB_CURRENT_NOW_NODE: &str = "class/power_supply/usb/current_now";
+pub const BATTERY_CURRENT_NOW_NODE: &str = "class/power_supply/battery/current_now";
+pub const BATTERY_TEMP_NODE: &str = "class/power_supply/battery/temp";
+pub const BATTERY_CAPACITY_NODE: &str = "class/power_supply/battery/capacity";
+
+// 温控保护可选的 thermal zone 目录（相对 sysfs 根目录）
+pub const THERMAL_CLASS_DIR: &str = "class/thermal";
+
+// 发送伪造广播使用的 am 命令
+pub const AM_BIN_PATH: &str = "/system/bin/am";
+
+// 诊断包采集使用的系统命令
+pub const LOGCAT_BIN_PATH: &str = "/system/bin/logcat";
+pub const GETPROP_BIN_PATH: &str = "/system/bin/getprop";
+// 诊断包默认输出目录（用户可直接在文件管理器中找到）
+pub const DIAGNOSTICS_OUTPUT_DIR: &str = "/sdcard/Download";
@@ -8,4 +8,6 @@ pub enum FreePPSError {
     #[cfg(unix)]
     #[error("inotify监控失败: {0}")]
     InotifyError(String),
+    #[error("参数错误: {0}")]
+    InvalidArgument(String),
 }
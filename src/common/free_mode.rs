@@ -0,0 +1,40 @@
+use serde::{Deserialize, Serialize};
+
+/// free 文件表示的运行模式
+///
+/// - `1`：锁定PPS支持，始终解锁
+/// - `auto`：按充电头自动选择，公版 PPS 充电头解锁，小米充电头走原生 MIPPS
+/// - `0`（及其它取值）：暂停
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FreeMode {
+    #[default]
+    Off,
+    On,
+    Auto,
+}
+
+impl FreeMode {
+    /// 解析 free 文件内容
+    pub fn parse(content: &str) -> Self {
+        match content.trim() {
+            "1" => Self::On,
+            "auto" => Self::Auto,
+            _ => Self::Off,
+        }
+    }
+
+    /// 写入 free 文件的内容
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Off => "0",
+            Self::On => "1",
+            Self::Auto => "auto",
+        }
+    }
+
+    /// 是否需要监控充电事件（`1` 与 `auto`）
+    pub fn is_enabled(&self) -> bool {
+        *self != Self::Off
+    }
+}
@@ -0,0 +1,301 @@
+use crate::common::FreePPSError;
+#[cfg(feature = "control-socket")]
+use crate::common::constants::CONTROL_SOCKET_NAME;
+#[cfg(feature = "telemetry")]
+use crate::common::constants::TELEMETRY_DIR_NAME;
+use crate::common::constants::{
+    ADAPTER_RULES_NAME, ADAPTER_SVID_NODE, APDO_MAX_NODE, BATTERY_CAPACITY_NODE,
+    BATTERY_CURRENT_NOW_NODE, BATTERY_STATUS_NODE, BATTERY_TEMP_NODE, BATTERY_UEVENT_NODE,
+    CONFIG_FILE_NAME, DEFAULT_MODULE_BASE_PATH, DEFAULT_SYSFS_ROOT, DISABLE_FILE_NAME,
+    ENV_CONFIG_FILE, ENV_MODULE_DIR, ENV_SYSFS_ROOT, FREE_FILE_NAME, FREE_RESUME_FILE_NAME,
+    MODULE_PROP_NAME, PD_ADAPTER_VERIFIED_NODE, PD_VERIFIED_NODE, REAL_TYPE_NODE,
+    SESSION_HISTORY_NAME, THERMAL_CLASS_DIR, USB_CURRENT_NOW_NODE, USB_ONLINE_NODE,
+    USB_VOLTAGE_NOW_NODE,
+};
+use crate::common::utils;
+use anyhow::Result;
+use log::{info, warn};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// 命令行传入的路径覆盖项（优先级最高）
+#[derive(Debug, Default, Clone)]
+pub struct PathOverrides {
+    pub module_dir: Option<PathBuf>,
+    pub sysfs_root: Option<PathBuf>,
+    pub config_file: Option<PathBuf>,
+}
+
+impl PathOverrides {
+    /// 从命令行参数中解析路径覆盖项，支持 `--flag value` 与 `--flag=value` 两种写法
+    pub fn parse<I: IntoIterator<Item = String>>(args: I) -> Result<Self> {
+        let mut overrides = Self::default();
+        let mut args = args.into_iter();
+
+        while let Some(arg) = args.next() {
+            let (flag, inline_value) = match arg.split_once('=') {
+                Some((flag, value)) => (flag.to_string(), Some(value.to_string())),
+                None => (arg, None),
+            };
+
+            let slot = match flag.as_str() {
+                "--module-dir" => &mut overrides.module_dir,
+                "--sysfs-root" => &mut overrides.sysfs_root,
+                "--config" => &mut overrides.config_file,
+                _ => {
+                    return Err(FreePPSError::InvalidArgument(format!("未知参数: {}", flag)).into());
+                }
+            };
+
+            let value = match inline_value.or_else(|| args.next()) {
+                Some(value) if !value.is_empty() => value,
+                _ => {
+                    return Err(
+                        FreePPSError::InvalidArgument(format!("参数缺少取值: {}", flag)).into(),
+                    );
+                }
+            };
+            *slot = Some(PathBuf::from(value));
+        }
+
+        Ok(overrides)
+    }
+}
+
+/// 运行时路径解析器
+///
+/// 所有模块文件与 sysfs 节点都由模块目录和 sysfs 根目录两个根路径拼接得到，
+/// 根路径按 命令行参数 > 环境变量 > 配置文件 > 内置默认值 的优先级确定，
+/// 因此可以把整个守护进程指向一个临时目录里的假 sysfs 树运行。
+#[derive(Debug, Clone)]
+pub struct Paths {
+    module_dir: PathBuf,
+    sysfs_root: PathBuf,
+    config_file: PathBuf,
+    free_file: PathBuf,
+    free_resume_file: PathBuf,
+    disable_file: PathBuf,
+    module_prop: PathBuf,
+    #[cfg(feature = "control-socket")]
+    control_socket: PathBuf,
+    #[cfg(feature = "telemetry")]
+    telemetry_dir: PathBuf,
+    session_history: PathBuf,
+    adapter_rules: PathBuf,
+    pd_verified: PathBuf,
+    pd_adapter_verified: PathBuf,
+    battery_status: PathBuf,
+    battery_uevent: PathBuf,
+    real_type: PathBuf,
+    apdo_max: PathBuf,
+    adapter_svid: PathBuf,
+    usb_voltage_now: PathBuf,
+    usb_online: PathBuf,
+    usb_current_now: PathBuf,
+    battery_current_now: PathBuf,
+    battery_temp: PathBuf,
+    battery_capacity: PathBuf,
+}
+
+impl Paths {
+    fn with_config_file(module_dir: PathBuf, sysfs_root: PathBuf, config_file: PathBuf) -> Self {
+        Self {
+            free_file: module_dir.join(FREE_FILE_NAME),
+            free_resume_file: module_dir.join(FREE_RESUME_FILE_NAME),
+            disable_file: module_dir.join(DISABLE_FILE_NAME),
+            module_prop: module_dir.join(MODULE_PROP_NAME),
+            #[cfg(feature = "control-socket")]
+            control_socket: module_dir.join(CONTROL_SOCKET_NAME),
+            #[cfg(feature = "telemetry")]
+            telemetry_dir: module_dir.join(TELEMETRY_DIR_NAME),
+            session_history: module_dir.join(SESSION_HISTORY_NAME),
+            adapter_rules: module_dir.join(ADAPTER_RULES_NAME),
+            pd_verified: sysfs_root.join(PD_VERIFIED_NODE),
+            pd_adapter_verified: sysfs_root.join(PD_ADAPTER_VERIFIED_NODE),
+            battery_status: sysfs_root.join(BATTERY_STATUS_NODE),
+            battery_uevent: sysfs_root.join(BATTERY_UEVENT_NODE),
+            real_type: sysfs_root.join(REAL_TYPE_NODE),
+            apdo_max: sysfs_root.join(APDO_MAX_NODE),
+            adapter_svid: sysfs_root.join(ADAPTER_SVID_NODE),
+            usb_voltage_now: sysfs_root.join(USB_VOLTAGE_NOW_NODE),
+            usb_online: sysfs_root.join(USB_ONLINE_NODE),
+            usb_current_now: sysfs_root.join(USB_CURRENT_NOW_NODE),
+            battery_current_now: sysfs_root.join(BATTERY_CURRENT_NOW_NODE),
+            battery_temp: sysfs_root.join(BATTERY_TEMP_NODE),
+            battery_capacity: sysfs_root.join(BATTERY_CAPACITY_NODE),
+            module_dir,
+            sysfs_root,
+            config_file,
+        }
+    }
+
+    /// 按 命令行参数 > 环境变量 > 配置文件 > 默认值 解析最终路径
+    pub fn resolve(overrides: &PathOverrides) -> Result<Self> {
+        let env_path = |key: &str| {
+            std::env::var_os(key)
+                .filter(|value| !value.is_empty())
+                .map(PathBuf::from)
+        };
+
+        let cli_or_env_module_dir = overrides
+            .module_dir
+            .clone()
+            .or_else(|| env_path(ENV_MODULE_DIR));
+
+        let config_file = overrides
+            .config_file
+            .clone()
+            .or_else(|| env_path(ENV_CONFIG_FILE))
+            .unwrap_or_else(|| {
+                cli_or_env_module_dir
+                    .as_deref()
+                    .unwrap_or(Path::new(DEFAULT_MODULE_BASE_PATH))
+                    .join(CONFIG_FILE_NAME)
+            });
+
+        let mut config_module_dir = None;
+        let mut config_sysfs_root = None;
+        if config_file.exists() {
+            let content = fs::read_to_string(&config_file).map_err(FreePPSError::FileOperation)?;
+            for (key, value) in utils::parse_key_values(&content) {
+                match key.as_str() {
+                    "module_dir" => config_module_dir = Some(PathBuf::from(value)),
+                    "sysfs_root" => config_sysfs_root = Some(PathBuf::from(value)),
+                    _ => {}
+                }
+            }
+        } else if overrides.config_file.is_some() {
+            warn!(
+                "指定的配置文件不存在，使用默认路径: {}",
+                config_file.display()
+            );
+        }
+
+        let module_dir = cli_or_env_module_dir
+            .or(config_module_dir)
+            .unwrap_or_else(|| PathBuf::from(DEFAULT_MODULE_BASE_PATH));
+        let sysfs_root = overrides
+            .sysfs_root
+            .clone()
+            .or_else(|| env_path(ENV_SYSFS_ROOT))
+            .or(config_sysfs_root)
+            .unwrap_or_else(|| PathBuf::from(DEFAULT_SYSFS_ROOT));
+
+        let paths = Self::with_config_file(module_dir, sysfs_root, config_file);
+        info!(
+            "路径解析完成: 模块目录={} sysfs根目录={} 配置文件={}",
+            paths.module_dir.display(),
+            paths.sysfs_root.display(),
+            paths.config_file.display()
+        );
+        Ok(paths)
+    }
+
+    pub fn module_dir(&self) -> &Path {
+        &self.module_dir
+    }
+
+    pub fn sysfs_root(&self) -> &Path {
+        &self.sysfs_root
+    }
+
+    pub fn config_file(&self) -> &Path {
+        &self.config_file
+    }
+
+    pub fn free_file(&self) -> &Path {
+        &self.free_file
+    }
+
+    pub fn free_resume_file(&self) -> &Path {
+        &self.free_resume_file
+    }
+
+    pub fn disable_file(&self) -> &Path {
+        &self.disable_file
+    }
+
+    pub fn module_prop(&self) -> &Path {
+        &self.module_prop
+    }
+
+    #[cfg(feature = "control-socket")]
+    pub fn control_socket(&self) -> &Path {
+        &self.control_socket
+    }
+
+    #[cfg(feature = "telemetry")]
+    pub fn telemetry_dir(&self) -> &Path {
+        &self.telemetry_dir
+    }
+
+    pub fn session_history(&self) -> &Path {
+        &self.session_history
+    }
+
+    pub fn adapter_rules(&self) -> &Path {
+        &self.adapter_rules
+    }
+
+    pub fn pd_verified(&self) -> &Path {
+        &self.pd_verified
+    }
+
+    pub fn pd_adapter_verified(&self) -> &Path {
+        &self.pd_adapter_verified
+    }
+
+    pub fn battery_status(&self) -> &Path {
+        &self.battery_status
+    }
+
+    pub fn battery_uevent(&self) -> &Path {
+        &self.battery_uevent
+    }
+
+    pub fn real_type(&self) -> &Path {
+        &self.real_type
+    }
+
+    pub fn apdo_max(&self) -> &Path {
+        &self.apdo_max
+    }
+
+    pub fn adapter_svid(&self) -> &Path {
+        &self.adapter_svid
+    }
+
+    pub fn usb_voltage_now(&self) -> &Path {
+        &self.usb_voltage_now
+    }
+
+    pub fn usb_online(&self) -> &Path {
+        &self.usb_online
+    }
+
+    pub fn usb_current_now(&self) -> &Path {
+        &self.usb_current_now
+    }
+
+    pub fn battery_current_now(&self) -> &Path {
+        &self.battery_current_now
+    }
+
+    pub fn battery_temp(&self) -> &Path {
+        &self.battery_temp
+    }
+
+    pub fn battery_capacity(&self) -> &Path {
+        &self.battery_capacity
+    }
+
+    /// thermal zone 温度节点，如 `thermal_zone5` → `<sysfs>/class/thermal/thermal_zone5/temp`
+    ///
+    /// `zone` 在解析配置时已校验为 `thermal_zone<N>`，不会跳出 thermal 目录。
+    pub fn thermal_zone_temp(&self, zone: &str) -> PathBuf {
+        self.sysfs_root
+            .join(THERMAL_CLASS_DIR)
+            .join(zone)
+            .join("temp")
+    }
+}
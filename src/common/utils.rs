@@ -7,3 +7,49 @@ pub fn get_current_thread_name() -> String {
         .map(|s| s.to_string())
         .unwrap_or_else(|| format!("unnamed-thread-{:?}", thread::current().id()))
 }
+
+/// 解析 `key=value` 形式的文本（与 module.prop 格式一致），忽略空行与 `#` 注释行
+pub fn parse_key_values(content: &str) -> Vec<(String, String)> {
+    content
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .filter_map(|line| line.split_once('='))
+        .map(|(key, value)| (key.trim().to_string(), value.trim().to_string()))
+        .collect()
+}
+
+/// 把 Unix 时间戳格式化为本地时间 `YYYY-MM-DD HH:MM:SS`
+#[cfg(unix)]
+pub fn format_local_time(secs: u64) -> String {
+    let time = secs as libc::time_t;
+    let mut tm: libc::tm = unsafe { std::mem::zeroed() };
+    if unsafe { libc::localtime_r(&time, &mut tm) }.is_null() {
+        return secs.to_string();
+    }
+    format!(
+        "{:04}-{:02}-{:02} {:02}:{:02}:{:02}",
+        tm.tm_year + 1900,
+        tm.tm_mon + 1,
+        tm.tm_mday,
+        tm.tm_hour,
+        tm.tm_min,
+        tm.tm_sec
+    )
+}
+
+#[cfg(not(unix))]
+pub fn format_local_time(secs: u64) -> String {
+    secs.to_string()
+}
+
+/// Unix 时间戳对应的本地时间是当天的第几秒
+#[cfg(unix)]
+pub fn local_seconds_of_day(secs: u64) -> Option<u32> {
+    let time = secs as libc::time_t;
+    let mut tm: libc::tm = unsafe { std::mem::zeroed() };
+    if unsafe { libc::localtime_r(&time, &mut tm) }.is_null() {
+        return None;
+    }
+    Some((tm.tm_hour * 3600 + tm.tm_min * 60 + tm.tm_sec.min(59)) as u32)
+}
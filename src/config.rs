@@ -0,0 +1,373 @@
+//! 运行配置（模块目录下的 `freepps.conf`）
+//!
+//! 配置文件为 `key=value` 格式，同一个文件中同时包含路径覆盖（`module_dir` / `sysfs_root`，
+//! 仅在启动时由 [`crate::common::Paths`] 读取）与各功能的阈值和开关（本模块）。
+//! 守护进程运行期间由事件循环（[`crate::monitoring::Reactor`]）监控该文件，修改后无需重启即可生效：
+//! 各线程每次使用配置时从 [`ConfigStore`] 读取当前值；配置变化时 [`ConfigStore::changes`] 通知事件循环，
+//! 由事件循环立即应用日志级别与定时切换（文件监控与控制命令 `reload` 都经此生效）。
+//!
+//! 启动时无效的配置项使用默认值，配置项之间有冲突时整个文件不生效、使用默认配置；
+//! 运行中重新加载时只要有一项无效或冲突，整个文件都不生效，继续使用当前配置。
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::Duration;
+
+use anyhow::Result;
+use log::{LevelFilter, error, info, warn};
+
+use crate::common::{FreePPSError, Paths, utils};
+#[cfg(feature = "broadcast-forger")]
+use crate::pd::ForgerConfig;
+#[cfg(unix)]
+use crate::platform::Notifier;
+use crate::policy::schedule::ScheduleConfig;
+use crate::policy::soc::SocConfig;
+use crate::policy::thermal::ThermalConfig;
+use crate::telemetry::TelemetryConfig;
+
+/// 由 [`crate::common::Paths`] 在启动时读取的路径项，本模块忽略
+const PATH_KEYS: [&str; 2] = ["module_dir", "sysfs_root"];
+
+/// 配置文件中的一组配置项
+pub trait ConfigSection {
+    /// 应用一个配置项：不属于本组时返回 `None`，取值无效时返回原因
+    fn apply(&mut self, key: &str, value: &str) -> Option<Result<(), String>>;
+
+    /// 全部配置项应用后检查配置项之间的约束（如阈值的大小关系），不满足时返回写明相关配置项的原因
+    fn finish(&mut self) -> Result<(), String> {
+        Ok(())
+    }
+}
+
+/// 全部运行配置
+#[derive(Debug, Clone, PartialEq)]
+pub struct Config {
+    /// 日志级别：`log_level=debug|info|warn|error|off`
+    pub log_level: LevelFilter,
+    #[cfg(feature = "broadcast-forger")]
+    pub forger: ForgerConfig,
+    pub telemetry: TelemetryConfig,
+    pub thermal: ThermalConfig,
+    pub soc: SocConfig,
+    pub schedule: ScheduleConfig,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            log_level: LevelFilter::Debug,
+            #[cfg(feature = "broadcast-forger")]
+            forger: ForgerConfig::default(),
+            telemetry: TelemetryConfig::default(),
+            thermal: ThermalConfig::default(),
+            soc: SocConfig::default(),
+            schedule: ScheduleConfig::default(),
+        }
+    }
+}
+
+/// 配置文件中的一处错误
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ConfigError {
+    /// 单个配置项取值无效
+    Invalid {
+        key: String,
+        value: String,
+        reason: String,
+    },
+    /// 配置项之间的约束不满足
+    Conflict(String),
+}
+
+impl std::fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Invalid { key, value, reason } => write!(f, "{}={}：{}", key, value, reason),
+            Self::Conflict(reason) => f.write_str(reason),
+        }
+    }
+}
+
+impl Config {
+    /// 解析配置文件内容，返回解析结果（无效项保持默认值）与全部错误
+    pub fn parse(content: &str) -> (Self, Vec<ConfigError>) {
+        let mut config = Self::default();
+        let mut errors = Vec::new();
+
+        for (key, value) in utils::parse_key_values(content) {
+            let result = if key == "log_level" {
+                Some(parse_level(&value).map(|level| config.log_level = level))
+            } else {
+                config
+                    .sections()
+                    .into_iter()
+                    .find_map(|section| section.apply(&key, &value))
+            };
+            match result {
+                Some(Ok(())) => {}
+                Some(Err(reason)) => errors.push(ConfigError::Invalid { key, value, reason }),
+                None if PATH_KEYS.contains(&key.as_str()) => {}
+                None => warn!("未知的配置项 {}，已忽略", key),
+            }
+        }
+
+        for section in config.sections() {
+            if let Err(reason) = section.finish() {
+                errors.push(ConfigError::Conflict(reason));
+            }
+        }
+        (config, errors)
+    }
+
+    /// 编译时启用的各组配置项（未编译的功能的配置项按未知项忽略）
+    fn sections(&mut self) -> Vec<&mut dyn ConfigSection> {
+        vec![
+            #[cfg(feature = "broadcast-forger")]
+            &mut self.forger,
+            &mut self.telemetry,
+            &mut self.thermal,
+            &mut self.soc,
+            &mut self.schedule,
+        ]
+    }
+}
+
+/// 当前生效的配置（守护进程内各线程共享）
+pub struct ConfigStore {
+    path: PathBuf,
+    current: Mutex<Config>,
+    // 重新加载后配置发生变化时通知事件循环
+    #[cfg(unix)]
+    changes: Notifier,
+}
+
+impl ConfigStore {
+    /// 读取配置文件；文件不存在时使用默认配置，无效项使用默认值并逐项记录错误，
+    /// 配置项之间有冲突时整个文件不生效、使用默认配置
+    pub fn load(paths: &Paths) -> Result<Self> {
+        let path = paths.config_file().to_path_buf();
+        let config = match read_config(&path)? {
+            Some(content) => {
+                let (config, errors) = Config::parse(&content);
+                let mut conflicted = false;
+                for e in &errors {
+                    match e {
+                        ConfigError::Invalid { .. } => error!("无效的配置项 {}，使用默认值", e),
+                        ConfigError::Conflict(_) => {
+                            error!("配置冲突：{}", e);
+                            conflicted = true;
+                        }
+                    }
+                }
+                if conflicted {
+                    error!("配置文件有冲突，整个文件不生效，使用默认配置");
+                    Config::default()
+                } else {
+                    config
+                }
+            }
+            None => Config::default(),
+        };
+        Self::with_config(path, config)
+    }
+
+    /// 使用指定配置（不读取文件，用于回放与测试）
+    pub fn with_config(path: PathBuf, config: Config) -> Result<Self> {
+        Ok(Self {
+            path,
+            current: Mutex::new(config),
+            #[cfg(unix)]
+            changes: Notifier::new()?,
+        })
+    }
+
+    /// 配置文件路径
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// 当前配置的快照
+    pub fn current(&self) -> Config {
+        self.current.lock().unwrap().clone()
+    }
+
+    /// 配置变化通知（事件循环加入 epoll，被唤醒后应用新配置）
+    #[cfg(unix)]
+    pub fn changes(&self) -> &Notifier {
+        &self.changes
+    }
+
+    /// 重新读取配置文件，返回配置是否发生变化（变化时经 [`Self::changes`] 通知事件循环）
+    ///
+    /// 有任何无效项时逐项记录错误并拒绝整个文件，当前配置保持不变。
+    pub fn reload(&self) -> Result<bool> {
+        let config = match read_config(&self.path)? {
+            Some(content) => {
+                let (config, errors) = Config::parse(&content);
+                if !errors.is_empty() {
+                    for e in &errors {
+                        match e {
+                            ConfigError::Invalid { .. } => error!("无效的配置项 {}", e),
+                            ConfigError::Conflict(_) => error!("配置冲突：{}", e),
+                        }
+                    }
+                    error!(
+                        "配置文件有{}处无效，本次修改未生效，继续使用当前配置",
+                        errors.len()
+                    );
+                    return Ok(false);
+                }
+                config
+            }
+            None => Config::default(),
+        };
+
+        {
+            let mut current = self.current.lock().unwrap();
+            if *current == config {
+                return Ok(false);
+            }
+            *current = config;
+        }
+        info!("配置已重新加载: {}", self.path.display());
+        #[cfg(unix)]
+        self.changes.notify();
+        Ok(true)
+    }
+}
+
+/// 读取配置文件内容，文件不存在时返回 `None`
+fn read_config(path: &Path) -> Result<Option<String>> {
+    if !path.exists() {
+        return Ok(None);
+    }
+    Ok(Some(
+        fs::read_to_string(path).map_err(FreePPSError::FileOperation)?,
+    ))
+}
+
+fn parse_level(value: &str) -> Result<LevelFilter, String> {
+    match value {
+        "trace" => Ok(LevelFilter::Trace),
+        "debug" => Ok(LevelFilter::Debug),
+        "info" => Ok(LevelFilter::Info),
+        "warn" => Ok(LevelFilter::Warn),
+        "error" => Ok(LevelFilter::Error),
+        "off" => Ok(LevelFilter::Off),
+        _ => Err("可选 trace / debug / info / warn / error / off".to_string()),
+    }
+}
+
+/// 解析 `on` / `off`
+pub fn parse_switch(value: &str) -> Result<bool, String> {
+    match value {
+        "on" => Ok(true),
+        "off" => Ok(false),
+        _ => Err("可选 on / off".to_string()),
+    }
+}
+
+/// 解析不小于 `min_ms` 的毫秒数
+pub fn parse_millis(value: &str, min_ms: u64) -> Result<Duration, String> {
+    match value.parse::<u64>() {
+        Ok(ms) if ms >= min_ms => Ok(Duration::from_millis(ms)),
+        _ => Err(format!("需要不小于{}的毫秒数", min_ms)),
+    }
+}
+
+/// 解析正整数
+pub fn parse_positive<T>(value: &str) -> Result<T, String>
+where
+    T: std::str::FromStr + PartialOrd + Default,
+{
+    match value.parse::<T>() {
+        Ok(number) if number > T::default() => Ok(number),
+        _ => Err("需要正整数".to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::FakeSysfs;
+
+    /// 写入配置文件并加载
+    fn load(sysfs: &FakeSysfs, content: &str) -> ConfigStore {
+        sysfs.write(sysfs.paths().config_file(), content);
+        ConfigStore::load(sysfs.paths()).unwrap()
+    }
+
+    #[test]
+    fn invalid_value_keeps_default_and_is_reported() {
+        let (config, errors) = Config::parse("log_level=info\nthermal_limit_c=hot\n");
+        assert_eq!(config.log_level, LevelFilter::Info);
+        assert_eq!(config.thermal.limit_c, ThermalConfig::default().limit_c);
+        assert!(matches!(
+            errors.as_slice(),
+            [ConfigError::Invalid { key, .. }] if key == "thermal_limit_c"
+        ));
+    }
+
+    #[test]
+    fn path_keys_are_not_reported() {
+        let (_, errors) = Config::parse("module_dir=/data/adb/modules/FreePPS\nsysfs_root=/sys\n");
+        assert!(errors.is_empty());
+    }
+
+    #[test]
+    fn resume_not_below_limit_is_a_conflict_naming_both_keys() {
+        let (_, errors) = Config::parse("thermal_limit_c=42\nthermal_resume_c=45\n");
+        let [ConfigError::Conflict(reason)] = errors.as_slice() else {
+            panic!("应报告配置冲突: {:?}", errors);
+        };
+        assert!(reason.contains("thermal_resume_c") && reason.contains("thermal_limit_c"));
+    }
+
+    #[test]
+    fn conflict_at_load_uses_default_config() {
+        let sysfs = FakeSysfs::new();
+        let store = load(
+            &sysfs,
+            "soc_limit=80\nthermal_limit_c=42\nthermal_resume_c=45\n",
+        );
+        assert_eq!(store.current(), Config::default());
+    }
+
+    #[test]
+    fn reload_applies_valid_change_once() {
+        let sysfs = FakeSysfs::new();
+        let store = load(&sysfs, "soc_limit=80\n");
+        assert_eq!(store.current().soc.limit, Some(80));
+
+        sysfs.write(sysfs.paths().config_file(), "soc_limit=70\n");
+        assert!(store.reload().unwrap());
+        assert_eq!(store.current().soc.limit, Some(70));
+        assert!(!store.reload().unwrap());
+    }
+
+    #[test]
+    fn reload_rejects_file_with_invalid_value() {
+        let sysfs = FakeSysfs::new();
+        let store = load(&sysfs, "soc_limit=80\n");
+
+        sysfs.write(sysfs.paths().config_file(), "soc_limit=70\ntelemetry=xml\n");
+        assert!(!store.reload().unwrap());
+        assert_eq!(store.current().soc.limit, Some(80));
+    }
+
+    #[test]
+    fn reload_rejects_conflicting_thresholds() {
+        let sysfs = FakeSysfs::new();
+        let store = load(&sysfs, "thermal_limit_c=48\nthermal_resume_c=44\n");
+
+        sysfs.write(
+            sysfs.paths().config_file(),
+            "thermal_limit_c=42\nthermal_resume_c=45\n",
+        );
+        assert!(!store.reload().unwrap());
+        let thermal = store.current().thermal;
+        assert_eq!((thermal.limit_c, thermal.resume_c), (48.0, 44.0));
+    }
+}
@@ -0,0 +1,14 @@
+#[cfg(feature = "control-socket")]
+pub mod client;
+// 未编译控制 socket 时只有 CLI 的直接操作路径使用其中的状态类型
+#[cfg_attr(not(feature = "control-socket"), allow(dead_code))]
+pub mod protocol;
+#[cfg(feature = "control-socket")]
+pub mod server;
+pub mod status;
+
+#[cfg(feature = "control-socket")]
+pub use client::send_command;
+#[cfg(feature = "control-socket")]
+pub use server::spawn_control_server;
+pub use status::collect_status;
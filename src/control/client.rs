@@ -0,0 +1,48 @@
+use std::io::{BufRead, BufReader, Write};
+use std::os::unix::net::UnixStream;
+use std::path::Path;
+use std::time::Duration;
+
+use anyhow::{Result, anyhow};
+
+use crate::common::FreePPSError;
+use crate::control::protocol::{Command, Reply};
+
+// 等待回复的超时：forge-now 最长约 3s（门控等待 2s + 爆发序列）
+const REPLY_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// 向运行中的守护进程发送一条控制命令并等待回复
+///
+/// socket 不存在或连接被拒绝（守护进程未运行）时返回 `Ok(None)`，由调用方决定回退方式。
+pub fn send_command(socket_path: &Path, command: Command) -> Result<Option<Reply>> {
+    let stream = match UnixStream::connect(socket_path) {
+        Ok(stream) => stream,
+        Err(e)
+            if matches!(
+                e.kind(),
+                std::io::ErrorKind::NotFound | std::io::ErrorKind::ConnectionRefused
+            ) =>
+        {
+            return Ok(None);
+        }
+        Err(e) => return Err(FreePPSError::FileOperation(e).into()),
+    };
+
+    stream
+        .set_read_timeout(Some(REPLY_TIMEOUT))
+        .and_then(|_| stream.set_write_timeout(Some(REPLY_TIMEOUT)))
+        .map_err(FreePPSError::FileOperation)?;
+
+    (&stream)
+        .write_all(format!("{}\n", command.as_str()).as_bytes())
+        .map_err(FreePPSError::FileOperation)?;
+
+    let mut line = String::new();
+    BufReader::new(&stream)
+        .read_line(&mut line)
+        .map_err(FreePPSError::FileOperation)?;
+
+    let reply: Reply = serde_json::from_str(line.trim())
+        .map_err(|e| anyhow!("解析守护进程回复失败: {} ({})", e, line.trim()))?;
+    Ok(Some(reply))
+}
@@ -0,0 +1,156 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::common::FreeMode;
+use crate::pd::NodeWriteStats;
+use crate::session::AdapterInfo;
+
+/// 控制命令
+///
+/// 协议：客户端连接控制 socket 后发送一行命令文本（如 `status\n`），
+/// 守护进程回复一行 JSON（[`Reply`]）后关闭连接。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Command {
+    Status,
+    Enable,
+    Disable,
+    Toggle,
+    Auto,
+    Reload,
+    ForgeNow,
+}
+
+impl Command {
+    pub fn parse(text: &str) -> Option<Self> {
+        match text.trim() {
+            "status" => Some(Self::Status),
+            "enable" => Some(Self::Enable),
+            "disable" => Some(Self::Disable),
+            "toggle" => Some(Self::Toggle),
+            "auto" => Some(Self::Auto),
+            "reload" => Some(Self::Reload),
+            "forge-now" => Some(Self::ForgeNow),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Status => "status",
+            Self::Enable => "enable",
+            Self::Disable => "disable",
+            Self::Toggle => "toggle",
+            Self::Auto => "auto",
+            Self::Reload => "reload",
+            Self::ForgeNow => "forge-now",
+        }
+    }
+}
+
+/// 命令回复
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Reply {
+    pub ok: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub command: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub data: Option<Value>,
+}
+
+impl Reply {
+    pub fn success(command: Command, data: impl Serialize) -> Self {
+        Self {
+            ok: true,
+            command: Some(command.as_str().to_string()),
+            error: None,
+            data: serde_json::to_value(data).ok(),
+        }
+    }
+
+    pub fn failure(command: Option<Command>, error: impl Into<String>) -> Self {
+        Self {
+            ok: false,
+            command: command.map(|c| c.as_str().to_string()),
+            error: Some(error.into()),
+            data: None,
+        }
+    }
+}
+
+/// 单个充电解锁后端的状态
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BackendStatus {
+    pub name: String,
+    pub node: String,
+    pub detected: bool,
+    /// 节点当前取值：`true`=已解锁，`false`=未解锁，缺省=节点缺失或取值未知
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub unlocked: Option<bool>,
+    /// 节点写入统计（回读校验与被驱动改回的次数），缺省表示该后端不校验写入
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub writes: Option<NodeWriteStats>,
+}
+
+/// `status` 命令返回的守护进程状态
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DaemonStatus {
+    pub version: String,
+    /// 守护进程 pid，缺省表示守护进程未运行（客户端本地采集的状态）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pid: Option<u32>,
+    pub enabled: bool,
+    /// free 文件表示的模式（off / on / auto）
+    #[serde(default)]
+    pub mode: FreeMode,
+    pub battery_status: String,
+    pub backends: Vec<BackendStatus>,
+    /// 策略暂停解锁的原因（温控等），为空表示未暂停
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub holds: Vec<String>,
+    /// 当前（或最近一次）充电会话，缺省表示启动后尚无会话
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub session: Option<SessionStatus>,
+}
+
+/// 充电会话快照（时间为 Unix 时间戳，秒）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SessionStatus {
+    pub id: u32,
+    pub state: String,
+    pub adapter: AdapterInfo,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub plugged_at: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub negotiating_at: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pps_active_at: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub unplugged_at: Option<u64>,
+    pub duration_secs: u64,
+}
+
+/// `enable` / `disable` / `toggle` / `auto` 命令返回的切换结果
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FreeState {
+    pub enabled: bool,
+    #[serde(default)]
+    pub mode: FreeMode,
+}
+
+impl From<FreeMode> for FreeState {
+    fn from(mode: FreeMode) -> Self {
+        Self {
+            enabled: mode.is_enabled(),
+            mode,
+        }
+    }
+}
+
+/// `forge-now` 命令返回的伪造结果
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ForgeResult {
+    pub backend: String,
+    pub fired: bool,
+}
@@ -0,0 +1,193 @@
+use std::io::{BufRead, BufReader, Write};
+use std::os::unix::fs::PermissionsExt;
+use std::os::unix::io::AsRawFd;
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+use anyhow::Result;
+use log::{debug, error, info, warn};
+
+use crate::common::{FreeMode, FreePPSError, utils};
+use crate::config::ConfigStore;
+use crate::control::collect_status;
+use crate::control::protocol::{Command, ForgeResult, FreeState, Reply};
+use crate::monitoring::{FileMonitor, ModuleManager};
+use crate::platform::ShutdownSignal;
+
+// 单个连接的读写超时，避免异常客户端阻塞控制线程
+const CONNECTION_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// 启动控制 socket 线程（`<模块目录>/freepps.sock`）
+pub fn spawn_control_server(
+    shutdown: Arc<ShutdownSignal>,
+    module_manager: Arc<ModuleManager>,
+    config: Arc<ConfigStore>,
+) -> thread::JoinHandle<()> {
+    thread::Builder::new()
+        .name("control-socket".to_string())
+        .spawn(move || {
+            if let Err(e) = worker(shutdown, module_manager, config) {
+                error!("控制socket线程出错: {}", e);
+            }
+        })
+        .expect("创建控制socket线程失败")
+}
+
+fn worker(
+    shutdown: Arc<ShutdownSignal>,
+    module_manager: Arc<ModuleManager>,
+    config: Arc<ConfigStore>,
+) -> Result<()> {
+    let thread_name = utils::get_current_thread_name();
+    let socket_path = module_manager.paths().control_socket().to_path_buf();
+    info!(
+        "[{}] 启动控制socket线程: {}",
+        thread_name,
+        socket_path.display()
+    );
+
+    // 清理上次异常退出遗留的 socket 文件
+    if socket_path.exists() {
+        std::fs::remove_file(&socket_path).map_err(FreePPSError::FileOperation)?;
+    }
+
+    let listener = UnixListener::bind(&socket_path).map_err(FreePPSError::FileOperation)?;
+    std::fs::set_permissions(&socket_path, std::fs::Permissions::from_mode(0o600))
+        .map_err(FreePPSError::FileOperation)?;
+    listener
+        .set_nonblocking(true)
+        .map_err(FreePPSError::FileOperation)?;
+
+    let file_monitor = FileMonitor::new()?;
+    file_monitor.add_shutdown_to_epoll(&shutdown)?;
+    let listener_fd = listener.as_raw_fd();
+    file_monitor.add_fd_to_epoll(listener_fd, libc::EPOLLIN as u32, listener_fd as u64)?;
+
+    let mut events = [libc::epoll_event { events: 0, u64: 0 }; 4];
+    while shutdown.is_running() {
+        match file_monitor.wait_events(&mut events, -1) {
+            Ok(_) => {}
+            Err(err) => match err.raw_os_error() {
+                Some(code) if code == libc::EINTR || code == libc::EAGAIN => continue,
+                _ => {
+                    error!("等待控制socket事件失败，将在1秒后重试：{}", err);
+                    thread::sleep(Duration::from_millis(1000));
+                    continue;
+                }
+            },
+        }
+
+        if !shutdown.is_running() {
+            break;
+        }
+
+        // 依次处理所有待接受的连接
+        loop {
+            match listener.accept() {
+                Ok((stream, _)) => handle_connection(stream, &module_manager, &config, &shutdown),
+                Err(e) if e.kind() == std::io::ErrorKind::WouldBlock => break,
+                Err(e) => {
+                    warn!("接受控制连接失败: {}", e);
+                    break;
+                }
+            }
+        }
+    }
+
+    if let Err(e) = std::fs::remove_file(&socket_path) {
+        debug!("删除控制socket文件失败: {}", e);
+    }
+
+    Ok(())
+}
+
+fn handle_connection(
+    stream: UnixStream,
+    module_manager: &ModuleManager,
+    config: &ConfigStore,
+    shutdown: &ShutdownSignal,
+) {
+    if let Err(e) = stream
+        .set_nonblocking(false)
+        .and_then(|_| stream.set_read_timeout(Some(CONNECTION_TIMEOUT)))
+        .and_then(|_| stream.set_write_timeout(Some(CONNECTION_TIMEOUT)))
+    {
+        warn!("设置控制连接参数失败: {}", e);
+        return;
+    }
+
+    let mut line = String::new();
+    if let Err(e) = BufReader::new(&stream).read_line(&mut line) {
+        warn!("读取控制命令失败: {}", e);
+        return;
+    }
+
+    let reply = match Command::parse(&line) {
+        Some(command) => {
+            info!("收到控制命令: {}", command.as_str());
+            execute(command, module_manager, config, shutdown)
+        }
+        None => Reply::failure(None, format!("未知命令: {}", line.trim())),
+    };
+
+    let mut payload = serde_json::to_string(&reply)
+        .unwrap_or_else(|e| format!(r#"{{"ok":false,"error":"序列化回复失败: {}"}}"#, e));
+    payload.push('\n');
+    if let Err(e) = (&stream).write_all(payload.as_bytes()) {
+        warn!("发送控制回复失败: {}", e);
+    }
+}
+
+fn execute(
+    command: Command,
+    module_manager: &ModuleManager,
+    config: &ConfigStore,
+    shutdown: &ShutdownSignal,
+) -> Reply {
+    let result = match command {
+        Command::Status => Ok(Reply::success(command, collect_status(module_manager))),
+        Command::Enable | Command::Disable | Command::Auto => {
+            let mode = match command {
+                Command::Enable => FreeMode::On,
+                Command::Auto => FreeMode::Auto,
+                _ => FreeMode::Off,
+            };
+            module_manager
+                .set_free_mode(mode)
+                .map(|_| Reply::success(command, FreeState::from(mode)))
+        }
+        Command::Toggle => module_manager
+            .toggle_free()
+            .map(|mode| Reply::success(command, FreeState::from(mode))),
+        // 配置变化时由事件循环应用日志级别与定时切换
+        Command::Reload => config
+            .reload()
+            .and_then(|_| module_manager.reload())
+            .map(|_| Reply::success(command, collect_status(module_manager))),
+        Command::ForgeNow => {
+            let results: Vec<ForgeResult> = module_manager
+                .backends()
+                .iter()
+                .filter(|backend| backend.detect())
+                .filter_map(|backend| {
+                    backend.forge_now(shutdown).map(|fired| ForgeResult {
+                        backend: backend.name().to_string(),
+                        fired,
+                    })
+                })
+                .collect();
+            if results.is_empty() {
+                Ok(Reply::failure(
+                    Some(command),
+                    "当前设备没有支持广播伪造的后端",
+                ))
+            } else {
+                Ok(Reply::success(command, results))
+            }
+        }
+    };
+
+    result.unwrap_or_else(|e| Reply::failure(Some(command), e.to_string()))
+}
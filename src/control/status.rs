@@ -0,0 +1,49 @@
+use std::time::SystemTime;
+
+use crate::control::protocol::{BackendStatus, DaemonStatus, SessionStatus};
+use crate::monitoring::{FileMonitor, ModuleManager};
+use crate::session::{ChargingSession, unix_secs};
+
+/// 采集守护进程与各后端节点的当前状态
+pub fn collect_status(module_manager: &ModuleManager) -> DaemonStatus {
+    let paths = module_manager.paths();
+    DaemonStatus {
+        version: env!("CARGO_PKG_VERSION").to_string(),
+        pid: Some(std::process::id()),
+        enabled: module_manager.is_free_enabled(),
+        mode: module_manager.free_mode(),
+        battery_status: FileMonitor::read_file_content(paths.battery_status()).unwrap_or_default(),
+        backends: module_manager
+            .backends()
+            .iter()
+            .map(|backend| BackendStatus {
+                name: backend.name().to_string(),
+                node: backend.node_path().display().to_string(),
+                detected: backend.detect(),
+                unlocked: backend.read_state().ok().flatten(),
+                writes: backend.write_stats(),
+            })
+            .collect(),
+        holds: module_manager.holds().labels(),
+        session: session_status(&module_manager.session().lock().unwrap()),
+    }
+}
+
+fn session_status(session: &ChargingSession) -> Option<SessionStatus> {
+    if session.id() == 0 {
+        return None;
+    }
+    Some(SessionStatus {
+        id: session.id(),
+        state: session.state().as_str().to_string(),
+        adapter: session.adapter().clone(),
+        plugged_at: session.plugged_at().map(unix_secs),
+        negotiating_at: session.negotiating_at().map(unix_secs),
+        pps_active_at: session.pps_active_at().map(unix_secs),
+        unplugged_at: session.unplugged_at().map(unix_secs),
+        duration_secs: session
+            .duration(SystemTime::now())
+            .map(|d| d.as_secs())
+            .unwrap_or(0),
+    })
+}
@@ -1,22 +1,28 @@
+mod cli;
 mod common;
+mod config;
+mod control;
 mod monitoring;
 mod pd;
 mod platform;
+mod policy;
+mod session;
+mod telemetry;
+#[cfg(test)]
+mod testing;
+mod trace;
 
 use std::sync::Arc;
-use std::sync::atomic::AtomicBool;
 use std::thread;
-use std::time::Duration;
 
-use common::constants::{FREE_FILE, PD_ADAPTER_VERIFIED_PATH, PD_VERIFIED_PATH};
-use common::utils;
+use cli::{Cli, Subcommand};
+use common::{Paths, utils};
+use config::{Config, ConfigStore};
+#[cfg(feature = "control-socket")]
+use control::spawn_control_server;
 use log::{error, info};
-use monitoring::{
-    ModuleManager, spawn_disable_file_monitor, spawn_free_file_monitor,
-    spawn_pd_adapter_verified_monitor, spawn_pd_verified_monitor,
-};
-use pd::{PdAdapterVerifier, PdVerifier};
-use platform::install_signal_handlers;
+use monitoring::{ModuleManager, NetlinkUevents, Reactor};
+use platform::{ShutdownSignal, install_signal_handlers};
 
 fn main() {
     // 初始化 Android Logger
@@ -26,83 +32,80 @@ fn main() {
             .with_tag("FreePPS"),
     );
 
+    let cli = match Cli::parse(std::env::args().skip(1)) {
+        Ok(cli) => cli,
+        Err(e) => {
+            eprintln!("{}\n\n{}", e, cli::USAGE);
+            std::process::exit(cli::EXIT_USAGE);
+        }
+    };
+
+    if cli.subcommand != Subcommand::Daemon {
+        std::process::exit(cli::run(&cli));
+    }
+
+    // 解析运行时路径（命令行参数 > 环境变量 > 配置文件 > 默认值）
+    let paths = Arc::new(Paths::resolve(&cli.overrides).expect("解析运行时路径失败"));
+    run_daemon(paths);
+}
+
+/// 守护进程主流程：启动控制socket线程，主线程运行事件循环直到收到退出信号
+fn run_daemon(paths: Arc<Paths>) {
     let main_thread_name = utils::get_current_thread_name();
     info!("[{}] 启动FreePPS", main_thread_name);
 
+    // 读取运行配置（阈值、开关与日志级别），运行中由事件循环监控并重新加载
+    let config = Arc::new(
+        ConfigStore::load(&paths)
+            .or_else(|e| {
+                error!("读取配置文件失败，使用默认配置: {}", e);
+                ConfigStore::with_config(paths.config_file().to_path_buf(), Config::default())
+            })
+            .expect("创建运行配置失败"),
+    );
+    log::set_max_level(config.current().log_level);
+
+    // 注册全部充电解锁后端（qcom / mtk）
+    let backends = pd::charger_backends(&paths, &config).expect("创建充电解锁后端失败");
+
     // 创建管理器实例
-    let module_manager = Arc::new(ModuleManager::new().expect("创建模块管理器失败"));
+    let module_manager =
+        Arc::new(ModuleManager::new(Arc::clone(&paths), backends).expect("创建模块管理器失败"));
 
     // 初始化阶段：确保基础文件存在并设置初始状态
     if let Err(e) = module_manager.initialize_module() {
         error!("模块初始化失败: {}", e);
     }
 
-    // 创建运行标志
-    let running = Arc::new(AtomicBool::new(true));
-    install_signal_handlers(&running);
-
-    let pd_verifier = Arc::new(PdVerifier::new().expect("创建PD验证器失败"));
-    let pd_adapter_verifier = Arc::new(PdAdapterVerifier::new().expect("创建PD适配器验证器失败"));
-
-    let free_enabled = Arc::new(AtomicBool::new(
-        monitoring::FileMonitor::read_file_content(FREE_FILE).unwrap_or_else(|_| "0".to_string())
-            == "1",
-    ));
-
-    let mut thread_handles: Vec<thread::JoinHandle<()>> = Vec::new();
-
-    // 创建free文件监控线程
-    thread_handles.push(spawn_free_file_monitor(
-        Arc::clone(&running),
-        Arc::clone(&module_manager),
-        Arc::clone(&free_enabled),
-    ));
-
-    // 创建disable文件监控线程
-    thread_handles.push(spawn_disable_file_monitor(
-        Arc::clone(&running),
+    // 创建退出信号（SIGINT/SIGTERM 触发，唤醒所有阻塞在 epoll 的线程）
+    let shutdown = Arc::new(ShutdownSignal::new().expect("创建退出信号失败"));
+    install_signal_handlers(&shutdown);
+
+    let thread_handles: Vec<thread::JoinHandle<()>> = vec![
+        // 创建控制socket线程（status/enable/disable/toggle/reload/forge-now）
+        #[cfg(feature = "control-socket")]
+        spawn_control_server(
+            Arc::clone(&shutdown),
+            Arc::clone(&module_manager),
+            Arc::clone(&config),
+        ),
+    ];
+
+    // free/disable/配置文件、uevent、定时切换与会话期间的温控/遥测采样由事件循环在主线程处理，
+    // 阻塞于此直到退出信号
+    info!("[{}] 主线程开始运行事件循环...", main_thread_name);
+    let reactor = Reactor::new(
+        Arc::clone(&shutdown),
         Arc::clone(&module_manager),
-    ));
-
-    // 初始化时按节点存在性一次性创建 qcom/mtk 线程（不做后续轮询判断/重启）
-    if std::path::Path::new(PD_VERIFIED_PATH).exists() {
-        info!("检测到qcom节点存在，启动qcom线程: {}", PD_VERIFIED_PATH);
-        thread_handles.push(spawn_pd_verified_monitor(
-            Arc::clone(&running),
-            Arc::clone(&pd_verifier),
-            Arc::clone(&free_enabled),
-        ));
-    } else {
-        info!("qcom节点不存在，跳过qcom线程启动: {}", PD_VERIFIED_PATH);
-    }
-
-    if std::path::Path::new(PD_ADAPTER_VERIFIED_PATH).exists() {
-        info!(
-            "检测到mtk节点存在，启动mtk线程: {}",
-            PD_ADAPTER_VERIFIED_PATH
-        );
-        thread_handles.push(spawn_pd_adapter_verified_monitor(
-            Arc::clone(&running),
-            Arc::clone(&pd_adapter_verifier),
-            Arc::clone(&free_enabled),
-        ));
-    } else {
-        info!(
-            "mtk节点不存在，跳过mtk线程启动: {}",
-            PD_ADAPTER_VERIFIED_PATH
-        );
-    }
-
-    info!(
-        "[{}] 监控线程已按需启动（仅初始化判断一次），主线程park等待...",
-        main_thread_name
+        Arc::clone(&config),
+        Arc::new(NetlinkUevents),
     );
-    while running.load(std::sync::atomic::Ordering::Relaxed) {
-        thread::park_timeout(Duration::from_secs(1));
+    if let Err(e) = reactor.and_then(Reactor::run) {
+        error!("事件循环出错: {}", e);
     }
 
     info!("检测到退出信号，开始停止所有监控线程...");
-    running.store(false, std::sync::atomic::Ordering::Relaxed);
+    shutdown.trigger();
 
     for handle in thread_handles {
         if let Err(e) = handle.join() {
@@ -1,10 +1,21 @@
+#[cfg(unix)]
+pub mod charging;
 pub mod file_monitor;
 pub mod module_manager;
-pub mod threads;
+#[cfg(unix)]
+pub mod reactor;
+#[cfg(unix)]
+pub mod soc;
+#[cfg(unix)]
+pub mod telemetry;
+#[cfg(unix)]
+pub mod thermal;
+pub mod uevent;
 
 pub use file_monitor::FileMonitor;
 pub use module_manager::ModuleManager;
-pub use threads::{
-    spawn_disable_file_monitor, spawn_free_file_monitor, spawn_pd_adapter_verified_monitor,
-    spawn_pd_verified_monitor,
-};
+#[cfg(unix)]
+pub use reactor::Reactor;
+#[cfg(unix)]
+pub use uevent::NetlinkUevents;
+pub use uevent::{UeventAction, UeventFilter, UeventSource};
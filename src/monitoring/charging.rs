@@ -0,0 +1,267 @@
+//! 充电会话处理：把 power_supply uevent 送入会话状态机，并按 free 模式与充电头规则写解锁节点
+//!
+//! 由事件循环（[`crate::monitoring::Reactor`]）对每个已检测到的后端调用，
+//! 轨迹回放（[`crate::trace::replay`]）复用同一套逻辑。
+
+use std::time::SystemTime;
+
+use anyhow::Result;
+use log::{debug, info, warn};
+
+use crate::common::{FreeMode, Paths};
+use crate::monitoring::uevent::Uevent;
+use crate::monitoring::{FileMonitor, UeventFilter};
+use crate::pd::ChargerBackend;
+use crate::policy::adapters;
+use crate::session::{AdapterInfo, AdapterKind, SessionEvent, SharedSession};
+
+/// 事件循环处理的充电 uevent：只关心电池与 USB 口的 power_supply 事件
+pub fn power_supply_filter() -> UeventFilter {
+    UeventFilter::new()
+        .subsystem("power_supply")
+        .names(["battery", "usb"])
+}
+
+/// 处理一条已通过订阅过滤的 power_supply uevent：推进充电会话，必要时写回解锁节点
+///
+/// `mode` 为 auto 时不预先解锁：插电后按充电头 SVID 决定是否解锁，拔出后写回 0 恢复原厂行为。
+pub fn handle_power_supply_uevent(
+    uevent: &Uevent,
+    session: &SharedSession,
+    paths: &Paths,
+    backend: &dyn ChargerBackend,
+    mode: FreeMode,
+) -> Result<()> {
+    let name = backend.name();
+    let status = uevent.power_supply_status();
+    debug!(
+        "[{}] uevent#{} {}: status={:?}",
+        name,
+        uevent.seqnum().unwrap_or_default(),
+        uevent.name(),
+        status
+    );
+
+    let mut should_set_node = false;
+
+    if mode == FreeMode::On && backend.rearm_on_power_supply_event() {
+        debug!(
+            "[{}] 锁定PPS模式：检测到{}的POWER_SUPPLY事件",
+            name,
+            uevent.name()
+        );
+        should_set_node = true;
+    }
+
+    // 充电过程中不强制写入节点：
+    // - 小米原装充电头：内核通过verify_process自行管理pd_verifed
+    //   （verify结束后内核自己设pd_verifed=1），反复写入会干扰MIPPS握手
+    // - 公版PPS充电头：内核不碰pd_verifed，依赖启动时设置的值
+    // 仅在拔出(Discharging)时写回节点，为下次插电准备
+    match status {
+        Some("Discharging") if feed_session(session, paths, name, SessionEvent::Discharging) => {
+            if mode == FreeMode::Auto {
+                relock_for_auto(backend)?;
+            } else {
+                info!(
+                    "[{}] 检测到Charging→Discharging状态跳变，写回解锁节点为下次插电准备",
+                    name
+                );
+                should_set_node = true;
+            }
+        }
+        Some("Charging") => feed_charging(session, paths, backend, mode)?,
+        _ => {}
+    }
+
+    if should_set_node && backend.read_state()? == Some(false) {
+        info!("[{}] 设置解锁节点为1", name);
+        backend.unlock()?;
+    }
+
+    Ok(())
+}
+
+/// 可能错过了 power_supply 事件（uevent 接收缓冲区溢出、free=0 期间暂停跟踪）后的重新同步
+///
+/// 错过的可能正是 Charging→Discharging 跳变（导致拔出后会话未结束、解锁节点未写回），
+/// 因此以 battery/status 与解锁节点的当前值为准重建会话状态，并补做拔出时的写回。
+pub fn resync(
+    session: &SharedSession,
+    paths: &Paths,
+    backend: &dyn ChargerBackend,
+    mode: FreeMode,
+) -> Result<()> {
+    let name = backend.name();
+    let status = FileMonitor::read_file_content(paths.battery_status()).unwrap_or_default();
+    warn!(
+        "[{}] 可能错过了充电事件，按当前状态重新同步: battery={}",
+        name, status
+    );
+
+    match status.as_str() {
+        "Charging" => feed_charging(session, paths, backend, mode)?,
+        "Discharging" => {
+            feed_session(session, paths, name, SessionEvent::Discharging);
+            if mode == FreeMode::Auto {
+                relock_for_auto(backend)?;
+            } else if backend.read_state()? == Some(false) {
+                info!("[{}] 重新同步：已拔出且解锁节点为0，设置解锁节点为1", name);
+                backend.unlock()?;
+            }
+        }
+        _ => {}
+    }
+
+    Ok(())
+}
+
+/// 送入 Charging 事件并按充电头规则 / auto 模式选择，记录此时解锁节点是否已生效（会话历史中的"解锁"）
+pub fn feed_charging(
+    session: &SharedSession,
+    paths: &Paths,
+    backend: &dyn ChargerBackend,
+    mode: FreeMode,
+) -> Result<()> {
+    feed_session(session, paths, backend.name(), SessionEvent::Charging);
+    apply_adapter_policy(session, paths, backend, mode)?;
+    if matches!(backend.read_state(), Ok(Some(true))) {
+        session.lock().unwrap().mark_unlock_applied();
+    }
+    Ok(())
+}
+
+/// 按当前充电头选择是否解锁
+///
+/// - `adapters.conf` 中记录了该充电头的决定：`unlock` 时解锁，`lock` 时写回 0
+/// - 否则 free=auto：公版充电头（SVID=0000）解锁，小米充电头保持原生 MIPPS 路径，不写节点
+/// - 否则（free=1）维持插电前已写入的解锁
+///
+/// SVID 尚未上报时不做决定，等待后续 uevent 刷新充电头信息。
+pub fn apply_adapter_policy(
+    session: &SharedSession,
+    paths: &Paths,
+    backend: &dyn ChargerBackend,
+    mode: FreeMode,
+) -> Result<()> {
+    let (active, adapter) = {
+        let session = session.lock().unwrap();
+        (session.is_active(), session.adapter().clone())
+    };
+    if !active {
+        return Ok(());
+    }
+
+    let name = backend.name();
+    match adapters::lookup(paths.adapter_rules(), &adapter).and_then(|rule| rule.unlock) {
+        Some(true) if backend.read_state()? == Some(false) => {
+            info!(
+                "[{}] 充电头{}规则为始终解锁，设置解锁节点为1",
+                name,
+                adapters::fingerprint(&adapter).unwrap_or_default()
+            );
+            backend.unlock()?;
+        }
+        Some(false) if backend.read_state()? == Some(true) => {
+            info!(
+                "[{}] 充电头{}规则为从不解锁，写回解锁节点为0",
+                name,
+                adapters::fingerprint(&adapter).unwrap_or_default()
+            );
+            backend.relock()?;
+        }
+        Some(_) => {}
+        None if mode != FreeMode::Auto => {}
+        None => match adapter.kind() {
+            Some(AdapterKind::Public) if backend.read_state()? == Some(false) => {
+                info!(
+                    "[{}] auto：公版充电头(SVID=0000, {})，设置解锁节点为1",
+                    name,
+                    adapter.real_type.as_deref().unwrap_or("-")
+                );
+                backend.unlock()?;
+            }
+            Some(AdapterKind::Xiaomi) => debug!(
+                "[{}] auto：小米充电头(SVID={})，保持原生MIPPS路径",
+                name,
+                adapter.svid.as_deref().unwrap_or("-")
+            ),
+            _ => {}
+        },
+    }
+    Ok(())
+}
+
+/// 充电中切换为 free=1：按充电头规则处理，无"从不解锁"规则时立即解锁
+pub fn unlock_active_session(
+    session: &SharedSession,
+    paths: &Paths,
+    backend: &dyn ChargerBackend,
+) -> Result<()> {
+    let adapter = session.lock().unwrap().adapter().clone();
+    if adapters::lookup(paths.adapter_rules(), &adapter).and_then(|rule| rule.unlock) == Some(false)
+    {
+        return apply_adapter_policy(session, paths, backend, FreeMode::On);
+    }
+    if backend.read_state()? == Some(false) {
+        info!("[{}] free=1：设置解锁节点为1", backend.name());
+        backend.unlock()?;
+    }
+    if matches!(backend.read_state(), Ok(Some(true))) {
+        session.lock().unwrap().mark_unlock_applied();
+    }
+    Ok(())
+}
+
+/// free=auto 拔出后：解锁节点写回 0，下次插电先走原生路径，识别充电头后再决定
+fn relock_for_auto(backend: &dyn ChargerBackend) -> Result<()> {
+    if backend.read_state()? == Some(true) {
+        info!(
+            "[{}] auto：已拔出，写回解锁节点为0等待识别下一个充电头",
+            backend.name()
+        );
+        backend.relock()?;
+    }
+    Ok(())
+}
+
+/// 把事件送入充电会话状态机并记录状态跳变，返回是否发生跳变
+///
+/// 会话中的每个事件之后都会重新读取充电头信息，推动 Plugged → Negotiating → PpsActive。
+pub fn feed_session(
+    session: &SharedSession,
+    paths: &Paths,
+    name: &str,
+    event: SessionEvent,
+) -> bool {
+    let mut session = session.lock().unwrap();
+    let now = SystemTime::now();
+    let mut changed = false;
+
+    if let Some(transition) = session.handle(event, now) {
+        info!(
+            "[{}] 充电会话#{}: {} → {}",
+            name,
+            session.id(),
+            transition.from.as_str(),
+            transition.to.as_str()
+        );
+        changed = true;
+    }
+
+    if session.is_active()
+        && let Some(transition) =
+            session.handle(SessionEvent::Adapter(AdapterInfo::read(paths)), now)
+    {
+        info!(
+            "[{}] 充电会话#{}: {} → {} ({:?})",
+            name,
+            session.id(),
+            transition.from.as_str(),
+            transition.to.as_str(),
+            session.adapter()
+        );
+    }
+
+    changed
+}
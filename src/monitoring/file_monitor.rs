@@ -1,19 +1,33 @@
 use crate::common::FreePPSError;
+#[cfg(unix)]
+use crate::platform::ShutdownSignal;
 use anyhow::Result;
+#[cfg(unix)]
+use log::{info, warn};
 use std::fs;
 use std::path::Path;
 
 #[cfg(unix)]
 use libc::c_int;
 #[cfg(unix)]
+use std::ffi::{OsStr, OsString};
+#[cfg(unix)]
 use std::io;
 #[cfg(unix)]
-use std::os::raw::c_char;
+use std::path::PathBuf;
+
+#[cfg(unix)]
+use crate::common::constants::{
+    IN_CLOSE_WRITE, IN_CREATE, IN_DELETE, IN_DELETE_SELF, IN_IGNORED, IN_MOVE_SELF, IN_MOVED_FROM,
+    IN_MOVED_TO, IN_Q_OVERFLOW,
+};
 
-/// 文件监控器
+// uevent socket 接收缓冲区：插拔风暴时默认缓冲区（通常 ~200KB）可能溢出丢事件
+#[cfg(unix)]
+const UEVENT_RCVBUF_SIZE: c_int = 4 * 1024 * 1024;
+
+/// 文件监控器：epoll 集合与文件读写工具（inotify 由 [`InotifyWatcher`] 负责）
 pub struct FileMonitor {
-    #[cfg(unix)]
-    pub inotify_fd: c_int,
     #[cfg(unix)]
     epoll_fd: c_int,
 }
@@ -23,33 +37,22 @@ impl FileMonitor {
     pub fn new() -> Result<Self> {
         // 外部函数声明（仅Unix）
         unsafe extern "C" {
-            fn inotify_init() -> c_int;
             fn epoll_create1(flags: c_int) -> c_int;
         }
 
-        let inotify_fd = unsafe { inotify_init() };
-        if inotify_fd == -1 {
-            return Err(FreePPSError::InotifyError("无法初始化inotify".to_string()).into());
-        }
-
         // 创建epoll实例
         let epoll_fd = unsafe { epoll_create1(0) };
         if epoll_fd == -1 {
-            unsafe {
-                libc::close(inotify_fd);
-            }
             return Err(FreePPSError::InotifyError("无法初始化epoll".to_string()).into());
         }
 
-        Ok(Self {
-            inotify_fd,
-            epoll_fd,
-        })
+        Ok(Self { epoll_fd })
     }
 
     /// 读取文件内容
-    pub fn read_file_content(path: &str) -> Result<String> {
-        if !Path::new(path).exists() {
+    pub fn read_file_content(path: impl AsRef<Path>) -> Result<String> {
+        let path = path.as_ref();
+        if !path.exists() {
             return Ok(String::new());
         }
 
@@ -62,33 +65,11 @@ impl FileMonitor {
     }
 
     /// 写入文件内容
-    pub fn write_file_content(path: &str, content: &str) -> Result<()> {
+    pub fn write_file_content(path: impl AsRef<Path>, content: &str) -> Result<()> {
         fs::write(path, content).map_err(FreePPSError::FileOperation)?;
         Ok(())
     }
 
-    /// 添加文件监控
-    #[cfg(unix)]
-    pub fn add_watch(&self, path: &str, mask: u32) -> Result<i32> {
-        use std::ffi::CString;
-
-        // 外部函数声明（仅Unix）
-        unsafe extern "C" {
-            fn inotify_add_watch(fd: c_int, pathname: *const c_char, mask: u32) -> c_int;
-        }
-
-        let path_cstring = CString::new(path)
-            .map_err(|e| FreePPSError::InotifyError(format!("路径转换失败: {}", e)))?;
-
-        let wd = unsafe { inotify_add_watch(self.inotify_fd, path_cstring.as_ptr(), mask) };
-
-        if wd == -1 {
-            return Err(FreePPSError::InotifyError(format!("无法监控文件: {}", path)).into());
-        }
-
-        Ok(wd)
-    }
-
     /// 将任意 fd 添加到 epoll（只应在初始化时调用一次）
     #[cfg(unix)]
     pub fn add_fd_to_epoll(&self, fd: c_int, events: u32, data: u64) -> Result<()> {
@@ -108,32 +89,10 @@ impl FileMonitor {
         Ok(())
     }
 
-    /// 将任意 fd 从 epoll 中移除（free=0 暂停时移除 uevent socket，避免无关唤醒）
+    /// 将退出信号的 eventfd 添加到 epoll，收到退出信号时立即唤醒 `wait_events`
     #[cfg(unix)]
-    pub fn remove_fd_from_epoll(&self, fd: c_int) -> Result<()> {
-        unsafe extern "C" {
-            fn epoll_ctl(epfd: c_int, op: c_int, fd: c_int, event: *mut libc::epoll_event)
-            -> c_int;
-        }
-
-        let result =
-            unsafe { epoll_ctl(self.epoll_fd, libc::EPOLL_CTL_DEL, fd, std::ptr::null_mut()) };
-
-        if result == -1 {
-            return Err(FreePPSError::InotifyError("无法将fd从epoll移除".to_string()).into());
-        }
-
-        Ok(())
-    }
-
-    /// 将 inotify_fd 添加到 epoll（只应在初始化时调用一次）
-    #[cfg(unix)]
-    pub fn add_inotify_to_epoll(&self) -> Result<()> {
-        self.add_fd_to_epoll(
-            self.inotify_fd,
-            libc::EPOLLIN as u32,
-            self.inotify_fd as u64,
-        )
+    pub fn add_shutdown_to_epoll(&self, shutdown: &ShutdownSignal) -> Result<()> {
+        self.add_fd_to_epoll(shutdown.fd(), libc::EPOLLIN as u32, shutdown.fd() as u64)
     }
 
     /// 等待 epoll 事件
@@ -193,6 +152,25 @@ impl FileMonitor {
                 return Err(FreePPSError::InotifyError("无法绑定uevent socket".to_string()).into());
             }
 
+            // 扩大接收缓冲区：SO_RCVBUFFORCE 不受 rmem_max 限制（需 CAP_NET_ADMIN），
+            // 失败时退回受限的 SO_RCVBUF；均失败时沿用默认值，溢出由调用方重新同步兜底
+            let size = UEVENT_RCVBUF_SIZE;
+            let set_rcvbuf = |option| {
+                libc::setsockopt(
+                    sock,
+                    libc::SOL_SOCKET,
+                    option,
+                    &size as *const c_int as *const libc::c_void,
+                    mem::size_of::<c_int>() as u32,
+                ) == 0
+            };
+            if !set_rcvbuf(libc::SO_RCVBUFFORCE) && !set_rcvbuf(libc::SO_RCVBUF) {
+                warn!(
+                    "设置uevent socket接收缓冲区失败，使用默认大小: {}",
+                    io::Error::last_os_error()
+                );
+            }
+
             Ok(sock)
         }
     }
@@ -207,17 +185,390 @@ impl Drop for FileMonitor {
                 fn close(fd: c_int) -> c_int;
             }
 
-            if self.inotify_fd != -1 {
+            if self.epoll_fd != -1 {
                 unsafe {
-                    close(self.inotify_fd);
+                    close(self.epoll_fd);
                 }
             }
+        }
+    }
+}
 
-            if self.epoll_fd != -1 {
+/// 目录 watch 的事件掩码：文件写入完成、创建/删除、改名进出，以及目录自身被删除或移动
+#[cfg(unix)]
+const DIR_WATCH_MASK: u32 = IN_CLOSE_WRITE
+    | IN_CREATE
+    | IN_DELETE
+    | IN_MOVED_FROM
+    | IN_MOVED_TO
+    | IN_DELETE_SELF
+    | IN_MOVE_SELF;
+
+/// [`InotifyWatcher::watch_file`] 返回的文件标识，用于分发 [`FileEvent`]
+#[cfg(unix)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WatchId(usize);
+
+/// 被监控文件的变化
+#[cfg(unix)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FileChange {
+    /// 写入完成（IN_CLOSE_WRITE）
+    Written,
+    /// 文件出现：新建或由改名替换（IN_CREATE / IN_MOVED_TO）
+    Created,
+    /// 文件消失：删除或被改名移走（IN_DELETE / IN_MOVED_FROM）
+    Removed,
+    /// 事件可能已丢失（队列溢出、watch 重新建立），应按文件当前状态重新处理
+    Rescan,
+}
+
+/// 一条已分发到具体文件的事件
+#[cfg(unix)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FileEvent {
+    pub id: WatchId,
+    pub change: FileChange,
+}
+
+/// 从 inotify 读出的一条原始事件（`name` 为目录 watch 上的文件名）
+#[cfg(unix)]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InotifyEvent {
+    pub wd: c_int,
+    pub mask: u32,
+    pub name: Option<OsString>,
+}
+
+#[cfg(unix)]
+impl InotifyEvent {
+    /// 是否包含 `mask` 中的任一事件位
+    pub fn has(&self, mask: u32) -> bool {
+        self.mask & mask != 0
+    }
+}
+
+/// 解析一次 `read` 得到的 inotify 缓冲区
+///
+/// 缓冲区中的事件头不保证对齐，逐个以非对齐读取复制出来；截断的尾部被忽略。
+#[cfg(unix)]
+pub fn parse_inotify_events(buffer: &[u8]) -> Vec<InotifyEvent> {
+    let header_size = std::mem::size_of::<libc::inotify_event>();
+    let mut events = Vec::new();
+    let mut offset = 0usize;
+    while offset + header_size <= buffer.len() {
+        // SAFETY: 上面已检查剩余长度不小于事件头，read_unaligned 不要求对齐
+        let header = unsafe {
+            std::ptr::read_unaligned(buffer.as_ptr().add(offset) as *const libc::inotify_event)
+        };
+        let name_start = offset + header_size;
+        let name_end = name_start + header.len as usize;
+        if name_end > buffer.len() {
+            break;
+        }
+        // 文件名以 NUL 结尾并按对齐补齐 NUL
+        let name = &buffer[name_start..name_end];
+        let name = &name[..name.iter().position(|&b| b == 0).unwrap_or(name.len())];
+        events.push(InotifyEvent {
+            wd: header.wd,
+            mask: header.mask,
+            name: (!name.is_empty()).then(|| {
+                use std::os::unix::ffi::OsStrExt;
+                OsStr::from_bytes(name).to_os_string()
+            }),
+        });
+        offset = name_end;
+    }
+    events
+}
+
+/// 被监控的目录（`wd` 为 `None` 表示 watch 已丢失，等待重新建立）
+#[cfg(unix)]
+struct WatchedDir {
+    path: PathBuf,
+    wd: Option<c_int>,
+}
+
+/// 被监控的文件：所在目录在 `dirs` 中的下标与文件名
+#[cfg(unix)]
+struct WatchedFile {
+    dir: usize,
+    name: OsString,
+}
+
+/// inotify 封装：按文件注册，实际监控文件所在目录
+///
+/// 直接监控文件时，文件被删除或被改名替换（编辑器、`mv` 的原子替换）后 watch 随之失效且不再有事件；
+/// 监控所在目录则文件的新建、替换与删除都能收到，同一目录下的多个文件共用一个 watch。
+/// 目录本身被删除或移动时 watch 丢失（IN_IGNORED），由 [`InotifyWatcher::rearm`] 重新建立，
+/// 重新建立后对该目录下的文件补发 [`FileChange::Rescan`]。
+#[cfg(unix)]
+pub struct InotifyWatcher {
+    fd: c_int,
+    dirs: Vec<WatchedDir>,
+    files: Vec<WatchedFile>,
+    buffer: [u8; 4096],
+}
+
+#[cfg(unix)]
+impl InotifyWatcher {
+    pub fn new() -> Result<Self> {
+        let fd = unsafe { libc::inotify_init1(libc::IN_NONBLOCK | libc::IN_CLOEXEC) };
+        if fd == -1 {
+            return Err(FreePPSError::InotifyError("无法初始化inotify".to_string()).into());
+        }
+        Ok(Self {
+            fd,
+            dirs: Vec::new(),
+            files: Vec::new(),
+            buffer: [0; 4096],
+        })
+    }
+
+    /// inotify fd（非阻塞），用于注册到 epoll
+    pub fn fd(&self) -> c_int {
+        self.fd
+    }
+
+    /// 监控文件的变化；文件可以尚不存在，所在目录必须存在
+    pub fn watch_file(&mut self, path: impl AsRef<Path>) -> Result<WatchId> {
+        let path = path.as_ref();
+        let (Some(dir), Some(name)) = (path.parent(), path.file_name()) else {
+            return Err(FreePPSError::InvalidArgument(format!(
+                "无法监控没有所在目录的路径: {}",
+                path.display()
+            ))
+            .into());
+        };
+
+        let dir = match self.dirs.iter().position(|watched| watched.path == dir) {
+            Some(index) => index,
+            None => {
+                let wd = self.add_watch(dir)?;
+                self.dirs.push(WatchedDir {
+                    path: dir.to_path_buf(),
+                    wd: Some(wd),
+                });
+                self.dirs.len() - 1
+            }
+        };
+        self.files.push(WatchedFile {
+            dir,
+            name: name.to_os_string(),
+        });
+        Ok(WatchId(self.files.len() - 1))
+    }
+
+    /// 是否有目录 watch 丢失（此时应定期调用 [`Self::rearm`]）
+    pub fn has_lost_watches(&self) -> bool {
+        self.dirs.iter().any(|dir| dir.wd.is_none())
+    }
+
+    /// 尝试重新建立丢失的目录 watch，返回重新建立的目录下各文件的 [`FileChange::Rescan`]
+    pub fn rearm(&mut self) -> Vec<FileEvent> {
+        let mut events = Vec::new();
+        for index in 0..self.dirs.len() {
+            if self.dirs[index].wd.is_some() {
+                continue;
+            }
+            let Ok(wd) = self.add_watch(&self.dirs[index].path) else {
+                continue;
+            };
+            info!("已重新监控目录: {}", self.dirs[index].path.display());
+            self.dirs[index].wd = Some(wd);
+            events.extend(self.rescan_dir(index));
+        }
+        events
+    }
+
+    /// 读取并分发全部待处理事件（读到 EAGAIN 为止）
+    pub fn read_events(&mut self) -> io::Result<Vec<FileEvent>> {
+        let mut raw = Vec::new();
+        loop {
+            let bytes_read = unsafe {
+                libc::read(
+                    self.fd,
+                    self.buffer.as_mut_ptr() as *mut libc::c_void,
+                    self.buffer.len(),
+                )
+            };
+            if bytes_read == -1 {
+                let err = io::Error::last_os_error();
+                match err.raw_os_error() {
+                    Some(code) if code == libc::EAGAIN => break,
+                    Some(code) if code == libc::EINTR => continue,
+                    _ => return Err(err),
+                }
+            }
+            if bytes_read == 0 {
+                break;
+            }
+            raw.extend(parse_inotify_events(&self.buffer[..bytes_read as usize]));
+        }
+
+        let mut events = Vec::new();
+        for event in raw {
+            events.extend(self.dispatch(&event));
+        }
+        // 目录 watch 在本批事件中丢失时立即尝试重新建立（目录可能已被重新创建）
+        if self.has_lost_watches() {
+            events.extend(self.rearm());
+        }
+        Ok(events)
+    }
+
+    /// 把一条原始事件转换为所在目录下各被监控文件的事件
+    fn dispatch(&mut self, event: &InotifyEvent) -> Vec<FileEvent> {
+        if event.has(IN_Q_OVERFLOW) {
+            warn!("inotify事件队列溢出，重新检查全部监控文件");
+            return (0..self.files.len())
+                .map(|index| FileEvent {
+                    id: WatchId(index),
+                    change: FileChange::Rescan,
+                })
+                .collect();
+        }
+
+        let Some(dir) = self.dirs.iter().position(|dir| dir.wd == Some(event.wd)) else {
+            return Vec::new();
+        };
+
+        if event.has(IN_IGNORED) || event.has(IN_MOVE_SELF) {
+            // IN_IGNORED：目录被删除或所在文件系统被卸载，watch 已被内核移除；
+            // IN_MOVE_SELF：目录被移走，watch 跟随新位置，不再对应原路径，主动移除
+            if event.has(IN_MOVE_SELF) {
                 unsafe {
-                    close(self.epoll_fd);
+                    libc::inotify_rm_watch(self.fd, event.wd);
                 }
             }
+            warn!(
+                "目录监控已失效，等待重新建立: {}",
+                self.dirs[dir].path.display()
+            );
+            self.dirs[dir].wd = None;
+            return Vec::new();
+        }
+
+        let change = if event.has(IN_CLOSE_WRITE) {
+            FileChange::Written
+        } else if event.has(IN_CREATE | IN_MOVED_TO) {
+            FileChange::Created
+        } else if event.has(IN_DELETE | IN_MOVED_FROM) {
+            FileChange::Removed
+        } else {
+            return Vec::new();
+        };
+        let Some(name) = &event.name else {
+            return Vec::new();
+        };
+        self.files
+            .iter()
+            .enumerate()
+            .filter(|(_, file)| file.dir == dir && file.name == *name)
+            .map(|(index, _)| FileEvent {
+                id: WatchId(index),
+                change,
+            })
+            .collect()
+    }
+
+    fn rescan_dir(&self, dir: usize) -> Vec<FileEvent> {
+        self.files
+            .iter()
+            .enumerate()
+            .filter(|(_, file)| file.dir == dir)
+            .map(|(index, _)| FileEvent {
+                id: WatchId(index),
+                change: FileChange::Rescan,
+            })
+            .collect()
+    }
+
+    fn add_watch(&self, dir: &Path) -> Result<c_int> {
+        use std::ffi::CString;
+        use std::os::unix::ffi::OsStrExt;
+
+        let path_cstring = CString::new(dir.as_os_str().as_bytes())
+            .map_err(|e| FreePPSError::InotifyError(format!("路径转换失败: {}", e)))?;
+        let wd = unsafe { libc::inotify_add_watch(self.fd, path_cstring.as_ptr(), DIR_WATCH_MASK) };
+        if wd == -1 {
+            return Err(
+                FreePPSError::InotifyError(format!("无法监控目录: {}", dir.display())).into(),
+            );
+        }
+        Ok(wd)
+    }
+}
+
+#[cfg(unix)]
+impl Drop for InotifyWatcher {
+    fn drop(&mut self) {
+        unsafe {
+            libc::close(self.fd);
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::FakeSysfs;
+
+    fn changes(watcher: &mut InotifyWatcher, id: WatchId) -> Vec<FileChange> {
+        watcher
+            .read_events()
+            .unwrap()
+            .into_iter()
+            .filter(|event| event.id == id)
+            .map(|event| event.change)
+            .collect()
+    }
+
+    #[test]
+    fn watch_survives_replacement_and_directory_recreation() {
+        let sysfs = FakeSysfs::new();
+        let dir = sysfs.paths().module_dir().join("watched");
+        fs::create_dir_all(&dir).unwrap();
+        let file = dir.join("free");
+        let mut watcher = InotifyWatcher::new().unwrap();
+        let id = watcher.watch_file(&file).unwrap();
+        let other = watcher.watch_file(dir.join("disable")).unwrap();
+
+        fs::write(&file, "1").unwrap();
+        assert_eq!(
+            changes(&mut watcher, id),
+            [FileChange::Created, FileChange::Written]
+        );
+
+        // 原子替换：写临时文件后改名，直接监控文件时此后不再有事件
+        fs::write(dir.join("free.tmp"), "0").unwrap();
+        fs::rename(dir.join("free.tmp"), &file).unwrap();
+        assert_eq!(changes(&mut watcher, id), [FileChange::Created]);
+        fs::write(&file, "1").unwrap();
+        assert_eq!(changes(&mut watcher, id), [FileChange::Written]);
+
+        fs::remove_file(&file).unwrap();
+        assert_eq!(changes(&mut watcher, id), [FileChange::Removed]);
+
+        // 目录被删除后 watch 丢失，目录重新出现后重新建立并补发 Rescan
+        fs::remove_dir(&dir).unwrap();
+        assert!(changes(&mut watcher, id).is_empty());
+        assert!(watcher.has_lost_watches());
+        fs::create_dir_all(&dir).unwrap();
+        let rearmed = watcher.rearm();
+        assert!(!watcher.has_lost_watches());
+        assert!(rearmed.contains(&FileEvent {
+            id,
+            change: FileChange::Rescan
+        }));
+        assert!(rearmed.contains(&FileEvent {
+            id: other,
+            change: FileChange::Rescan
+        }));
+        fs::write(&file, "1").unwrap();
+        assert_eq!(
+            changes(&mut watcher, id),
+            [FileChange::Created, FileChange::Written]
+        );
+    }
+}
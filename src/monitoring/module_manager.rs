@@ -1,89 +1,240 @@
-use crate::common::FreePPSError;
-#[cfg(unix)]
-use crate::common::constants::BATTERY_STATUS_PATH;
-#[cfg(unix)]
-use crate::common::constants::MODULE_PROP;
-#[cfg(unix)]
-use crate::common::constants::PD_ADAPTER_VERIFIED_PATH;
-use crate::common::constants::{DISABLE_FILE, FREE_FILE, PD_VERIFIED_PATH};
+use crate::common::{FreeMode, FreePPSError, Paths};
 use crate::monitoring::FileMonitor;
-#[cfg(unix)]
-use crate::pd::PdAdapterVerifier;
-use crate::pd::PdVerifier;
+use crate::pd::ChargerBackend;
+use crate::policy::{GuardedBackend, HoldReason, UnlockHolds};
+use crate::session::{AdapterKind, ChargingSession, SharedSession};
 use anyhow::Result;
 use log::{info, warn};
 use std::fs;
-use std::path::Path;
-use std::sync::Mutex;
+use std::sync::{Arc, Mutex};
+
+// module.prop 描述的状态前缀
+#[cfg(unix)]
+const PAUSED_PREFIX: &str = "[⏸️PPS已暂停💤] ";
+#[cfg(unix)]
+const ENABLED_PREFIX: &str = "[✅锁定PPS支持⚡] ";
+#[cfg(unix)]
+const AUTO_PREFIX: &str = "[🔀自动选择PPS/MIPPS] ";
+// 多个策略暂停说明之间的分隔符
+#[cfg(unix)]
+const HOLD_SEPARATOR: &str = "，";
 
 /// 模块状态管理器
 pub struct ModuleManager {
+    paths: Arc<Paths>,
+    backends: Vec<Arc<dyn ChargerBackend>>,
+    // 当前充电会话（由事件循环驱动）
+    session: SharedSession,
+    // 策略暂停解锁的原因（温控等）
+    holds: Arc<UnlockHolds>,
     // 缓存最后一次处理的状态
     last_state: Mutex<String>,
 }
 
 impl ModuleManager {
-    pub fn new() -> Result<Self> {
+    /// 各后端包装为 [`GuardedBackend`]，策略暂停期间的解锁写入一律被拦截
+    pub fn new(paths: Arc<Paths>, backends: Vec<Arc<dyn ChargerBackend>>) -> Result<Self> {
+        let holds = UnlockHolds::shared();
+        let backends = backends
+            .into_iter()
+            .map(|backend| {
+                Arc::new(GuardedBackend::new(backend, Arc::clone(&holds)))
+                    as Arc<dyn ChargerBackend>
+            })
+            .collect();
         Ok(Self {
+            paths,
+            backends,
+            session: ChargingSession::shared(),
+            holds,
             last_state: Mutex::new(String::new()),
         })
     }
 
+    /// 运行时路径
+    pub fn paths(&self) -> &Arc<Paths> {
+        &self.paths
+    }
+
+    /// 已注册的充电解锁后端（受策略约束）
+    pub fn backends(&self) -> &[Arc<dyn ChargerBackend>] {
+        &self.backends
+    }
+
+    /// 当前充电会话
+    pub fn session(&self) -> &SharedSession {
+        &self.session
+    }
+
+    /// 当前生效的解锁暂停
+    pub fn holds(&self) -> &Arc<UnlockHolds> {
+        &self.holds
+    }
+
+    /// 策略暂停解锁：登记原因，free=1/auto 时把已检测到的解锁节点写回 0，并在 module.prop 中显示
+    #[cfg(unix)]
+    pub fn hold_unlock(&self, reason: HoldReason, label: &str) -> Result<()> {
+        if !self.holds.hold(reason, label) {
+            return Ok(());
+        }
+        warn!("策略暂停解锁: {}", label);
+
+        let mode = self.free_mode();
+        if mode.is_enabled() {
+            for backend in self.backends.iter().filter(|backend| backend.detect()) {
+                if let Err(e) = backend.relock() {
+                    warn!("暂停解锁时写回{}节点失败: {}", backend.name(), e);
+                }
+            }
+        }
+        self.update_module_description(mode)
+    }
+
+    /// 解除策略暂停：全部原因解除且 free=1（或 auto 且当前为公版充电头）时恢复解锁
+    #[cfg(unix)]
+    pub fn release_unlock(&self, reason: HoldReason) -> Result<()> {
+        if !self.holds.release(reason) {
+            return Ok(());
+        }
+
+        let mode = self.free_mode();
+        if self.holds.is_held() {
+            info!(
+                "{:?}暂停已解除，仍有其它暂停原因: {}",
+                reason,
+                self.holds.labels().join("，")
+            );
+        } else if self.should_unlock(mode) {
+            info!("{:?}暂停已解除，恢复解锁", reason);
+            for backend in self.backends.iter().filter(|backend| backend.detect()) {
+                if let Err(e) = backend.unlock() {
+                    warn!("恢复{}解锁节点失败: {}", backend.name(), e);
+                }
+            }
+        }
+        self.update_module_description(mode)
+    }
+
+    /// 当前模式下是否应解锁：free=1 总是解锁，auto 仅在充电会话中识别到公版充电头时解锁
+    #[cfg(unix)]
+    fn should_unlock(&self, mode: FreeMode) -> bool {
+        match mode {
+            FreeMode::On => true,
+            FreeMode::Auto => {
+                let session = self.session.lock().unwrap();
+                session.is_active() && session.adapter().kind() == Some(AdapterKind::Public)
+            }
+            FreeMode::Off => false,
+        }
+    }
+
+    /// 当前free文件表示的模式
+    pub fn free_mode(&self) -> FreeMode {
+        FreeMode::parse(&FileMonitor::read_file_content(self.paths.free_file()).unwrap_or_default())
+    }
+
+    /// 当前free文件是否为启用状态（1 或 auto）
+    pub fn is_free_enabled(&self) -> bool {
+        self.free_mode().is_enabled()
+    }
+
+    /// 写入free文件切换模式（与 action.sh 相同的控制通道，由 free 文件 inotify 监控生效）
+    pub fn set_free_mode(&self, mode: FreeMode) -> Result<()> {
+        FileMonitor::write_file_content(self.paths.free_file(), mode.as_str())?;
+        info!("已写入free文件: {}", mode.as_str());
+        self.remember_enabled_mode(mode);
+        Ok(())
+    }
+
+    /// 写入free文件切换启用状态；重新启用时恢复暂停前的模式（1 或 auto）
+    pub fn set_free_enabled(&self, enabled: bool) -> Result<()> {
+        self.set_free_mode(if enabled {
+            self.resume_mode()
+        } else {
+            FreeMode::Off
+        })
+    }
+
+    /// 翻转free文件状态，返回翻转后的模式
+    pub fn toggle_free(&self) -> Result<FreeMode> {
+        let enabled = !self.is_free_enabled();
+        self.set_free_enabled(enabled)?;
+        Ok(self.free_mode())
+    }
+
+    /// 暂停后重新启用时使用的模式：最近一次启用的模式，未记录时为 1
+    pub fn resume_mode(&self) -> FreeMode {
+        let content =
+            FileMonitor::read_file_content(self.paths.free_resume_file()).unwrap_or_default();
+        match FreeMode::parse(&content) {
+            FreeMode::Off => FreeMode::On,
+            mode => mode,
+        }
+    }
+
+    /// 记录启用模式，供定时切换、toggle 与 disable 文件删除后恢复（free=0 不记录）
+    fn remember_enabled_mode(&self, mode: FreeMode) {
+        if !mode.is_enabled() || self.resume_mode() == mode {
+            return;
+        }
+        if let Err(e) =
+            FileMonitor::write_file_content(self.paths.free_resume_file(), mode.as_str())
+        {
+            warn!("记录启用模式失败: {}", e);
+        }
+    }
+
+    /// 重新读取free文件并强制重新应用（忽略状态缓存，控制命令 reload）
+    #[cfg(all(unix, feature = "control-socket"))]
+    pub fn reload(&self) -> Result<()> {
+        self.last_state.lock().unwrap().clear();
+        let content = FileMonitor::read_file_content(self.paths.free_file())?;
+        info!("重新加载free文件状态: {}", content);
+        self.handle_free_file_change(&content)
+    }
+
     /// 初始化模块状态
     pub fn initialize_module(&self) -> Result<()> {
         info!("开始模块初始化...");
 
         // 确保free文件存在
-        if !Path::new(FREE_FILE).exists() {
+        if !self.paths.free_file().exists() {
             info!("free文件不存在，创建并设置为1");
-            FileMonitor::write_file_content(FREE_FILE, "1")?;
+            FileMonitor::write_file_content(self.paths.free_file(), "1")?;
         }
 
         // 确保disable文件不存在（模块启用状态）
-        if Path::new(DISABLE_FILE).exists() {
+        if self.paths.disable_file().exists() {
             info!("检测到disable文件，删除以启用模块");
-            fs::remove_file(DISABLE_FILE).map_err(FreePPSError::FileOperation)?;
+            fs::remove_file(self.paths.disable_file()).map_err(FreePPSError::FileOperation)?;
         }
 
         // 读取当前free文件状态并主动更新描述
-        let free_content = FileMonitor::read_file_content(FREE_FILE)?;
+        let free_content = FileMonitor::read_file_content(self.paths.free_file())?;
         info!("当前free文件内容: {}", free_content);
 
-        if free_content == "1" {
+        let mode = FreeMode::parse(&free_content);
+        self.remember_enabled_mode(mode);
+        if mode == FreeMode::On {
             info!("模块启用 - 锁定PPS支持模式");
             #[cfg(unix)]
-            self.update_module_description(true)?;
-
-            if Path::new(PD_VERIFIED_PATH).exists() {
-                info!("初始化：设置qcom节点为1");
-                match PdVerifier::new() {
-                    Ok(pd_verifier) => match pd_verifier.set_pd_verified(true) {
-                        Ok(_) => info!("qcom节点初始化成功"),
-                        Err(e) => warn!("设置qcom节点失败: {}", e),
-                    },
-                    Err(e) => warn!("创建PD验证器失败: {}", e),
-                }
-            }
+            self.update_module_description(mode)?;
 
-            #[cfg(unix)]
-            {
-                if Path::new(PD_ADAPTER_VERIFIED_PATH).exists() {
-                    info!("初始化：设置mtk节点为1");
-                    match PdAdapterVerifier::new() {
-                        Ok(pd_adapter_verifier) => {
-                            match pd_adapter_verifier.set_pd_adapter_verified(true) {
-                                Ok(_) => info!("mtk节点初始化成功"),
-                                Err(e) => warn!("设置mtk节点失败: {}", e),
-                            }
-                        }
-                        Err(e) => warn!("创建PD适配器验证器失败: {}", e),
-                    }
+            for backend in self.backends.iter().filter(|backend| backend.detect()) {
+                info!("初始化：设置{}节点为1", backend.name());
+                match backend.unlock() {
+                    Ok(_) => info!("{}节点初始化成功", backend.name()),
+                    Err(e) => warn!("设置{}节点失败: {}", backend.name(), e),
                 }
             }
         } else {
-            info!("模块已暂停（free=0）");
+            if mode == FreeMode::Auto {
+                info!("模块启用 - 按充电头自动选择PPS/MIPPS（free=auto）");
+            } else {
+                info!("模块已暂停（free=0）");
+            }
             #[cfg(unix)]
-            self.update_module_description(false)?;
+            self.update_module_description(mode)?;
             #[cfg(unix)]
             self.restore_pd_when_idle();
         }
@@ -93,42 +244,39 @@ impl ModuleManager {
     }
 
     /// 更新module.prop描述
+    ///
+    /// 描述前缀：暂停为 `[⏸️PPS已暂停💤]`，启用为 `[✅锁定PPS支持⚡]`，auto 为 `[🔀自动选择PPS/MIPPS]`，
+    /// 启用但被策略暂停解锁时显示暂停原因，如 `[🌡️温控暂停解锁(47.2℃)]`。
     #[cfg(unix)]
-    pub fn update_module_description(&self, enabled: bool) -> Result<()> {
-        let prop_content = FileMonitor::read_file_content(MODULE_PROP)?;
+    pub fn update_module_description(&self, mode: FreeMode) -> Result<()> {
+        let prop_content = FileMonitor::read_file_content(self.paths.module_prop())?;
 
-        let status_prefix = if !enabled {
-            "[⏸️PPS已暂停💤] "
+        let status_prefix = if !mode.is_enabled() {
+            PAUSED_PREFIX.to_string()
+        } else if self.holds.is_held() {
+            format!("[{}] ", self.holds.labels().join(HOLD_SEPARATOR))
+        } else if mode == FreeMode::Auto {
+            AUTO_PREFIX.to_string()
         } else {
-            "[✅锁定PPS支持⚡] "
+            ENABLED_PREFIX.to_string()
         };
 
         let updated_content = prop_content
             .lines()
-            .map(|line| {
-                if line.starts_with("description=") {
-                    let original_description = line.strip_prefix("description=").unwrap_or("");
-                    let clean_description =
-                        if original_description.starts_with("[✅锁定PPS支持⚡] ") {
-                            original_description
-                                .strip_prefix("[✅锁定PPS支持⚡] ")
-                                .unwrap_or(original_description)
-                        } else if original_description.starts_with("[⏸️PPS已暂停💤] ") {
-                            original_description
-                                .strip_prefix("[⏸️PPS已暂停💤] ")
-                                .unwrap_or(original_description)
-                        } else {
-                            original_description
-                        };
-                    format!("description={}{}", status_prefix, clean_description)
-                } else {
-                    line.to_string()
+            .map(|line| match line.strip_prefix("description=") {
+                Some(original_description) => {
+                    format!(
+                        "description={}{}",
+                        status_prefix,
+                        strip_status_prefix(original_description)
+                    )
                 }
+                None => line.to_string(),
             })
             .collect::<Vec<_>>()
             .join("\n");
 
-        FileMonitor::write_file_content(MODULE_PROP, &updated_content)?;
+        FileMonitor::write_file_content(self.paths.module_prop(), &updated_content)?;
         info!(
             "更新module.prop描述，添加状态前缀: {}",
             status_prefix.trim()
@@ -141,7 +289,7 @@ impl ModuleManager {
     #[cfg(unix)]
     fn restore_pd_when_idle(&self) {
         let battery_status =
-            FileMonitor::read_file_content(BATTERY_STATUS_PATH).unwrap_or_default();
+            FileMonitor::read_file_content(self.paths.battery_status()).unwrap_or_default();
         if battery_status != "Discharging" {
             info!(
                 "当前电池状态={}，free=0不动pd，交由内核/MIPPS自然握手",
@@ -150,30 +298,14 @@ impl ModuleManager {
             return;
         }
 
-        if Path::new(PD_VERIFIED_PATH).exists() {
-            match PdVerifier::new() {
-                Ok(pd_verifier) => match pd_verifier.set_pd_verified(false) {
-                    Ok(_) => {}
-                    Err(e) => warn!("设置PD验证状态失败: {}，跳过此步骤", e),
-                },
-                Err(e) => warn!("创建PD验证器失败: {}，跳过此步骤", e),
+        for backend in &self.backends {
+            if !backend.detect() {
+                warn!("{}解锁节点不存在，跳过恢复", backend.name());
+                continue;
             }
-        } else {
-            warn!("PD验证文件不存在，跳过恢复");
-        }
-
-        if Path::new(PD_ADAPTER_VERIFIED_PATH).exists() {
-            match PdAdapterVerifier::new() {
-                Ok(pd_adapter_verifier) => {
-                    match pd_adapter_verifier.set_pd_adapter_verified(false) {
-                        Ok(_) => {}
-                        Err(e) => warn!("设置PD适配器验证状态失败: {}，跳过此步骤", e),
-                    }
-                }
-                Err(e) => warn!("创建PD适配器验证器失败: {}，跳过此步骤", e),
+            if let Err(e) = backend.relock() {
+                warn!("还原{}解锁节点失败: {}，跳过此步骤", backend.name(), e);
             }
-        } else {
-            warn!("PD适配器验证文件不存在，跳过恢复");
         }
     }
 
@@ -194,39 +326,27 @@ impl ModuleManager {
         }
 
         info!("free文件内容: {}", content);
+        self.remember_enabled_mode(FreeMode::parse(content));
 
         if content == "1" {
             info!("free文件为1，启用锁定PPS支持模式");
-            self.update_module_description(true)?;
+            self.update_module_description(FreeMode::On)?;
 
-            // free=1 时设置pd_verifed=1（与initialize_module一致），解锁高功率PPS；
+            // free=1 时将各后端解锁节点置1（与initialize_module一致），解锁高功率PPS；
             // 否则free置1后pd保持旧值，下次插电可能无法解锁
-            if Path::new(PD_VERIFIED_PATH).exists() {
-                match PdVerifier::new() {
-                    Ok(pd_verifier) => match pd_verifier.set_pd_verified(true) {
-                        Ok(_) => {}
-                        Err(e) => warn!("设置PD验证状态失败: {}，跳过此步骤", e),
-                    },
-                    Err(e) => warn!("创建PD验证器失败: {}，跳过此步骤", e),
-                }
-            }
-            #[cfg(unix)]
-            {
-                if Path::new(PD_ADAPTER_VERIFIED_PATH).exists() {
-                    match PdAdapterVerifier::new() {
-                        Ok(pd_adapter_verifier) => {
-                            match pd_adapter_verifier.set_pd_adapter_verified(true) {
-                                Ok(_) => {}
-                                Err(e) => warn!("设置PD适配器验证状态失败: {}，跳过此步骤", e),
-                            }
-                        }
-                        Err(e) => warn!("创建PD适配器验证器失败: {}，跳过此步骤", e),
-                    }
+            for backend in self.backends.iter().filter(|backend| backend.detect()) {
+                if let Err(e) = backend.unlock() {
+                    warn!("设置{}解锁节点失败: {}，跳过此步骤", backend.name(), e);
                 }
             }
+        } else if content == "auto" {
+            info!("free文件为auto，按充电头自动选择PPS/MIPPS");
+            self.update_module_description(FreeMode::Auto)?;
+            // auto 时：未插电还原pd为0；是否解锁由事件循环在插电后按充电头 SVID 决定
+            self.restore_pd_when_idle();
         } else if content == "0" {
             info!("free文件为0，暂停模块");
-            self.update_module_description(false)?;
+            self.update_module_description(FreeMode::Off)?;
             // free=0 时：未插电还原pd为0，已插电不动pd（交由内核/MIPPS自然握手）
             self.restore_pd_when_idle();
         }
@@ -239,14 +359,86 @@ impl ModuleManager {
         if exists {
             info!("检测到disable文件创建");
             // disable文件出现，设置free为0
-            FileMonitor::write_file_content(FREE_FILE, "0")?;
+            FileMonitor::write_file_content(self.paths.free_file(), "0")?;
             info!("已处理disable文件创建事件");
         } else {
             info!("检测到disable文件删除");
-            // disable文件消失，设置free为1
-            FileMonitor::write_file_content(FREE_FILE, "1")?;
+            // disable文件消失，恢复暂停前的启用模式
+            FileMonitor::write_file_content(self.paths.free_file(), self.resume_mode().as_str())?;
             info!("已处理disable文件删除事件");
         }
         Ok(())
     }
 }
+
+/// 去掉描述开头由本模块添加的状态前缀，原描述本身以 `[` 开头时保持不变
+///
+/// 策略暂停前缀的方括号内是一个或多个以暂停原因图标开头的说明。
+#[cfg(unix)]
+fn strip_status_prefix(description: &str) -> &str {
+    if let Some(rest) = [PAUSED_PREFIX, ENABLED_PREFIX, AUTO_PREFIX]
+        .iter()
+        .find_map(|prefix| description.strip_prefix(prefix))
+    {
+        return rest;
+    }
+    description
+        .strip_prefix('[')
+        .and_then(|rest| rest.split_once("] "))
+        .filter(|(labels, _)| {
+            labels.split(HOLD_SEPARATOR).all(|label| {
+                HoldReason::ALL
+                    .iter()
+                    .any(|reason| label.starts_with(reason.icon()))
+            })
+        })
+        .map_or(description, |(_, rest)| rest)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::FakeSysfs;
+
+    #[test]
+    fn status_prefixes_written_by_module_are_stripped() {
+        assert_eq!(strip_status_prefix("[⏸️PPS已暂停💤] 解锁PPS"), "解锁PPS");
+        assert_eq!(
+            strip_status_prefix("[🔀自动选择PPS/MIPPS] 解锁PPS"),
+            "解锁PPS"
+        );
+        assert_eq!(
+            strip_status_prefix("[🌡️温控暂停解锁(47.2℃)，🔋电量80%≥80%暂停解锁] 解锁PPS"),
+            "解锁PPS"
+        );
+    }
+
+    #[test]
+    fn bracketed_original_description_is_kept() {
+        assert_eq!(strip_status_prefix("[Beta] 解锁PPS"), "[Beta] 解锁PPS");
+        assert_eq!(
+            strip_status_prefix("[✅锁定PPS支持⚡] [Beta] 解锁PPS"),
+            "[Beta] 解锁PPS"
+        );
+    }
+
+    #[test]
+    fn reenabling_restores_auto_mode() {
+        let sysfs = FakeSysfs::qcom();
+        let manager = ModuleManager::new(Arc::clone(sysfs.paths()), Vec::new()).unwrap();
+
+        manager.set_free_mode(FreeMode::Auto).unwrap();
+        assert_eq!(manager.toggle_free().unwrap(), FreeMode::Off);
+        assert_eq!(manager.toggle_free().unwrap(), FreeMode::Auto);
+
+        // 定时切换经 set_free_enabled 暂停/恢复
+        manager.set_free_enabled(false).unwrap();
+        manager.set_free_enabled(true).unwrap();
+        assert_eq!(manager.free_mode(), FreeMode::Auto);
+
+        manager.set_free_mode(FreeMode::On).unwrap();
+        manager.set_free_enabled(false).unwrap();
+        manager.set_free_enabled(true).unwrap();
+        assert_eq!(manager.free_mode(), FreeMode::On);
+    }
+}
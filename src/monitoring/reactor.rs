@@ -0,0 +1,707 @@
+//! 守护进程事件循环
+//!
+//! 所有文件、uevent 与定时事件都在主线程的同一个 epoll 集合上处理：
+//!
+//! - 一个 inotify（[`InotifyWatcher`]）：free、disable 与配置文件，监控所在目录，文件被替换或目录重建后仍有效
+//! - 一个 uevent socket：热插拔（后端节点出现/消失）与 power_supply 充电事件
+//! - 定时切换的 timerfd
+//! - 充电会话期间的周期采样 timerfd（温控、遥测），会话开始时启动、结束时停止
+//! - 配置变化通知 eventfd（配置文件被修改或控制命令 `reload` 重新加载后）
+//! - 退出信号 eventfd
+//!
+//! free 文件的变化与 uevent 在同一线程内按顺序处理，不存在多个线程各自读取 free 文件的时间差。
+//! 会阻塞的工作（`am` 广播）由后端的附属线程执行，事件循环只负责唤醒。
+
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, SystemTime};
+
+use anyhow::Result;
+use libc::c_int;
+use log::{debug, error, info, warn};
+
+use crate::common::{FreeMode, utils};
+use crate::config::ConfigStore;
+use crate::monitoring::charging::{
+    apply_adapter_policy, feed_charging, handle_power_supply_uevent, power_supply_filter, resync,
+    unlock_active_session,
+};
+use crate::monitoring::file_monitor::{FileChange, FileEvent, InotifyWatcher, WatchId};
+use crate::monitoring::soc::SocMonitor;
+use crate::monitoring::telemetry::TelemetryRecorder;
+use crate::monitoring::thermal::ThermalMonitor;
+use crate::monitoring::uevent::{is_overflow, recv_uevent};
+use crate::monitoring::{FileMonitor, ModuleManager, UeventAction, UeventFilter, UeventSource};
+use crate::pd::ChargerBackend;
+use crate::platform::{ShutdownSignal, WallClockTimer};
+use crate::policy::schedule::{ScheduleConfig, format_secs_of_day};
+use crate::session::{SharedSession, history, unix_secs};
+
+/// 事件循环监控的文件
+struct WatchedFiles {
+    free: WatchId,
+    disable: WatchId,
+    config: Option<WatchId>,
+}
+
+/// 后端附属线程（如 broadcast-forger），退出信号独立于全局退出信号，节点消失时单独停止
+struct Companion {
+    shutdown: Arc<ShutdownSignal>,
+    handle: thread::JoinHandle<()>,
+}
+
+/// 单个后端的运行状态：节点存在时为 active，只有 active 的后端参与 uevent 处理
+#[derive(Default)]
+struct BackendSlot {
+    active: bool,
+    companion: Option<Companion>,
+}
+
+/// 守护进程事件循环，在主线程运行直到收到退出信号
+pub struct Reactor {
+    shutdown: Arc<ShutdownSignal>,
+    module_manager: Arc<ModuleManager>,
+    store: Arc<ConfigStore>,
+    uevents: Arc<dyn UeventSource>,
+    slots: Vec<BackendSlot>,
+    // 已请求停止、尚未 join 的附属线程
+    retiring: Vec<thread::JoinHandle<()>>,
+    // 以 free 文件为准的当前模式，只在 free 文件变化时刷新
+    mode: FreeMode,
+    disable_exists: bool,
+    schedule: ScheduleConfig,
+    // 定时切换：在下一个时间段边界到期，不轮询
+    schedule_timer: WallClockTimer,
+    // 已通知后端的会话 id，每个会话只通知一次
+    notified_session: Option<u32>,
+    // 会话内的周期工作是否在运行（会话进行中且 free 启用）
+    sampling: bool,
+    thermal: ThermalMonitor,
+    soc: SocMonitor,
+    telemetry: TelemetryRecorder,
+}
+
+impl Reactor {
+    pub fn new(
+        shutdown: Arc<ShutdownSignal>,
+        module_manager: Arc<ModuleManager>,
+        store: Arc<ConfigStore>,
+        uevents: Arc<dyn UeventSource>,
+    ) -> Result<Self> {
+        let slots = module_manager
+            .backends()
+            .iter()
+            .map(|_| BackendSlot::default())
+            .collect();
+        let mode = module_manager.free_mode();
+        let disable_exists = module_manager.paths().disable_file().exists();
+        let config = store.current();
+        Ok(Self {
+            shutdown,
+            module_manager,
+            store,
+            uevents,
+            slots,
+            retiring: Vec::new(),
+            mode,
+            disable_exists,
+            schedule: config.schedule,
+            schedule_timer: WallClockTimer::new()?,
+            notified_session: None,
+            sampling: false,
+            thermal: ThermalMonitor::new(config.thermal)?,
+            soc: SocMonitor::new(config.soc.limit),
+            telemetry: TelemetryRecorder::new(config.telemetry)?,
+        })
+    }
+
+    /// 运行事件循环直到收到退出信号；返回前停止并 join 全部附属线程
+    pub fn run(mut self) -> Result<()> {
+        let thread_name = utils::get_current_thread_name();
+        info!("[{}] 启动事件循环...", thread_name);
+
+        let result = self.run_loop();
+        self.stop_companions();
+
+        // 退出时仍在进行的会话同样写入历史（结束时间留空）
+        self.record_session();
+        result
+    }
+
+    fn run_loop(&mut self) -> Result<()> {
+        let file_monitor = FileMonitor::new()?;
+        let mut watcher = InotifyWatcher::new()?;
+        let watched = self.watch_files(&mut watcher)?;
+        file_monitor.add_fd_to_epoll(watcher.fd(), libc::EPOLLIN as u32, watcher.fd() as u64)?;
+        file_monitor.add_shutdown_to_epoll(&self.shutdown)?;
+
+        let timer_fd = self.schedule_timer.fd();
+        file_monitor.add_fd_to_epoll(timer_fd, libc::EPOLLIN as u32, timer_fd as u64)?;
+        if self.schedule.is_enabled() {
+            info!(
+                "已启用定时切换（{}个解锁时间段）",
+                self.schedule.windows.len()
+            );
+            self.apply_schedule();
+        }
+        let changes_fd = self.store.changes().fd();
+        file_monitor.add_fd_to_epoll(changes_fd, libc::EPOLLIN as u32, changes_fd as u64)?;
+        let thermal_fd = self.thermal.fd();
+        file_monitor.add_fd_to_epoll(thermal_fd, libc::EPOLLIN as u32, thermal_fd as u64)?;
+        let telemetry_fd = self.telemetry.fd();
+        file_monitor.add_fd_to_epoll(telemetry_fd, libc::EPOLLIN as u32, telemetry_fd as u64)?;
+
+        // free=0 时 uevent socket 也保持在 epoll 中：热插拔检测不受 free 模式影响，
+        // 暂停期间的 power_supply 事件读取后直接丢弃
+        let uevent_sock = match self.open_uevents(&file_monitor) {
+            Ok(sock) => Some(sock),
+            Err(e) => {
+                // uevent 监听不可用时退化为仅启动时检测一次后端节点，文件与定时事件照常处理
+                error!("uevent监听失败，仅保留启动时检测结果: {}", e);
+                None
+            }
+        };
+
+        // 启动时检测后端节点；若已处于充电状态（如开机前已插电）直接进入充电会话
+        self.reconcile();
+        self.soc.evaluate(&self.module_manager);
+        self.sync_session();
+
+        let hotplug_filter = UeventFilter::new()
+            .action(UeventAction::Add)
+            .action(UeventAction::Remove);
+        let power_supply_filter = power_supply_filter();
+
+        let mut events = [libc::epoll_event { events: 0, u64: 0 }; 16];
+        while self.shutdown.is_running() {
+            // 有目录 watch 丢失时每秒尝试重新建立，其余时间无限阻塞
+            let timeout = if watcher.has_lost_watches() {
+                let rearmed = watcher.rearm();
+                self.dispatch_file_events(&watched, &rearmed);
+                if watcher.has_lost_watches() { 1000 } else { -1 }
+            } else {
+                -1
+            };
+            let nfds = match file_monitor.wait_events(&mut events, timeout) {
+                Ok(nfds) => nfds,
+                Err(err) => match err.raw_os_error() {
+                    Some(code) if code == libc::EINTR || code == libc::EAGAIN => continue,
+                    _ => {
+                        error!("epoll_wait错误，1秒后重试：{}", err);
+                        thread::sleep(Duration::from_millis(1000));
+                        continue;
+                    }
+                },
+            };
+
+            // 被退出信号唤醒：不再读取 inotify（阻塞读会卡住退出）
+            if !self.shutdown.is_running() {
+                break;
+            }
+
+            let ready = |fd: c_int| {
+                events[..nfds.max(0) as usize]
+                    .iter()
+                    .any(|e| e.u64 == fd as u64)
+            };
+
+            // 先处理文件事件，刷新 free 模式后再处理 uevent，
+            // 保证同一批事件中 free=0 时 uevent 不会按旧模式处理
+            if ready(watcher.fd()) {
+                match watcher.read_events() {
+                    Ok(changed) => self.dispatch_file_events(&watched, &changed),
+                    Err(e) => error!("读取inotify事件失败: {}", e),
+                }
+            }
+
+            if ready(changes_fd) {
+                self.store.changes().clear();
+                self.apply_config();
+            }
+
+            if ready(timer_fd) && self.schedule_timer.clear() && self.schedule.is_enabled() {
+                self.apply_schedule();
+            }
+
+            if ready(thermal_fd) {
+                self.thermal.on_timer(&self.module_manager);
+            }
+
+            if ready(telemetry_fd) {
+                self.telemetry
+                    .on_timer(self.module_manager.paths(), self.module_manager.session());
+            }
+
+            if let Some(sock) = uevent_sock
+                && ready(sock)
+                && self.handle_uevent(sock, &hotplug_filter, &power_supply_filter)
+            {
+                self.reconcile();
+            }
+
+            self.sync_session();
+        }
+
+        if let Some(sock) = uevent_sock {
+            unsafe {
+                libc::close(sock);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// 注册 free 文件、disable 文件与配置文件的监控
+    ///
+    /// 配置文件路径无效时只记录错误，不影响其余监控。
+    fn watch_files(&self, watcher: &mut InotifyWatcher) -> Result<WatchedFiles> {
+        let paths = self.module_manager.paths();
+        Ok(WatchedFiles {
+            free: watcher.watch_file(paths.free_file())?,
+            disable: watcher.watch_file(paths.disable_file())?,
+            config: match watcher.watch_file(self.store.path()) {
+                Ok(id) => Some(id),
+                Err(e) => {
+                    error!("无法监控配置文件，修改后需重启生效: {}", e);
+                    None
+                }
+            },
+        })
+    }
+
+    /// 打开 uevent socket 并加入 epoll
+    fn open_uevents(&self, file_monitor: &FileMonitor) -> Result<c_int> {
+        let sock = self.uevents.open()?;
+        if let Err(e) =
+            file_monitor.add_fd_to_epoll(sock, (libc::EPOLLIN | libc::EPOLLPRI) as u32, sock as u64)
+        {
+            unsafe {
+                libc::close(sock);
+            }
+            return Err(e);
+        }
+        Ok(sock)
+    }
+
+    /// 分发一批文件事件；同一批中同一文件的多次变化只处理一次
+    fn dispatch_file_events(&mut self, watched: &WatchedFiles, events: &[FileEvent]) {
+        let changed = |id: WatchId, changes: &[FileChange]| {
+            events
+                .iter()
+                .any(|event| event.id == id && changes.contains(&event.change))
+        };
+        let all = [
+            FileChange::Written,
+            FileChange::Created,
+            FileChange::Removed,
+            FileChange::Rescan,
+        ];
+
+        // 配置文件被删除时恢复默认配置
+        if watched.config.is_some_and(|id| changed(id, &all)) {
+            info!("检测到配置文件变化");
+            // 配置变化时经配置变化通知应用
+            if let Err(e) = self.store.reload() {
+                error!("重新加载配置文件失败: {}", e);
+            }
+        }
+        if changed(
+            watched.disable,
+            &[FileChange::Created, FileChange::Removed, FileChange::Rescan],
+        ) {
+            self.on_disable_file_changed();
+        }
+        // free 文件被删除时不处理，等待重新写入
+        if changed(
+            watched.free,
+            &[FileChange::Written, FileChange::Created, FileChange::Rescan],
+        ) {
+            info!("检测到free文件变化");
+            if let Err(e) = self.on_free_file_changed() {
+                error!("处理free文件变化失败: {}", e);
+            }
+        }
+    }
+
+    /// free 文件变化：更新模块描述与解锁节点，并切换充电会话跟踪
+    fn on_free_file_changed(&mut self) -> Result<()> {
+        let paths = Arc::clone(self.module_manager.paths());
+        // 目录重新监控后补发的检查：文件尚未重新写入时保持当前模式
+        if !paths.free_file().exists() {
+            return Ok(());
+        }
+        let content = FileMonitor::read_file_content(paths.free_file())?;
+        let new_mode = FreeMode::parse(&content);
+
+        if self.schedule.is_enabled()
+            && let Some((scheduled, next_boundary)) = schedule_state(&self.schedule)
+            && scheduled != new_mode.is_enabled()
+        {
+            info!(
+                "手动切换free={}，保持到下一个时间段边界 {}",
+                content,
+                format_secs_of_day(next_boundary)
+            );
+        }
+
+        if let Err(e) = self.module_manager.handle_free_file_change(&content) {
+            error!("更新模块状态失败: {}", e);
+        }
+
+        let old_mode = std::mem::replace(&mut self.mode, new_mode);
+        let session = Arc::clone(self.module_manager.session());
+        if new_mode.is_enabled() && !old_mode.is_enabled() {
+            info!("free文件恢复为{}，恢复充电会话跟踪", new_mode.as_str());
+            // free=0 期间未跟踪 power_supply 事件，插入与拔出都可能被错过：
+            // 按 battery/status 补开始或结束充电会话
+            self.for_each_active(|backend| resync(&session, &paths, backend, new_mode));
+            self.soc.evaluate(&self.module_manager);
+        } else if !new_mode.is_enabled() && old_mode.is_enabled() {
+            info!("free文件为0，暂停充电会话跟踪");
+        } else if new_mode == FreeMode::Auto && old_mode == FreeMode::On {
+            // 充电中从 1 切换到 auto：按当前充电头重新选择
+            info!("free文件切换为auto，按充电头自动选择");
+            self.for_each_active(|backend| {
+                apply_adapter_policy(&session, &paths, backend, new_mode)
+            });
+        } else if new_mode == FreeMode::On
+            && old_mode == FreeMode::Auto
+            && session.lock().unwrap().is_active()
+        {
+            // 充电中从 auto 切换到 1：小米充电头走原生路径时解锁节点仍为 0，立即解锁
+            info!("free文件切换为1，解锁当前充电会话");
+            self.for_each_active(|backend| unlock_active_session(&session, &paths, backend));
+        }
+        Ok(())
+    }
+
+    /// disable 文件创建/删除：经 free 文件切换模式（随后由 free 文件事件生效）
+    fn on_disable_file_changed(&mut self) {
+        let exists = self.module_manager.paths().disable_file().exists();
+        if exists == self.disable_exists {
+            return;
+        }
+        self.disable_exists = exists;
+        if let Err(e) = self.module_manager.handle_disable_file_change(exists) {
+            error!("处理disable文件变化失败: {}", e);
+        }
+    }
+
+    /// 应用重新加载后的配置：日志级别、温控、电量阈值、遥测采样间隔与定时切换在此处立即生效，
+    /// 其余配置在下次使用时读取
+    fn apply_config(&mut self) {
+        let config = self.store.current();
+        log::set_max_level(config.log_level);
+        self.thermal
+            .apply_config(config.thermal, &self.module_manager);
+        self.soc
+            .apply_config(config.soc.limit, &self.module_manager);
+        self.telemetry.apply_config(config.telemetry);
+        if config.schedule == self.schedule {
+            return;
+        }
+        self.schedule = config.schedule;
+        if self.schedule.is_enabled() {
+            info!(
+                "定时切换配置已更新（{}个解锁时间段）",
+                self.schedule.windows.len()
+            );
+            self.apply_schedule();
+        } else {
+            info!("定时切换已关闭");
+            if let Err(e) = self.schedule_timer.disarm() {
+                error!("取消定时器失败: {}", e);
+            }
+        }
+    }
+
+    /// 读取一条 uevent：power_supply 事件推进充电会话，返回是否需要重新检测后端节点
+    fn handle_uevent(
+        &mut self,
+        sock: c_int,
+        hotplug_filter: &UeventFilter,
+        power_supply_filter: &UeventFilter,
+    ) -> bool {
+        let paths = Arc::clone(self.module_manager.paths());
+        let session = Arc::clone(self.module_manager.session());
+        let mode = self.mode;
+
+        match recv_uevent(sock) {
+            Ok(Some(uevent)) => {
+                if mode.is_enabled() && power_supply_filter.matches(&uevent) {
+                    self.for_each_active(|backend| {
+                        handle_power_supply_uevent(&uevent, &session, &paths, backend, mode)
+                    });
+                    // 电量变化与插拔都有 power_supply uevent，在此评估电量阈值
+                    self.soc.evaluate(&self.module_manager);
+                }
+                // class 属性节点（如 pd_verifed）出现时不一定有属于自己的 uevent，
+                // 因此任意子系统的 add/remove 都触发一次重新检测
+                let hotplug = hotplug_filter.matches(&uevent);
+                if hotplug {
+                    debug!(
+                        "检测到热插拔事件: {}@{}",
+                        uevent.action().as_str(),
+                        uevent.devpath()
+                    );
+                }
+                hotplug
+            }
+            Ok(None) => false,
+            Err(e) if is_overflow(&e) => {
+                // 丢失的可能是充电跳变，也可能是 add/remove：两者都按当前状态重新同步
+                warn!("uevent接收缓冲区溢出，重新检测充电后端节点");
+                if mode.is_enabled() {
+                    self.for_each_active(|backend| resync(&session, &paths, backend, mode));
+                    self.soc.evaluate(&self.module_manager);
+                }
+                true
+            }
+            Err(e) => {
+                debug!("读取uevent失败: {}", e);
+                false
+            }
+        }
+    }
+
+    /// 对每个节点存在的后端执行处理，单个后端出错只记录，不影响其它后端与事件循环
+    fn for_each_active(&self, mut handle: impl FnMut(&dyn ChargerBackend) -> Result<()>) {
+        for (backend, slot) in self.module_manager.backends().iter().zip(&self.slots) {
+            if !slot.active {
+                continue;
+            }
+            if let Err(e) = handle(backend.as_ref()) {
+                error!("[{}] 处理充电事件失败: {}", backend.name(), e);
+            }
+        }
+    }
+
+    /// 跟随充电会话状态：会话进行中（且 free 启用）时运行会话内的周期工作，
+    /// 新会话开始（会话 id 变化）时通知各后端，每个会话只通知一次
+    fn sync_session(&mut self) {
+        let paths = Arc::clone(self.module_manager.paths());
+        let session = Arc::clone(self.module_manager.session());
+        let (active, session_id) = {
+            let session = session.lock().unwrap();
+            (session.is_active(), session.id())
+        };
+
+        // 会话拔出（Unplugged）或已被新会话取代：写入会话历史
+        if self
+            .telemetry
+            .tracked_session()
+            .is_some_and(|id| !active || id != session_id)
+        {
+            self.record_session();
+        }
+
+        let sampling = active && self.mode.is_enabled();
+        if sampling != self.sampling {
+            self.sampling = sampling;
+            if sampling {
+                self.thermal.start(&self.module_manager);
+                self.telemetry.start(&paths, &session);
+            } else {
+                self.thermal.stop(&self.module_manager);
+                self.telemetry.stop();
+            }
+        }
+
+        if !active {
+            self.notified_session = None;
+            return;
+        }
+        if self.notified_session == Some(session_id) {
+            return;
+        }
+        self.notified_session = Some(session_id);
+        self.for_each_active(|backend| {
+            backend.on_session_started(session_id);
+            Ok(())
+        });
+    }
+
+    /// 取出遥测累计的会话统计，追加到会话历史
+    fn record_session(&mut self) {
+        let Some(id) = self.telemetry.tracked_session() else {
+            return;
+        };
+        let latest = self.module_manager.session().lock().unwrap().clone();
+        let Some(record) = self.telemetry.finish(&latest, SystemTime::now()) else {
+            return;
+        };
+        info!(
+            "会话#{}结束: {}秒，峰值{}W，平均{}W，{}Wh",
+            id, record.duration_secs, record.peak_w, record.avg_w, record.energy_wh
+        );
+        let path = self.module_manager.paths().session_history();
+        if let Err(e) = history::append(path, &record) {
+            error!("写入会话历史失败: {}", e);
+        }
+    }
+
+    /// 按节点存在性启用/停用各后端（部分内核上 class 节点在驱动 probe 之后才出现）
+    fn reconcile(&mut self) {
+        self.reap_retiring();
+
+        let paths = Arc::clone(self.module_manager.paths());
+        let session = Arc::clone(self.module_manager.session());
+        for (backend, slot) in self
+            .module_manager
+            .backends()
+            .iter()
+            .zip(self.slots.iter_mut())
+        {
+            // 附属线程已自行退出（如出错）：回收句柄
+            if slot
+                .companion
+                .as_ref()
+                .is_some_and(|c| c.handle.is_finished())
+                && let Some(companion) = slot.companion.take()
+                && let Err(e) = companion.handle.join()
+            {
+                error!("[{}] 附属线程join失败: {:?}", backend.name(), e);
+            }
+
+            match (backend.detect(), slot.active) {
+                (true, false) => {
+                    info!(
+                        "检测到{}节点存在，启用{}后端: {}",
+                        backend.name(),
+                        backend.name(),
+                        backend.node_path().display()
+                    );
+                    slot.active = true;
+                    slot.companion = spawn_companion(backend, &session);
+
+                    // 节点出现时已在充电（如开机前已插电）：直接进入充电会话
+                    if self.mode.is_enabled()
+                        && FileMonitor::read_file_content(paths.battery_status())
+                            .unwrap_or_default()
+                            == "Charging"
+                    {
+                        info!("[{}] 已处于充电状态，初始化充电会话", backend.name());
+                        if let Err(e) = feed_charging(&session, &paths, backend.as_ref(), self.mode)
+                        {
+                            error!("[{}] 初始化充电会话失败: {}", backend.name(), e);
+                        }
+                    }
+                }
+                (false, true) => {
+                    info!(
+                        "{}节点已消失，停用{}后端: {}",
+                        backend.name(),
+                        backend.name(),
+                        backend.node_path().display()
+                    );
+                    slot.active = false;
+                    if let Some(companion) = slot.companion.take() {
+                        companion.shutdown.trigger();
+                        self.retiring.push(companion.handle);
+                    }
+                }
+                (false, false) => debug!(
+                    "{}节点不存在，暂不启用{}后端: {}",
+                    backend.name(),
+                    backend.name(),
+                    backend.node_path().display()
+                ),
+                (true, true) => {}
+            }
+        }
+    }
+
+    /// 回收已经退出的停止中线程
+    fn reap_retiring(&mut self) {
+        let (finished, pending): (Vec<_>, Vec<_>) = self
+            .retiring
+            .drain(..)
+            .partition(|handle| handle.is_finished());
+        self.retiring = pending;
+        for handle in finished {
+            if let Err(e) = handle.join() {
+                error!("线程join失败: {:?}", e);
+            }
+        }
+    }
+
+    /// 停止并 join 全部附属线程
+    fn stop_companions(&mut self) {
+        for companion in self
+            .slots
+            .iter_mut()
+            .filter_map(|slot| slot.companion.take())
+        {
+            companion.shutdown.trigger();
+            self.retiring.push(companion.handle);
+        }
+        for handle in self.retiring.drain(..) {
+            if let Err(e) = handle.join() {
+                error!("线程join失败: {:?}", e);
+            }
+        }
+    }
+
+    /// 按当前时间段写入 free 文件（经 inotify 生效，与 action.sh 同一通道），并把定时器设到下一个边界
+    ///
+    /// 只在启动和边界时调用：两次边界之间的手动切换不会被覆盖。
+    fn apply_schedule(&self) {
+        let now = unix_secs(SystemTime::now());
+        let Some(secs) = utils::local_seconds_of_day(now) else {
+            error!("无法获取本地时间，1分钟后重试定时切换");
+            if let Err(e) = self.schedule_timer.arm_at(now + 60) {
+                error!("设置定时器失败: {}", e);
+            }
+            return;
+        };
+
+        let scheduled = self.schedule.is_unlocked_at(secs);
+        if self.module_manager.is_free_enabled() != scheduled {
+            info!(
+                "定时切换：{} 进入{}时间段，free={}",
+                format_secs_of_day(secs),
+                if scheduled { "解锁" } else { "原厂" },
+                if scheduled {
+                    self.module_manager.resume_mode()
+                } else {
+                    FreeMode::Off
+                }
+                .as_str()
+            );
+            if let Err(e) = self.module_manager.set_free_enabled(scheduled) {
+                error!("定时切换写入free文件失败: {}", e);
+            }
+        }
+
+        let next = now + u64::from(self.schedule.secs_until_boundary(secs));
+        match self.schedule_timer.arm_at(next) {
+            Ok(()) => info!("下一个时间段边界: {}", utils::format_local_time(next)),
+            Err(e) => error!("设置定时器失败: {}", e),
+        }
+    }
+}
+
+/// 为刚启用的后端创建独立退出信号并启动附属线程
+fn spawn_companion(
+    backend: &Arc<dyn ChargerBackend>,
+    session: &SharedSession,
+) -> Option<Companion> {
+    let shutdown = match ShutdownSignal::new() {
+        Ok(shutdown) => Arc::new(shutdown),
+        Err(e) => {
+            error!("创建{}附属线程退出信号失败: {}", backend.name(), e);
+            return None;
+        }
+    };
+    let handle = backend.spawn_companion(Arc::clone(&shutdown), Arc::clone(session))?;
+    Some(Companion { shutdown, handle })
+}
+
+/// 当前时间段应有的 free 状态与下一个边界（当天第几秒）；无法获取本地时间时返回 `None`
+fn schedule_state(schedule: &ScheduleConfig) -> Option<(bool, u32)> {
+    let secs = utils::local_seconds_of_day(unix_secs(SystemTime::now()))?;
+    Some((
+        schedule.is_unlocked_at(secs),
+        secs + schedule.secs_until_boundary(secs),
+    ))
+}
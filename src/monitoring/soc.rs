@@ -0,0 +1,97 @@
+use log::{error, info};
+
+use crate::monitoring::{FileMonitor, ModuleManager};
+use crate::policy::HoldReason;
+use crate::policy::soc::{SocAction, SocPolicy};
+
+/// 电量阈值策略：会话中电量达到阈值时暂停解锁，会话结束时恢复
+///
+/// 由事件循环在 power_supply uevent（电量变化、插拔）之后评估，不轮询。
+pub struct SocMonitor {
+    // 策略关闭时为 None
+    policy: Option<SocPolicy>,
+}
+
+impl SocMonitor {
+    pub fn new(limit: Option<u8>) -> Self {
+        if let Some(limit) = limit {
+            info!("电量阈值策略已启用（阈值{}%）", limit);
+        }
+        Self {
+            policy: limit.map(SocPolicy::new),
+        }
+    }
+
+    /// 配置变化：启用、修改阈值或关闭（关闭时解除暂停）
+    pub fn apply_config(&mut self, limit: Option<u8>, module_manager: &ModuleManager) {
+        match (limit, self.policy.as_mut()) {
+            (Some(limit), Some(policy)) if policy.limit() != limit => {
+                info!("电量阈值改为{}%", limit);
+                policy.set_limit(limit);
+            }
+            (Some(_), Some(_)) | (None, None) => return,
+            (Some(limit), None) => {
+                info!("电量阈值策略已启用（阈值{}%）", limit);
+                self.policy = Some(SocPolicy::new(limit));
+            }
+            (None, Some(policy)) => {
+                info!("电量阈值策略已关闭");
+                if policy.is_held()
+                    && let Err(e) = module_manager.release_unlock(HoldReason::Soc)
+                {
+                    error!("解除电量阈值暂停失败: {}", e);
+                }
+                self.policy = None;
+                return;
+            }
+        }
+        self.evaluate(module_manager);
+    }
+
+    /// 按当前会话与电量评估一次
+    pub fn evaluate(&mut self, module_manager: &ModuleManager) {
+        let Some(policy) = self.policy.as_mut() else {
+            return;
+        };
+        let limit = policy.limit();
+
+        let (active, session_id) = {
+            let session = module_manager.session().lock().unwrap();
+            (session.is_active(), session.id())
+        };
+        // 会话之外只可能需要解除暂停，不读取电量
+        let capacity = if active {
+            FileMonitor::read_file_content(module_manager.paths().battery_capacity())
+                .ok()
+                .and_then(|value| value.parse::<u8>().ok())
+        } else {
+            None
+        };
+
+        let result = match policy.update(active, session_id, capacity) {
+            Some(SocAction::Hold(capacity)) => {
+                info!(
+                    "会话#{}电量{}%达到阈值{}%，暂停解锁回落到普通充电",
+                    session_id, capacity, limit
+                );
+                module_manager.hold_unlock(
+                    HoldReason::Soc,
+                    &format!(
+                        "{}电量{}%≥{}%暂停解锁",
+                        HoldReason::Soc.icon(),
+                        capacity,
+                        limit
+                    ),
+                )
+            }
+            Some(SocAction::Release) => {
+                info!("充电会话结束，解除电量阈值暂停");
+                module_manager.release_unlock(HoldReason::Soc)
+            }
+            None => Ok(()),
+        };
+        if let Err(e) = result {
+            error!("执行电量阈值策略失败: {}", e);
+        }
+    }
+}
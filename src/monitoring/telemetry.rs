@@ -0,0 +1,172 @@
+use std::time::SystemTime;
+
+use anyhow::Result;
+use libc::c_int;
+#[cfg(feature = "telemetry")]
+use log::warn;
+use log::{error, info};
+
+use crate::common::Paths;
+use crate::platform::IntervalTimer;
+use crate::session::history::{SessionRecord, SessionStats};
+#[cfg(feature = "telemetry")]
+use crate::session::unix_secs;
+use crate::session::{ChargingSession, SharedSession};
+#[cfg(feature = "telemetry")]
+use crate::telemetry::SessionLog;
+use crate::telemetry::{Sample, TelemetryConfig};
+
+/// 正在跟踪的会话
+struct Tracked {
+    id: u32,
+    /// 最近一次采样时的会话快照
+    snapshot: ChargingSession,
+    stats: SessionStats,
+    /// 遥测关闭或文件创建失败时为 `None`
+    #[cfg(feature = "telemetry")]
+    log: Option<SessionLog>,
+}
+
+/// 充电遥测：会话进行中按间隔采样并写入该会话的遥测文件，同时累计会话的功率 / 电量统计
+///
+/// 会话历史由事件循环在会话拔出（Unplugged）时取出统计后写入，采样只负责统计。
+/// 由事件循环驱动：会话开始时启动采样定时器，会话结束（或 free=0）时停止，会话之外不唤醒。
+/// 配置修改后采样间隔立即生效，格式与大小上限从下一个会话的遥测文件开始生效。
+pub struct TelemetryRecorder {
+    config: TelemetryConfig,
+    timer: IntervalTimer,
+    current: Option<Tracked>,
+    sampling: bool,
+}
+
+impl TelemetryRecorder {
+    pub fn new(config: TelemetryConfig) -> Result<Self> {
+        match config.format {
+            Some(format) => info!(
+                "充电遥测已启用（{}，间隔{}ms）",
+                format.extension(),
+                config.interval.as_millis()
+            ),
+            None => info!(
+                "充电遥测文件已关闭，仅记录会话历史（间隔{}ms）",
+                config.interval.as_millis()
+            ),
+        }
+        Ok(Self {
+            config,
+            timer: IntervalTimer::new()?,
+            current: None,
+            sampling: false,
+        })
+    }
+
+    /// 采样定时器 fd，用于注册到 epoll
+    pub fn fd(&self) -> c_int {
+        self.timer.fd()
+    }
+
+    /// 充电会话进行中：立即采样一次并启动定时器
+    pub fn start(&mut self, paths: &Paths, session: &SharedSession) {
+        self.sampling = true;
+        if let Err(e) = self.timer.arm(self.config.interval) {
+            error!("设置遥测采样定时器失败: {}", e);
+        }
+        self.sample(paths, session);
+    }
+
+    /// 停止采样（会话结束或 free=0）
+    pub fn stop(&mut self) {
+        self.sampling = false;
+        if let Err(e) = self.timer.disarm() {
+            error!("取消遥测采样定时器失败: {}", e);
+        }
+    }
+
+    /// 采样定时器到期
+    pub fn on_timer(&mut self, paths: &Paths, session: &SharedSession) {
+        if self.timer.clear() && self.sampling {
+            self.sample(paths, session);
+        }
+    }
+
+    /// 配置变化：会话期间立即按新间隔采样
+    pub fn apply_config(&mut self, config: TelemetryConfig) {
+        let interval_changed = config.interval != self.config.interval;
+        self.config = config;
+        if interval_changed
+            && self.sampling
+            && let Err(e) = self.timer.arm(self.config.interval)
+        {
+            error!("设置遥测采样定时器失败: {}", e);
+        }
+    }
+
+    /// 会话已结束（或已被新会话取代）：关闭遥测文件并生成会话汇总，未跟踪会话时返回 `None`
+    ///
+    /// `latest` 已是新会话时，上一会话的拔出时间已被覆盖，只能使用最后一次采样时的快照（结束时间留空）。
+    pub fn finish(&mut self, latest: &ChargingSession, now: SystemTime) -> Option<SessionRecord> {
+        let tracked = self.current.take()?;
+        let session = if latest.id() == tracked.id {
+            latest
+        } else {
+            &tracked.snapshot
+        };
+        #[cfg(feature = "telemetry")]
+        if let Some(log) = &tracked.log {
+            info!("遥测文件已关闭: {}", log.path().display());
+        }
+        Some(tracked.stats.finish(session, now))
+    }
+
+    /// 正在跟踪的会话 id
+    pub fn tracked_session(&self) -> Option<u32> {
+        self.current.as_ref().map(|tracked| tracked.id)
+    }
+
+    fn sample(&mut self, paths: &Paths, session: &SharedSession) {
+        let now = SystemTime::now();
+        let (snapshot, sample) = {
+            let session = session.lock().unwrap();
+            if !session.is_active() {
+                return;
+            }
+            (session.clone(), Sample::read(paths, &session, now))
+        };
+
+        let tracked = self.current.get_or_insert_with(|| Tracked {
+            id: snapshot.id(),
+            snapshot: snapshot.clone(),
+            stats: SessionStats::default(),
+            #[cfg(feature = "telemetry")]
+            log: open_log(paths, &self.config, &snapshot, now),
+        });
+        tracked.snapshot = snapshot;
+        tracked.stats.add(&sample, now);
+        #[cfg(feature = "telemetry")]
+        if let Some(log) = tracked.log.as_mut()
+            && let Err(e) = log.append(&sample)
+        {
+            warn!("写入遥测失败: {}", e);
+        }
+    }
+}
+
+#[cfg(feature = "telemetry")]
+fn open_log(
+    paths: &Paths,
+    config: &TelemetryConfig,
+    session: &ChargingSession,
+    now: SystemTime,
+) -> Option<SessionLog> {
+    let format = config.format?;
+    SessionLog::create(
+        paths.telemetry_dir(),
+        format,
+        session.id(),
+        unix_secs(session.plugged_at().unwrap_or(now)),
+        config.max_file_bytes,
+        config.max_total_bytes,
+    )
+    .inspect_err(|e| error!("创建遥测文件失败: {}", e))
+    .ok()
+}
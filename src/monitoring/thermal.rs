@@ -0,0 +1,142 @@
+use anyhow::Result;
+use libc::c_int;
+use log::{error, info, warn};
+
+use crate::monitoring::ModuleManager;
+use crate::platform::IntervalTimer;
+use crate::policy::HoldReason;
+use crate::policy::thermal::{ThermalAction, ThermalConfig, ThermalGuard, read_max_temp};
+
+/// 电池温度保护：充电会话期间按间隔采样，超过上限时暂停解锁，降到恢复温度以下后恢复
+///
+/// 由事件循环驱动：会话开始时启动定时器，会话结束（或 free=0）时停止并解除温控暂停，
+/// 会话之外不读取温度。
+pub struct ThermalMonitor {
+    config: ThermalConfig,
+    timer: IntervalTimer,
+    // 会话期间且温控开启时存在
+    guard: Option<ThermalGuard>,
+    sampling: bool,
+    unreadable_reported: bool,
+}
+
+impl ThermalMonitor {
+    pub fn new(config: ThermalConfig) -> Result<Self> {
+        Ok(Self {
+            config,
+            timer: IntervalTimer::new()?,
+            guard: None,
+            sampling: false,
+            unreadable_reported: false,
+        })
+    }
+
+    /// 采样定时器 fd，用于注册到 epoll
+    pub fn fd(&self) -> c_int {
+        self.timer.fd()
+    }
+
+    /// 充电会话开始：温控开启时立即采样一次并启动定时器
+    pub fn start(&mut self, module_manager: &ModuleManager) {
+        self.sampling = true;
+        if self.config.enabled {
+            self.guard = Some(ThermalGuard::new(&self.config));
+            self.arm(module_manager);
+        }
+    }
+
+    /// 充电会话结束：停止采样并解除温控暂停（下次会话重新判断）
+    pub fn stop(&mut self, module_manager: &ModuleManager) {
+        self.sampling = false;
+        self.disarm(module_manager);
+    }
+
+    /// 采样定时器到期
+    pub fn on_timer(&mut self, module_manager: &ModuleManager) {
+        if self.timer.clear() {
+            self.sample(module_manager);
+        }
+    }
+
+    /// 配置变化：会话期间立即按新配置启动、调整或停止采样
+    pub fn apply_config(&mut self, config: ThermalConfig, module_manager: &ModuleManager) {
+        if config == self.config {
+            return;
+        }
+        self.config = config;
+        if !self.config.enabled {
+            info!("温控保护已关闭");
+            self.disarm(module_manager);
+            return;
+        }
+
+        info!(
+            "温控保护已启用（上限{}℃，恢复{}℃，额外thermal zone: {:?}）",
+            self.config.limit_c, self.config.resume_c, self.config.zones
+        );
+        if self.sampling {
+            match self.guard.as_mut() {
+                Some(guard) => guard.set_limits(&self.config),
+                None => self.guard = Some(ThermalGuard::new(&self.config)),
+            }
+            self.arm(module_manager);
+        }
+    }
+
+    fn arm(&mut self, module_manager: &ModuleManager) {
+        if let Err(e) = self.timer.arm(self.config.interval) {
+            error!("设置温控采样定时器失败: {}", e);
+        }
+        self.sample(module_manager);
+    }
+
+    fn disarm(&mut self, module_manager: &ModuleManager) {
+        if let Err(e) = self.timer.disarm() {
+            error!("取消温控采样定时器失败: {}", e);
+        }
+        if self.guard.take().is_some_and(|guard| guard.is_tripped())
+            && let Err(e) = module_manager.release_unlock(HoldReason::Thermal)
+        {
+            error!("解除温控暂停失败: {}", e);
+        }
+    }
+
+    fn sample(&mut self, module_manager: &ModuleManager) {
+        let Some(guard) = self.guard.as_mut() else {
+            return;
+        };
+        let paths = module_manager.paths();
+        let Some(temp_c) = read_max_temp(paths, &self.config.zone_paths(paths)) else {
+            if !self.unreadable_reported {
+                warn!("无法读取电池温度，温控保护暂不生效");
+                self.unreadable_reported = true;
+            }
+            return;
+        };
+        self.unreadable_reported = false;
+
+        let result = match guard.update(temp_c) {
+            Some(ThermalAction::Trip(temp_c)) => {
+                warn!(
+                    "温度{:.1}℃达到上限{}℃，暂停解锁",
+                    temp_c, self.config.limit_c
+                );
+                module_manager.hold_unlock(
+                    HoldReason::Thermal,
+                    &format!("{}温控暂停解锁({:.1}℃)", HoldReason::Thermal.icon(), temp_c),
+                )
+            }
+            Some(ThermalAction::Recover(temp_c)) => {
+                info!(
+                    "温度{:.1}℃低于恢复温度{}℃，解除温控暂停",
+                    temp_c, self.config.resume_c
+                );
+                module_manager.release_unlock(HoldReason::Thermal)
+            }
+            None => Ok(()),
+        };
+        if let Err(e) = result {
+            error!("执行温控动作失败: {}", e);
+        }
+    }
+}
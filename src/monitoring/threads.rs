@@ -1,9 +0,0 @@
-pub mod disable_file;
-pub mod free_file;
-pub mod pd_adapter_verified;
-pub mod pd_verified;
-
-pub use disable_file::spawn_disable_file_monitor;
-pub use free_file::spawn_free_file_monitor;
-pub use pd_adapter_verified::spawn_pd_adapter_verified_monitor;
-pub use pd_verified::spawn_pd_verified_monitor;
@@ -0,0 +1,292 @@
+use std::collections::HashMap;
+#[cfg(unix)]
+use std::io;
+use std::os::raw::c_int;
+
+use anyhow::Result;
+
+#[cfg(unix)]
+use crate::monitoring::FileMonitor;
+
+// 单个 uevent 报文上限（内核 UEVENT_BUFFER_SIZE 为 2048，留足余量）
+#[cfg(unix)]
+const UEVENT_BUFFER_SIZE: usize = 4096;
+
+/// uevent 动作
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum UeventAction {
+    Add,
+    Remove,
+    Change,
+    Move,
+    Online,
+    Offline,
+    Bind,
+    Unbind,
+    Other(String),
+}
+
+impl UeventAction {
+    pub fn parse(text: &str) -> Self {
+        match text {
+            "add" => Self::Add,
+            "remove" => Self::Remove,
+            "change" => Self::Change,
+            "move" => Self::Move,
+            "online" => Self::Online,
+            "offline" => Self::Offline,
+            "bind" => Self::Bind,
+            "unbind" => Self::Unbind,
+            other => Self::Other(other.to_string()),
+        }
+    }
+
+    pub fn as_str(&self) -> &str {
+        match self {
+            Self::Add => "add",
+            Self::Remove => "remove",
+            Self::Change => "change",
+            Self::Move => "move",
+            Self::Online => "online",
+            Self::Offline => "offline",
+            Self::Bind => "bind",
+            Self::Unbind => "unbind",
+            Self::Other(other) => other,
+        }
+    }
+}
+
+/// 解析后的内核 uevent
+///
+/// 内核报文格式为 `<action>@<devpath>\0KEY=VALUE\0KEY=VALUE\0...`，
+/// 其中包含 ACTION / DEVPATH / SUBSYSTEM / SEQNUM 以及驱动附带的属性（如 `POWER_SUPPLY_*`）。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Uevent {
+    action: UeventAction,
+    devpath: String,
+    subsystem: Option<String>,
+    properties: HashMap<String, String>,
+}
+
+impl Uevent {
+    /// 解析 netlink 报文；非内核格式（如 libudev 转发的报文）返回 `None`
+    pub fn parse(datagram: &[u8]) -> Option<Self> {
+        let text = String::from_utf8_lossy(datagram);
+        let mut fields = text.split('\0').filter(|field| !field.is_empty());
+
+        let (action, devpath) = fields.next()?.split_once('@')?;
+        let properties: HashMap<String, String> = fields
+            .filter_map(|field| field.split_once('='))
+            .map(|(key, value)| (key.to_string(), value.to_string()))
+            .collect();
+
+        // 以属性中的值为准，报文头只作兜底
+        let action = UeventAction::parse(properties.get("ACTION").map_or(action, String::as_str));
+        let devpath = properties
+            .get("DEVPATH")
+            .map_or(devpath, String::as_str)
+            .to_string();
+        let subsystem = properties.get("SUBSYSTEM").cloned();
+
+        Some(Self {
+            action,
+            devpath,
+            subsystem,
+            properties,
+        })
+    }
+
+    pub fn action(&self) -> &UeventAction {
+        &self.action
+    }
+
+    pub fn devpath(&self) -> &str {
+        &self.devpath
+    }
+
+    pub fn subsystem(&self) -> Option<&str> {
+        self.subsystem.as_deref()
+    }
+
+    /// 设备名：power_supply 取 `POWER_SUPPLY_NAME`，否则取 devpath 最后一段
+    pub fn name(&self) -> &str {
+        self.get("POWER_SUPPLY_NAME")
+            .unwrap_or_else(|| self.devpath.rsplit('/').next().unwrap_or_default())
+    }
+
+    /// 读取任意属性
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.properties.get(key).map(String::as_str)
+    }
+
+    /// 内核 uevent 序号
+    pub fn seqnum(&self) -> Option<u64> {
+        self.get("SEQNUM").and_then(|value| value.parse().ok())
+    }
+
+    /// `POWER_SUPPLY_STATUS`（Charging / Discharging / Full / Not charging）
+    pub fn power_supply_status(&self) -> Option<&str> {
+        self.get("POWER_SUPPLY_STATUS")
+    }
+}
+
+/// uevent 订阅过滤器：各条件之间为"与"，同一条件的多个取值之间为"或"，未设置的条件不过滤
+///
+/// ```ignore
+/// let filter = UeventFilter::new().subsystem("power_supply").names(["battery", "usb"]);
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct UeventFilter {
+    actions: Vec<UeventAction>,
+    subsystems: Vec<String>,
+    names: Vec<String>,
+}
+
+impl UeventFilter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn action(mut self, action: UeventAction) -> Self {
+        self.actions.push(action);
+        self
+    }
+
+    pub fn subsystem(mut self, subsystem: &str) -> Self {
+        self.subsystems.push(subsystem.to_string());
+        self
+    }
+
+    pub fn names<'a>(mut self, names: impl IntoIterator<Item = &'a str>) -> Self {
+        self.names.extend(names.into_iter().map(str::to_string));
+        self
+    }
+
+    pub fn matches(&self, uevent: &Uevent) -> bool {
+        (self.actions.is_empty() || self.actions.contains(uevent.action()))
+            && (self.subsystems.is_empty()
+                || uevent
+                    .subsystem()
+                    .is_some_and(|subsystem| self.subsystems.iter().any(|s| s == subsystem)))
+            && (self.names.is_empty() || self.names.iter().any(|name| name == uevent.name()))
+    }
+}
+
+/// uevent 报文来源
+///
+/// 每次 [`open`](UeventSource::open) 返回一个独立订阅的数据报 fd（由调用方负责 close），
+/// 各订阅者都能收到全部报文。生产环境为内核 netlink 广播，测试中可替换为注入源。
+pub trait UeventSource: Send + Sync {
+    fn open(&self) -> Result<c_int>;
+}
+
+/// 内核 netlink uevent 广播
+#[cfg(unix)]
+pub struct NetlinkUevents;
+
+#[cfg(unix)]
+impl UeventSource for NetlinkUevents {
+    fn open(&self) -> Result<c_int> {
+        FileMonitor::create_uevent_monitor()
+    }
+}
+
+/// 从 uevent socket 非阻塞读取一条原始报文
+///
+/// 无数据时返回 `Ok(None)`；接收缓冲区溢出时返回 ENOBUFS（见 [`is_overflow`]），此前的事件可能已丢失。
+#[cfg(unix)]
+pub fn recv_datagram(sock: c_int) -> io::Result<Option<Vec<u8>>> {
+    let mut buffer = [0u8; UEVENT_BUFFER_SIZE];
+    let bytes_read = unsafe {
+        libc::recv(
+            sock,
+            buffer.as_mut_ptr() as *mut std::os::raw::c_void,
+            buffer.len(),
+            libc::MSG_DONTWAIT,
+        )
+    };
+
+    if bytes_read < 0 {
+        let err = io::Error::last_os_error();
+        return match err.raw_os_error() {
+            Some(code) if code == libc::EAGAIN || code == libc::EINTR => Ok(None),
+            _ => Err(err),
+        };
+    }
+
+    Ok(Some(buffer[..bytes_read as usize].to_vec()))
+}
+
+/// 从 uevent socket 非阻塞读取并解析一条报文
+///
+/// 无数据或报文无法解析时返回 `Ok(None)`，由调用方继续等待；错误同 [`recv_datagram`]。
+#[cfg(unix)]
+pub fn recv_uevent(sock: c_int) -> io::Result<Option<Uevent>> {
+    Ok(recv_datagram(sock)?.and_then(|datagram| Uevent::parse(&datagram)))
+}
+
+/// 是否为 netlink 接收缓冲区溢出（内核已丢弃部分 uevent，调用方需按 sysfs 当前值重新同步）
+#[cfg(unix)]
+pub fn is_overflow(err: &io::Error) -> bool {
+    err.raw_os_error() == Some(libc::ENOBUFS)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BATTERY_CHANGE: &[u8] = b"change@/devices/platform/soc/qpnp-smb5/power_supply/battery\0\
+        ACTION=change\0\
+        DEVPATH=/devices/platform/soc/qpnp-smb5/power_supply/battery\0\
+        SUBSYSTEM=power_supply\0\
+        POWER_SUPPLY_NAME=battery\0\
+        POWER_SUPPLY_STATUS=Charging\0\
+        POWER_SUPPLY_CAPACITY=62\0\
+        SEQNUM=4242\0";
+
+    #[test]
+    fn parses_power_supply_change() {
+        let uevent = Uevent::parse(BATTERY_CHANGE).unwrap();
+        assert_eq!(uevent.action(), &UeventAction::Change);
+        assert_eq!(
+            uevent.devpath(),
+            "/devices/platform/soc/qpnp-smb5/power_supply/battery"
+        );
+        assert_eq!(uevent.subsystem(), Some("power_supply"));
+        assert_eq!(uevent.name(), "battery");
+        assert_eq!(uevent.power_supply_status(), Some("Charging"));
+        assert_eq!(uevent.seqnum(), Some(4242));
+    }
+
+    #[test]
+    fn rejects_non_kernel_datagrams() {
+        assert!(Uevent::parse(b"libudev\0\xfe\xed\xca\xfe").is_none());
+        assert!(Uevent::parse(b"").is_none());
+    }
+
+    #[test]
+    fn filter_combines_conditions() {
+        let uevent = Uevent::parse(BATTERY_CHANGE).unwrap();
+        let power_supply = UeventFilter::new()
+            .subsystem("power_supply")
+            .names(["battery", "usb"]);
+        assert!(power_supply.matches(&uevent));
+        assert!(!UeventFilter::new().names(["usb"]).matches(&uevent));
+        assert!(!UeventFilter::new().subsystem("typec").matches(&uevent));
+        assert!(
+            !UeventFilter::new()
+                .action(UeventAction::Add)
+                .action(UeventAction::Remove)
+                .matches(&uevent)
+        );
+
+        // 无 POWER_SUPPLY_NAME 时按 devpath 最后一段匹配
+        let typec = Uevent::parse(b"add@/devices/virtual/typec/port0\0SUBSYSTEM=typec\0").unwrap();
+        assert_eq!(typec.name(), "port0");
+        assert!(
+            UeventFilter::new()
+                .action(UeventAction::Add)
+                .matches(&typec)
+        );
+    }
+}
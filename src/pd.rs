@@ -1,9 +1,59 @@
-#[cfg(unix)]
+#[cfg(not(any(feature = "qcom", feature = "mtk")))]
+compile_error!("至少需要启用 qcom 或 mtk 其中一个后端 feature");
+
+pub mod backend;
+#[cfg(all(unix, feature = "broadcast-forger"))]
 pub mod broadcast_forger;
+#[cfg(all(unix, feature = "broadcast-forger"))]
+pub mod broadcast_sender;
+#[cfg(all(unix, feature = "mtk"))]
+pub mod mtk;
+pub mod node_writer;
+#[cfg(feature = "mtk")]
 pub mod pd_adapter_verifier;
+#[cfg(feature = "qcom")]
 pub mod pd_verifier;
+#[cfg(all(unix, feature = "qcom"))]
+pub mod qcom;
+
+use anyhow::Result;
+use std::sync::Arc;
 
+use crate::common::Paths;
 #[cfg(unix)]
-pub use broadcast_forger::{BroadcastForger, spawn_broadcast_forger_worker};
+use crate::config::ConfigStore;
+
+pub use backend::ChargerBackend;
+#[cfg(all(unix, feature = "broadcast-forger"))]
+pub use broadcast_forger::{BroadcastForger, ForgerConfig, spawn_broadcast_forger_worker};
+#[cfg(all(unix, feature = "broadcast-forger"))]
+pub use broadcast_sender::{AmBroadcastSender, Broadcast, BroadcastSender};
+#[cfg(all(unix, feature = "mtk"))]
+pub use mtk::MtkBackend;
+pub use node_writer::NodeWriteStats;
+#[cfg(any(feature = "qcom", feature = "mtk"))]
+pub use node_writer::VerifiedWriter;
+#[cfg(feature = "mtk")]
 pub use pd_adapter_verifier::PdAdapterVerifier;
+#[cfg(feature = "qcom")]
 pub use pd_verifier::PdVerifier;
+#[cfg(all(unix, feature = "qcom"))]
+pub use qcom::QcomBackend;
+
+/// 编译时启用的全部充电解锁后端（是否启用由各后端的 `detect` 决定）
+#[cfg(unix)]
+pub fn charger_backends(
+    paths: &Arc<Paths>,
+    config: &Arc<ConfigStore>,
+) -> Result<Vec<Arc<dyn ChargerBackend>>> {
+    #[cfg(not(feature = "qcom"))]
+    let _ = config;
+    #[cfg(not(any(feature = "qcom", feature = "mtk")))]
+    let _ = paths;
+    Ok(vec![
+        #[cfg(feature = "qcom")]
+        Arc::new(QcomBackend::new(Arc::clone(paths), Arc::clone(config))?),
+        #[cfg(feature = "mtk")]
+        Arc::new(MtkBackend::new(Arc::clone(paths))?),
+    ])
+}
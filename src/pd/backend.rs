@@ -0,0 +1,82 @@
+use anyhow::Result;
+use std::path::Path;
+use std::sync::Arc;
+use std::thread;
+
+use crate::monitoring::FileMonitor;
+use crate::pd::NodeWriteStats;
+use crate::platform::ShutdownSignal;
+use crate::session::SharedSession;
+
+/// 充电解锁后端（按 SoC 厂商区分）
+///
+/// 每个后端只描述"解锁节点在哪、怎么写、会话前后要做什么"，
+/// uevent/epoll 循环由通用的事件循环（[`crate::monitoring::Reactor`]）负责。
+/// 新增厂商只需实现本 trait 并在 [`super::charger_backends`] 中注册。
+pub trait ChargerBackend: Send + Sync {
+    /// 后端名称，同时用作线程名与日志前缀
+    fn name(&self) -> &'static str;
+
+    /// 解锁节点路径
+    fn node_path(&self) -> &Path;
+
+    /// 当前设备是否支持该后端（默认以节点存在为准）
+    fn detect(&self) -> bool {
+        self.node_path().exists()
+    }
+
+    /// 解锁高功率PPS（写入1）
+    fn unlock(&self) -> Result<()>;
+
+    /// 还原为锁定状态（写入0）
+    fn relock(&self) -> Result<()>;
+
+    /// 读取节点当前状态：`Some(true)`=已解锁，`Some(false)`=未解锁，`None`=节点缺失或取值未知
+    fn read_state(&self) -> Result<Option<bool>> {
+        if !self.node_path().exists() {
+            return Ok(None);
+        }
+        Ok(
+            match FileMonitor::read_file_content(self.node_path())?.as_str() {
+                "1" => Some(true),
+                "0" => Some(false),
+                _ => None,
+            },
+        )
+    }
+
+    /// 解锁节点的写入统计（回读校验、被驱动改回的次数与退避状态），不校验写入的后端返回 `None`
+    fn write_stats(&self) -> Option<NodeWriteStats> {
+        None
+    }
+
+    /// 是否在任意 POWER_SUPPLY 事件时都重新检查并写回节点
+    ///
+    /// 部分驱动会在握手过程中复位节点，此时仅在拔出时写回不够及时。
+    fn rearm_on_power_supply_event(&self) -> bool {
+        false
+    }
+
+    /// 立即执行一次金标动画广播伪造（控制命令 forge-now）
+    ///
+    /// 返回 `None` 表示该后端不支持伪造，`Some(fired)` 表示门控是否通过并已发送。
+    fn forge_now(&self, _shutdown: &ShutdownSignal) -> Option<bool> {
+        None
+    }
+
+    /// 新充电会话开始（会话 id 变化）时由事件循环调用
+    ///
+    /// 在事件循环线程中执行，不得阻塞；耗时的工作交给附属线程。
+    fn on_session_started(&self, _session_id: u32) {}
+
+    /// 节点出现时由事件循环启动的附属线程（如金标动画广播伪造），节点消失或退出时一并 join
+    ///
+    /// `session` 由事件循环根据 uevent 驱动，附属线程只读。
+    fn spawn_companion(
+        &self,
+        _shutdown: Arc<ShutdownSignal>,
+        _session: SharedSession,
+    ) -> Option<thread::JoinHandle<()>> {
+        None
+    }
+}
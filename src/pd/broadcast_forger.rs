@@ -1,12 +1,13 @@
-use crate::common::constants::{
-    ADAPTER_SVID_PATH, APDO_MAX_PATH, PD_VERIFIED_PATH, REAL_TYPE_PATH, USB_VOLTAGE_NOW_PATH,
-};
-use crate::common::utils;
+use crate::common::{Paths, utils};
+use crate::config::{self, ConfigSection, ConfigStore};
 use crate::monitoring::FileMonitor;
+use crate::pd::{AmBroadcastSender, Broadcast, BroadcastSender};
+use crate::platform::{Notifier, ShutdownSignal};
+use crate::policy::adapters;
+use crate::session::{AdapterInfo, SharedSession};
+use anyhow::Result;
 use log::{debug, info, warn};
-use std::process::{Command, Stdio};
-use std::sync::Arc;
-use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+use std::sync::{Arc, Mutex};
 use std::thread;
 use std::time::Duration;
 
@@ -26,19 +27,79 @@ const EXTRA_SOC_DECIMAL_RATE: &str = "miui.intent.extra.soc_decimal_rate";
 
 // SOC 小数伪造值：仅用于解锁超级岛数字显示路径，数值不参与真实充电计算
 // （0/0 表示小数部分为 0，SystemUI 会显示如 "100W 62.00%"）
-const SOC_DECIMAL: u32 = 0;
-const SOC_DECIMAL_RATE: u32 = 0;
+const SOC_DECIMAL: i32 = 0;
+const SOC_DECIMAL_RATE: i32 = 0;
 
-// 满血档判定阈值：内核 apdo_max 达到此值即视为"平台满血 90W 级"，显示满血功率数字
-const FULL_POWER_APDO_MAX: u32 = 90;
-// 满血档显示的功率数字（W）
-const FULL_POWER_DISPLAY_W: u32 = 100;
+/// 金标动画伪造配置（freepps.conf 中的 `forge_*` 项）
+///
+/// ```text
+/// forge=on                          # on / off，关闭后不再发送任何广播
+/// forge_full_power_apdo_max=90      # apdo_max 达到该值视为满血档
+/// forge_full_power_display_w=100    # 满血档显示的功率数字
+/// forge_min_voltage_uv=12000000     # Vbus 低于该值视为弱充电头，不伪造
+/// forge_gate_timeout_ms=2000        # 插入后等待门控成立的最长时间
+/// forge_burst_delays_ms=100,100,250,400  # 爆发序列各条广播之间的间隔
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ForgerConfig {
+    /// 运行时开关：非 HyperOS/MIUI 系统上广播没有接收者，可关闭
+    pub enabled: bool,
+    /// 满血档判定阈值：内核 apdo_max 达到此值即视为"平台满血 90W 级"，显示满血功率数字
+    pub full_power_apdo_max: u32,
+    /// 满血档显示的功率数字（W）
+    pub full_power_display_w: u32,
+    /// 弱充电头判定阈值：Vbus 电压低于此值视为弱充（<45W）不伪造
+    pub min_voltage_uv: u64,
+    /// 插入后等待门控成立（Vbus 爬升到高功率阈值）的超时
+    pub gate_timeout: Duration,
+    /// 爆发序列中 QUICK=1→SOC_DECIMAL→QUICK=4→QUICK=4→QUICK=4 之间的 4 个间隔
+    pub burst_delays: [Duration; 4],
+}
 
-// 弱充电头判定阈值：Vbus 电压低于此值视为弱充（<45W）不伪造
-const MIN_HIGH_POWER_VOLTAGE_UV: u64 = 12_000_000;
+impl Default for ForgerConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            full_power_apdo_max: 90,
+            full_power_display_w: 100,
+            min_voltage_uv: 12_000_000,
+            gate_timeout: Duration::from_secs(2),
+            burst_delays: [100, 100, 250, 400].map(Duration::from_millis),
+        }
+    }
+}
 
-// 会话轮询间隔：兼顾充电会话开始（含重启）的检测延迟与 CPU 占用
-const SESSION_POLL_INTERVAL: Duration = Duration::from_millis(100);
+impl ConfigSection for ForgerConfig {
+    fn apply(&mut self, key: &str, value: &str) -> Option<Result<(), String>> {
+        let result = match key {
+            "forge" => config::parse_switch(value).map(|enabled| self.enabled = enabled),
+            "forge_full_power_apdo_max" => {
+                config::parse_positive(value).map(|w| self.full_power_apdo_max = w)
+            }
+            "forge_full_power_display_w" => {
+                config::parse_positive(value).map(|w| self.full_power_display_w = w)
+            }
+            "forge_min_voltage_uv" => value
+                .parse::<u64>()
+                .map(|uv| self.min_voltage_uv = uv)
+                .map_err(|_| "需要微伏数值".to_string()),
+            "forge_gate_timeout_ms" => {
+                config::parse_millis(value, 0).map(|timeout| self.gate_timeout = timeout)
+            }
+            "forge_burst_delays_ms" => value
+                .split(',')
+                .map(|ms| config::parse_millis(ms.trim(), 0))
+                .collect::<Result<Vec<_>, _>>()
+                .and_then(|delays| {
+                    <[Duration; 4]>::try_from(delays)
+                        .map_err(|_| "需要4个逗号分隔的毫秒数".to_string())
+                })
+                .map(|delays| self.burst_delays = delays),
+            _ => return None,
+        };
+        Some(result)
+    }
+}
 
 /// 金标动画广播伪造器
 ///
@@ -48,20 +109,74 @@ const SESSION_POLL_INTERVAL: Duration = Duration::from_millis(100);
 /// 该广播（SystemUI 的 receivedDecimal 门控），锁屏金标动画则只依赖前者。
 /// 仅当 FreePPS 已解锁高功率（pd_verifed=1）且为公版 PPS 头（adapter_svid=0000）时伪造，
 /// 小米原装头走原生 MIPPS 路径，不受影响。
-pub struct BroadcastForger;
+pub struct BroadcastForger {
+    paths: Arc<Paths>,
+    config: Arc<ConfigStore>,
+    sender: Arc<dyn BroadcastSender>,
+    // 事件循环通知的新会话 id，由 broadcast-forger 线程取走
+    pending_session: Mutex<Option<u32>>,
+    wake: Notifier,
+}
 
 impl BroadcastForger {
+    pub fn new(paths: Arc<Paths>, config: Arc<ConfigStore>) -> Result<Self> {
+        Self::with_sender(paths, config, Arc::new(AmBroadcastSender))
+    }
+
+    /// 指定广播发送通道（测试中用于记录发出的广播）
+    pub fn with_sender(
+        paths: Arc<Paths>,
+        config: Arc<ConfigStore>,
+        sender: Arc<dyn BroadcastSender>,
+    ) -> Result<Self> {
+        Ok(Self {
+            paths,
+            config,
+            sender,
+            pending_session: Mutex::new(None),
+            wake: Notifier::new()?,
+        })
+    }
+
+    /// 新充电会话开始：唤醒 broadcast-forger 线程执行爆发序列（不阻塞调用方）
+    pub fn session_started(&self, session_id: u32) {
+        *self.pending_session.lock().unwrap() = Some(session_id);
+        self.wake.notify();
+    }
+
+    /// 阻塞到下一个待伪造的会话，收到退出信号时返回 `None`
+    fn next_session(&self, shutdown: &ShutdownSignal) -> Option<u32> {
+        while self.wake.wait(shutdown) {
+            if let Some(session_id) = self.pending_session.lock().unwrap().take() {
+                return Some(session_id);
+            }
+        }
+        None
+    }
+
+    /// 当前伪造配置
+    pub fn config(&self) -> ForgerConfig {
+        self.config.current().forger
+    }
+
     /// 门控：仅在以下条件全部满足时伪造（防误报）
     ///
+    /// - freepps.conf 中未设置 `forge=off`
     /// - real_type == PD_PPS：PPS 协议充电中
     /// - pd_verifed == 1：FreePPS 已解锁高功率档
     /// - adapter_svid == 0000：公版 PPS 头（非小米原装 MIPPS 头）
     /// - Vbus 电压足够高（排除弱充电头）
-    fn should_forge(&self) -> bool {
-        let real_type = FileMonitor::read_file_content(REAL_TYPE_PATH).unwrap_or_default();
-        let pd_verifed = FileMonitor::read_file_content(PD_VERIFIED_PATH).unwrap_or_default();
-        let adapter_svid = FileMonitor::read_file_content(ADAPTER_SVID_PATH).unwrap_or_default();
-        let voltage_uv: u64 = FileMonitor::read_file_content(USB_VOLTAGE_NOW_PATH)
+    /// - 该充电头在 adapters.conf 中没有标记 `no-forge`
+    fn should_forge(&self, config: &ForgerConfig) -> bool {
+        if !config.enabled {
+            return false;
+        }
+        let real_type = FileMonitor::read_file_content(self.paths.real_type()).unwrap_or_default();
+        let pd_verifed =
+            FileMonitor::read_file_content(self.paths.pd_verified()).unwrap_or_default();
+        let adapter_svid =
+            FileMonitor::read_file_content(self.paths.adapter_svid()).unwrap_or_default();
+        let voltage_uv: u64 = FileMonitor::read_file_content(self.paths.usb_voltage_now())
             .unwrap_or_default()
             .parse()
             .unwrap_or(0);
@@ -69,24 +184,40 @@ impl BroadcastForger {
         real_type == "PD_PPS"
             && pd_verifed == "1"
             && adapter_svid == "0000"
-            && voltage_uv >= MIN_HIGH_POWER_VOLTAGE_UV
+            && voltage_uv >= config.min_voltage_uv
+            && self.forge_allowed()
+    }
+
+    /// 充电头规则是否允许伪造
+    fn forge_allowed(&self) -> bool {
+        let adapter = AdapterInfo::read(&self.paths);
+        match adapters::lookup(self.paths.adapter_rules(), &adapter) {
+            Some(rule) if !rule.forge => {
+                debug!(
+                    "[broadcast-forger] 充电头{}规则为不伪造金标动画",
+                    adapters::fingerprint(&adapter).unwrap_or_default()
+                );
+                false
+            }
+            _ => true,
+        }
     }
 
     /// 计算广播的 POWER_MAX：按充电头 PPS 能力 apdo_max 分级显示。
     ///
-    /// - apdo_max >= 90（平台满血 90W 级）：显示满血功率数字 FULL_POWER_DISPLAY_W
-    /// - apdo_max < 90（如 65W 头）：显示真实 PPS 能力 apdo_max
-    fn power_max(&self) -> u32 {
-        let apdo_max = FileMonitor::read_file_content(APDO_MAX_PATH).unwrap_or_default();
+    /// - apdo_max >= `full_power_apdo_max`（默认 90，平台满血 90W 级）：显示满血功率数字 `full_power_display_w`
+    /// - 低于该值（如 65W 头）：显示真实 PPS 能力 apdo_max
+    fn power_max(&self, config: &ForgerConfig) -> u32 {
+        let apdo_max = FileMonitor::read_file_content(self.paths.apdo_max()).unwrap_or_default();
         match apdo_max.parse::<u32>() {
-            Ok(v) if v >= FULL_POWER_APDO_MAX => FULL_POWER_DISPLAY_W,
+            Ok(v) if v >= config.full_power_apdo_max => config.full_power_display_w,
             Ok(v) => v,
             Err(_) => {
                 debug!(
                     "[broadcast-forger] apdo_max解析失败({:?})，默认取{}W",
-                    apdo_max, FULL_POWER_DISPLAY_W
+                    apdo_max, config.full_power_display_w
                 );
-                FULL_POWER_DISPLAY_W
+                config.full_power_display_w
             }
         }
     }
@@ -102,83 +233,35 @@ impl BroadcastForger {
     /// `ACTION_QUICK_CHARGE_TYPE` 处理完成（chargeSpeed 传播到 KeyguardUpdateMonitor）后
     /// 再发送，调用方 `send()` 内已预留延迟。
     fn send_soc_decimal(&self) {
-        let soc_decimal = SOC_DECIMAL.to_string();
-        let soc_decimal_rate = SOC_DECIMAL_RATE.to_string();
-        let status = Command::new("/system/bin/am")
-            .args([
-                "broadcast",
-                "-p",
-                BROADCAST_TARGET_PKG,
-                "-a",
-                ACTION_SOC_DECIMAL,
-                "--ei",
-                EXTRA_SOC_DECIMAL,
-                soc_decimal.as_str(),
-                "--ei",
-                EXTRA_SOC_DECIMAL_RATE,
-                soc_decimal_rate.as_str(),
-            ])
-            .stdout(Stdio::null())
-            .stderr(Stdio::null())
-            .status();
+        let broadcast = Broadcast::new(BROADCAST_TARGET_PKG, ACTION_SOC_DECIMAL)
+            .extra(EXTRA_SOC_DECIMAL, SOC_DECIMAL)
+            .extra(EXTRA_SOC_DECIMAL_RATE, SOC_DECIMAL_RATE);
 
-        match status {
-            Ok(st) if st.success() => {
-                debug!("[broadcast-forger] am broadcast ACTION_SOC_DECIMAL 完成");
-            }
-            Ok(st) => warn!(
-                "[broadcast-forger] am broadcast ACTION_SOC_DECIMAL 返回异常: {}",
-                st
-            ),
-            Err(e) => warn!(
-                "[broadcast-forger] 执行 am broadcast ACTION_SOC_DECIMAL 失败: {}",
-                e
-            ),
+        match self.sender.send(&broadcast) {
+            Ok(()) => debug!("[broadcast-forger] am broadcast ACTION_SOC_DECIMAL 完成"),
+            Err(e) => warn!("[broadcast-forger] ACTION_SOC_DECIMAL: {}", e),
         }
     }
 
     /// 发送单条快速充电广播（quick_charge_type=1 或 4）
-    fn send_quick_charge(&self, quick_charge_type: &str, power_max: u32) {
+    fn send_quick_charge(&self, quick_charge_type: i32, power_max: u32) {
         info!(
             "[broadcast-forger] 发送伪造金标动画广播: quick_charge_type={} POWER_MAX={}W",
             quick_charge_type, power_max
         );
 
-        let power_max_arg = power_max.to_string();
-        let status = Command::new("/system/bin/am")
-            .args([
-                "broadcast",
-                "-p",
-                BROADCAST_TARGET_PKG,
-                "-a",
-                ACTION_QUICK_CHARGE_TYPE,
-                "--ei",
-                EXTRA_QUICK_CHARGE_TYPE,
-                quick_charge_type,
-                "--ei",
-                EXTRA_POWER_MAX,
-                power_max_arg.as_str(),
-                "--ei",
-                EXTRA_CAR_CHARGE,
-                "0",
-            ])
-            .stdout(Stdio::null())
-            .stderr(Stdio::null())
-            .status();
+        let broadcast = Broadcast::new(BROADCAST_TARGET_PKG, ACTION_QUICK_CHARGE_TYPE)
+            .extra(EXTRA_QUICK_CHARGE_TYPE, quick_charge_type)
+            .extra(EXTRA_POWER_MAX, power_max as i32)
+            .extra(EXTRA_CAR_CHARGE, 0);
 
-        match status {
-            Ok(st) if st.success() => {
-                debug!(
-                    "[broadcast-forger] am broadcast(quick_charge_type={}) 完成",
-                    quick_charge_type
-                );
-            }
-            Ok(st) => warn!(
-                "[broadcast-forger] am broadcast(quick_charge_type={}) 返回异常: {}",
-                quick_charge_type, st
+        match self.sender.send(&broadcast) {
+            Ok(()) => debug!(
+                "[broadcast-forger] am broadcast(quick_charge_type={}) 完成",
+                quick_charge_type
             ),
             Err(e) => warn!(
-                "[broadcast-forger] 执行 am broadcast(quick_charge_type={}) 失败: {}",
+                "[broadcast-forger] quick_charge_type={}: {}",
                 quick_charge_type, e
             ),
         }
@@ -196,56 +279,79 @@ impl BroadcastForger {
     /// 3. QUICK=4：chargeDeviceType 1→4 触发刷新，receivedDecimal=true 直接显示 100W MAX
     ///
     /// 之后补发 QUICK=4 两次，应对内核 quick_charge_type=1 广播竞争（降级后快速恢复）。
-    pub fn send_burst(&self) {
-        // 等待门控成立（插入后 Vbus 爬升到高功率阈值），超时 2s 放弃
-        let deadline = std::time::Instant::now() + Duration::from_secs(2);
-        while !self.should_forge() && std::time::Instant::now() < deadline {
-            thread::sleep(Duration::from_millis(50));
+    ///
+    /// 序列中的等待可被 `shutdown` 打断，收到退出信号时立即放弃剩余发送。
+    /// 返回值表示门控是否通过（至少发出了第一条广播）。
+    pub fn send_burst(&self, shutdown: &ShutdownSignal) -> bool {
+        // 整个序列使用同一份配置，避免中途重新加载导致间隔或门控前后不一致
+        let config = self.config();
+        let [
+            quick_delay,
+            decimal_delay,
+            first_retry_delay,
+            second_retry_delay,
+        ] = config.burst_delays;
+
+        // 等待门控成立（插入后 Vbus 爬升到高功率阈值），超时放弃
+        let deadline = std::time::Instant::now() + config.gate_timeout;
+        while !self.should_forge(&config) && std::time::Instant::now() < deadline {
+            if shutdown.wait_timeout(Duration::from_millis(50)) {
+                return false;
+            }
         }
-        if !self.should_forge() {
-            return;
+        if !self.should_forge(&config) {
+            return false;
         }
 
         // 1) 建立快充态（chargeSpeed>=1）
-        let power_max = self.power_max();
-        self.send_quick_charge("1", power_max);
-        thread::sleep(Duration::from_millis(100));
+        let power_max = self.power_max(&config);
+        self.send_quick_charge(1, power_max);
+        if shutdown.wait_timeout(quick_delay) {
+            return true;
+        }
 
         // 2) 让 SystemUI 的 receivedDecimal=true（需 chargeSpeed>0 已传播）
-        if !self.should_forge() {
-            return;
+        if !self.should_forge(&config) {
+            return true;
         }
         self.send_soc_decimal();
-        thread::sleep(Duration::from_millis(100));
+        if shutdown.wait_timeout(decimal_delay) {
+            return true;
+        }
 
         // 3) 升级 chargeSpeed=3 → 超级岛直接显示 100W MAX（不再经过"快充中"回退）
-        if !self.should_forge() {
-            return;
+        if !self.should_forge(&config) {
+            return true;
         }
-        self.send_quick_charge("4", power_max);
+        self.send_quick_charge(4, power_max);
 
         // 4-5) 补发 QUICK=4，应对内核 quick_charge_type=1 广播在握手期降级
-        thread::sleep(Duration::from_millis(250));
-        if self.should_forge() {
-            self.send_quick_charge("4", power_max);
+        if shutdown.wait_timeout(first_retry_delay) {
+            return true;
+        }
+        if self.should_forge(&config) {
+            self.send_quick_charge(4, power_max);
+        }
+        if shutdown.wait_timeout(second_retry_delay) {
+            return true;
         }
-        thread::sleep(Duration::from_millis(400));
-        if self.should_forge() {
-            self.send_quick_charge("4", power_max);
+        if self.should_forge(&config) {
+            self.send_quick_charge(4, power_max);
         }
+        true
     }
 }
 
 /// 金标动画广播伪造会话循环（broadcast-forger 线程）
 ///
-/// - 每次 Charging 会话开始（generation 增加）时：执行 QUICK=1 → SOC_DECIMAL → QUICK=4
-///   爆发序列（金标动画在插入 ~1s 内触发，需时序对齐；同时解锁亮屏超级岛数字显示）
+/// - 事件循环在每次 Charging 会话开始（会话 id 变化）时经 [`BroadcastForger::session_started`]
+///   唤醒本线程，执行 QUICK=1 → SOC_DECIMAL → QUICK=4 爆发序列（金标动画在插入 ~1s 内触发，
+///   需时序对齐；同时解锁亮屏超级岛数字显示）
+/// - `am` 调用是阻塞的，放在独立线程中执行，不占用事件循环
 /// - 充电期间不再补发（避免重复触发 SystemUI/PowerCenter，实测仅爆发一次即稳定生效）
-/// - 会话结束（Discharging）后停止补发
 pub fn spawn_broadcast_forger_worker(
-    running: Arc<AtomicBool>,
-    session_gen: Arc<AtomicU32>,
-    session_active: Arc<AtomicBool>,
+    shutdown: Arc<ShutdownSignal>,
+    session: SharedSession,
     forger: Arc<BroadcastForger>,
 ) -> thread::JoinHandle<()> {
     thread::Builder::new()
@@ -254,26 +360,23 @@ pub fn spawn_broadcast_forger_worker(
             let thread_name = utils::get_current_thread_name();
             info!("[{}] 启动金标动画广播伪造线程...", thread_name);
 
-            let mut burst_done_for: Option<u32> = None;
-
-            while running.load(Ordering::Relaxed) {
-                // 会话未激活：等待下一次充电会话（新会话需重新执行首轮发送）
-                if !session_active.load(Ordering::Relaxed) {
-                    burst_done_for = None;
-                    thread::sleep(SESSION_POLL_INTERVAL);
-                    continue;
+            while let Some(session_id) = forger.next_session(&shutdown) {
+                // 通知到达前会话已结束或已被新会话取代：新会话会再次通知
+                {
+                    let session = session.lock().unwrap();
+                    if !session.is_active() || session.id() != session_id {
+                        continue;
+                    }
                 }
 
-                let generation = session_gen.load(Ordering::Relaxed);
-                if burst_done_for != Some(generation) {
-                    // 新会话开始：QUICK=1 → SOC_DECIMAL → QUICK=4 爆发序列
-                    // （让超级岛直接显示 100W MAX，避免多余的"快充中"回退通知）
-                    burst_done_for = Some(generation);
-                    forger.send_burst();
-                    continue;
+                // QUICK=1 → SOC_DECIMAL → QUICK=4 爆发序列
+                // （让超级岛直接显示 100W MAX，避免多余的"快充中"回退通知）
+                if forger.send_burst(&shutdown) {
+                    let mut session = session.lock().unwrap();
+                    if session.id() == session_id {
+                        session.mark_broadcast_forged();
+                    }
                 }
-
-                thread::sleep(SESSION_POLL_INTERVAL);
             }
         })
         .expect("创建broadcast-forger线程失败")
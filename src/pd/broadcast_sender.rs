@@ -0,0 +1,61 @@
+use anyhow::{Result, anyhow};
+use std::process::{Command, Stdio};
+
+use crate::common::constants::AM_BIN_PATH;
+
+/// 一条定向广播（extra 均为 `--ei` 整型参数）
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Broadcast {
+    pub package: String,
+    pub action: String,
+    pub extras: Vec<(String, i32)>,
+}
+
+impl Broadcast {
+    pub fn new(package: &str, action: &str) -> Self {
+        Self {
+            package: package.to_string(),
+            action: action.to_string(),
+            extras: Vec::new(),
+        }
+    }
+
+    pub fn extra(mut self, key: &str, value: i32) -> Self {
+        self.extras.push((key.to_string(), value));
+        self
+    }
+}
+
+/// 广播发送通道（生产环境为 `am broadcast`，测试中替换为记录器）
+pub trait BroadcastSender: Send + Sync {
+    fn send(&self, broadcast: &Broadcast) -> Result<()>;
+}
+
+/// 通过 `/system/bin/am broadcast` 发送
+pub struct AmBroadcastSender;
+
+impl BroadcastSender for AmBroadcastSender {
+    fn send(&self, broadcast: &Broadcast) -> Result<()> {
+        let mut command = Command::new(AM_BIN_PATH);
+        command.args([
+            "broadcast",
+            "-p",
+            broadcast.package.as_str(),
+            "-a",
+            broadcast.action.as_str(),
+        ]);
+        for (key, value) in &broadcast.extras {
+            command.args(["--ei", key.as_str(), value.to_string().as_str()]);
+        }
+
+        let status = command
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .status()
+            .map_err(|e| anyhow!("执行 am broadcast 失败: {}", e))?;
+        if !status.success() {
+            return Err(anyhow!("am broadcast 返回异常: {}", status));
+        }
+        Ok(())
+    }
+}
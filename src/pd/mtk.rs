@@ -0,0 +1,48 @@
+use anyhow::Result;
+use std::path::Path;
+use std::sync::Arc;
+
+use crate::common::Paths;
+use crate::pd::{ChargerBackend, NodeWriteStats, PdAdapterVerifier};
+
+/// 联发科平台后端：`/sys/class/Charging_Adapter/pd_adapter/usbpd_verifed`
+pub struct MtkBackend {
+    paths: Arc<Paths>,
+    verifier: PdAdapterVerifier,
+}
+
+impl MtkBackend {
+    pub fn new(paths: Arc<Paths>) -> Result<Self> {
+        Ok(Self {
+            verifier: PdAdapterVerifier::new(Arc::clone(&paths))?,
+            paths,
+        })
+    }
+}
+
+impl ChargerBackend for MtkBackend {
+    fn name(&self) -> &'static str {
+        "mtk"
+    }
+
+    fn node_path(&self) -> &Path {
+        self.paths.pd_adapter_verified()
+    }
+
+    fn unlock(&self) -> Result<()> {
+        self.verifier.set_pd_adapter_verified(true)
+    }
+
+    fn write_stats(&self) -> Option<NodeWriteStats> {
+        Some(self.verifier.stats())
+    }
+
+    fn relock(&self) -> Result<()> {
+        self.verifier.set_pd_adapter_verified(false)
+    }
+
+    /// mtk 驱动会在握手过程中复位 usbpd_verifed，任意 POWER_SUPPLY 事件都需检查写回
+    fn rearm_on_power_supply_event(&self) -> bool {
+        true
+    }
+}
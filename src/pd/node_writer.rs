@@ -0,0 +1,436 @@
+use anyhow::Result;
+use log::{debug, error, info, warn};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant, SystemTime};
+use thiserror::Error;
+
+use crate::common::utils;
+use crate::monitoring::FileMonitor;
+use crate::session::unix_secs;
+
+// 写入后回读不一致时的最多写入次数（含首次）
+const MAX_ATTEMPTS: u32 = 3;
+// 写入后等待驱动处理再回读，驱动的复位通常紧跟在写入之后
+const READBACK_DELAY: Duration = Duration::from_millis(20);
+// 连续多少次写入请求（每次含全部重试）都被改回后判定为与驱动争夺节点
+const FIGHT_THRESHOLD: u32 = 3;
+// 争夺节点后的退避：首次 30 秒，再次争夺时翻倍，最长 10 分钟
+const INITIAL_BACKOFF: Duration = Duration::from_secs(30);
+const MAX_BACKOFF: Duration = Duration::from_secs(600);
+
+/// 解锁节点写入统计（`status` 命令与诊断报告中展示）
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NodeWriteStats {
+    /// 实际写入次数（含重试）
+    pub writes: u64,
+    /// 回读确认生效的写入请求次数
+    #[serde(default)]
+    pub verified: u64,
+    /// 回读时发现被驱动改回的次数
+    pub reverts: u64,
+    /// 全部重试后仍未生效的写入请求次数
+    pub failed: u64,
+    /// 退避期间跳过的写入请求次数
+    pub skipped: u64,
+    /// 判定与驱动争夺节点的次数
+    pub fights: u64,
+    /// 正在退避时为退避结束的 Unix 时间戳
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub backoff_until: Option<u64>,
+}
+
+impl NodeWriteStats {
+    /// 是否正处于与驱动争夺节点后的退避期
+    pub fn is_fighting(&self) -> bool {
+        self.backoff_until
+            .is_some_and(|until| until > unix_secs(SystemTime::now()))
+    }
+}
+
+/// 退避期间跳过的写入（以错误返回，调用方不会把跳过当作写入成功）
+#[derive(Debug, Error)]
+#[error("{label}节点写入{value}与驱动争夺中，退避至{until_text}前跳过写入", until_text = utils::format_local_time(*.until))]
+pub struct WriteSkipped {
+    pub label: &'static str,
+    pub value: String,
+    /// 退避结束的 Unix 时间戳
+    pub until: u64,
+}
+
+// 与驱动争夺中的值及其退避截止时间
+struct Backoff {
+    value: String,
+    deadline: Instant,
+    until: u64,
+}
+
+#[derive(Default)]
+struct WriterState {
+    stats: NodeWriteStats,
+    // 每次写入请求递增；回读校验前比对，已被更新的写入请求取代时放弃校验
+    generation: u64,
+    // 连续未生效的写入请求所写的值及次数
+    failing_value: Option<String>,
+    consecutive_failures: u32,
+    // 下一次争夺节点时的退避时长
+    next_backoff: Option<Duration>,
+    backoff: Option<Backoff>,
+}
+
+// 待回读校验的写入请求
+struct Verification {
+    node: PathBuf,
+    value: String,
+    generation: u64,
+}
+
+struct Shared {
+    label: &'static str,
+    state: Mutex<WriterState>,
+}
+
+/// 带回读校验的节点写入
+///
+/// 部分固件上驱动会在写入后立即把 `pd_verifed` / `usbpd_verifed` 复位。每次写入后回读，
+/// 不一致时有限次重试；同一个值连续多次写入都被改回时判定为"与驱动争夺节点"，记录诊断并在
+/// 一段时间内不再写入这个值，避免与驱动无休止地互相覆盖。退避只针对被争夺的值，
+/// 其它值（如安全策略要求的重新上锁）照常写入。
+///
+/// 写入请求在调用线程上立即写入一次；等待驱动处理、回读与重试在写入器自己的校验线程中进行，
+/// 不阻塞事件循环。校验结果记入统计与日志。
+pub struct VerifiedWriter {
+    shared: Arc<Shared>,
+    // 校验线程在首次写入时创建，写入器释放时关闭队列并 join
+    verifier: Mutex<Option<(Sender<Verification>, thread::JoinHandle<()>)>>,
+}
+
+impl VerifiedWriter {
+    /// `label` 为日志中的节点名称，如 "PD验证"
+    pub fn new(label: &'static str) -> Self {
+        Self {
+            shared: Arc::new(Shared {
+                label,
+                state: Mutex::new(WriterState::default()),
+            }),
+            verifier: Mutex::new(None),
+        }
+    }
+
+    /// 写入统计快照
+    pub fn stats(&self) -> NodeWriteStats {
+        self.shared.state.lock().unwrap().stats.clone()
+    }
+
+    /// 写入并安排回读确认；该值正在退避时跳过写入并返回 [`WriteSkipped`] 错误
+    ///
+    /// 返回 `Ok` 只表示已写入，是否被驱动改回由校验线程确认并重试。
+    pub fn write(&self, node: &Path, value: &str) -> Result<()> {
+        let generation = {
+            let mut state = self.shared.state.lock().unwrap();
+            self.shared.check_backoff(&mut state, node, value)?;
+            FileMonitor::write_file_content(node, value)?;
+            state.stats.writes += 1;
+            state.generation += 1;
+            state.generation
+        };
+        self.verify(Verification {
+            node: node.to_path_buf(),
+            value: value.to_string(),
+            generation,
+        });
+        Ok(())
+    }
+
+    /// 交给校验线程回读
+    fn verify(&self, verification: Verification) {
+        let mut verifier = self.verifier.lock().unwrap();
+        if verifier.is_none() {
+            let (sender, receiver) = mpsc::channel();
+            let shared = Arc::clone(&self.shared);
+            match thread::Builder::new()
+                .name("node-verifier".to_string())
+                .spawn(move || verify_worker(&shared, receiver))
+            {
+                Ok(handle) => *verifier = Some((sender, handle)),
+                Err(e) => {
+                    error!("创建{}节点校验线程失败: {}", self.shared.label, e);
+                    return;
+                }
+            }
+        }
+        if let Some((sender, _)) = verifier.as_ref() {
+            // 校验线程只会在队列关闭后退出，发送不会失败
+            let _ = sender.send(verification);
+        }
+    }
+}
+
+impl Drop for VerifiedWriter {
+    fn drop(&mut self) {
+        if let Some((sender, handle)) = self.verifier.lock().unwrap().take() {
+            drop(sender);
+            let _ = handle.join();
+        }
+    }
+}
+
+fn verify_worker(shared: &Shared, receiver: Receiver<Verification>) {
+    while let Ok(verification) = receiver.recv() {
+        shared.verify(&verification);
+    }
+}
+
+impl Shared {
+    /// 等待驱动处理后回读，被改回时重写，全部重试后仍未生效时记录失败
+    fn verify(&self, verification: &Verification) {
+        let Verification {
+            node,
+            value,
+            generation,
+        } = verification;
+        for attempt in 1..=MAX_ATTEMPTS {
+            thread::sleep(READBACK_DELAY);
+
+            let mut state = self.state.lock().unwrap();
+            if state.generation != *generation {
+                // 期间有新的写入请求，由新请求的校验接管
+                return;
+            }
+            let actual = match FileMonitor::read_file_content(node) {
+                Ok(actual) => actual,
+                Err(e) => {
+                    warn!("{}节点回读失败: {}", self.label, e);
+                    return;
+                }
+            };
+            if actual == *value {
+                self.record_success(&mut state, value);
+                if attempt > 1 {
+                    info!(
+                        "已将{}状态写入为{}（第{}次写入后生效）: {}",
+                        self.label,
+                        value,
+                        attempt,
+                        node.display()
+                    );
+                } else {
+                    info!("已将{}状态写入为{}: {}", self.label, value, node.display());
+                }
+                return;
+            }
+
+            state.stats.reverts += 1;
+            warn!(
+                "{}节点写入{}后回读为{:?}，已被驱动改回（第{}/{}次）",
+                self.label, value, actual, attempt, MAX_ATTEMPTS
+            );
+            if attempt == MAX_ATTEMPTS {
+                break;
+            }
+            if let Err(e) = FileMonitor::write_file_content(node, value) {
+                warn!("{}节点重新写入{}失败: {}", self.label, value, e);
+                return;
+            }
+            state.stats.writes += 1;
+        }
+
+        let mut state = self.state.lock().unwrap();
+        self.record_failure(&mut state, node, value);
+        warn!(
+            "{}节点写入{}后被驱动改回（已重试{}次）: {}",
+            self.label,
+            value,
+            MAX_ATTEMPTS,
+            node.display()
+        );
+    }
+
+    /// 该值正在退避时记录跳过并返回错误；退避已结束时清除，重新尝试
+    fn check_backoff(&self, state: &mut WriterState, node: &Path, value: &str) -> Result<()> {
+        let Some(backoff) = state.backoff.as_ref() else {
+            return Ok(());
+        };
+        if Instant::now() >= backoff.deadline {
+            // 退避结束：重新尝试，仍被改回时以更长的退避继续
+            state.backoff = None;
+            state.stats.backoff_until = None;
+            return Ok(());
+        }
+        if backoff.value != value {
+            return Ok(());
+        }
+
+        let until = backoff.until;
+        state.stats.skipped += 1;
+        debug!(
+            "{}节点与驱动争夺中，退避期间跳过写入{}: {}",
+            self.label,
+            value,
+            node.display()
+        );
+        Err(WriteSkipped {
+            label: self.label,
+            value: value.to_string(),
+            until,
+        }
+        .into())
+    }
+
+    fn record_success(&self, state: &mut WriterState, value: &str) {
+        state.stats.verified += 1;
+        if state.failing_value.as_deref() != Some(value) {
+            // 其它值写入成功不影响被争夺值的失败计数与退避
+            return;
+        }
+        if state.next_backoff.is_some() {
+            info!("{}节点写入恢复生效，不再退避", self.label);
+        }
+        state.failing_value = None;
+        state.consecutive_failures = 0;
+        state.next_backoff = None;
+    }
+
+    fn record_failure(&self, state: &mut WriterState, node: &Path, value: &str) {
+        state.stats.failed += 1;
+        if state.failing_value.as_deref() != Some(value) {
+            state.failing_value = Some(value.to_string());
+            state.consecutive_failures = 0;
+            state.next_backoff = None;
+        }
+        state.consecutive_failures += 1;
+        if state.consecutive_failures < FIGHT_THRESHOLD {
+            return;
+        }
+
+        let backoff = state.next_backoff.unwrap_or(INITIAL_BACKOFF);
+        state.next_backoff = Some((backoff * 2).min(MAX_BACKOFF));
+        state.consecutive_failures = 0;
+        let until = unix_secs(SystemTime::now() + backoff);
+        state.backoff = Some(Backoff {
+            value: value.to_string(),
+            deadline: Instant::now() + backoff,
+            until,
+        });
+        state.stats.backoff_until = Some(until);
+        state.stats.fights += 1;
+        warn!(
+            "{}节点写入{}连续{}次都被驱动改回，判定为与驱动争夺节点，暂停写入{}至{}: {}",
+            self.label,
+            value,
+            FIGHT_THRESHOLD,
+            value,
+            utils::format_local_time(until),
+            node.display()
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{FakeSysfs, wait_until};
+
+    const TIMEOUT: Duration = Duration::from_secs(5);
+
+    /// /dev/null 可写但回读为空：模拟每次写入后都被驱动复位的节点
+    fn fighting_node(sysfs: &FakeSysfs) -> PathBuf {
+        let fighting = sysfs.paths().module_dir().join("fighting");
+        std::os::unix::fs::symlink("/dev/null", &fighting).unwrap();
+        fighting
+    }
+
+    /// 连续写入被改回直到判定为争夺节点
+    fn fight(writer: &VerifiedWriter, node: &Path, value: &str) {
+        for failed in 1..=FIGHT_THRESHOLD {
+            writer.write(node, value).unwrap();
+            assert!(wait_until(TIMEOUT, || writer.stats().failed == u64::from(failed)));
+        }
+    }
+
+    #[test]
+    fn accepted_write_is_verified_once() {
+        let sysfs = FakeSysfs::qcom();
+        let node = sysfs.paths().pd_verified();
+        let writer = VerifiedWriter::new("PD验证");
+
+        writer.write(node, "0").unwrap();
+        assert_eq!(sysfs.read(node), "0");
+        assert!(wait_until(TIMEOUT, || writer.stats().verified == 1));
+        let stats = writer.stats();
+        assert_eq!((stats.writes, stats.reverts, stats.failed), (1, 0, 0));
+    }
+
+    #[test]
+    fn reverted_write_is_retried_then_counted_as_failed() {
+        let sysfs = FakeSysfs::new();
+        let node = fighting_node(&sysfs);
+        let writer = VerifiedWriter::new("PD验证");
+
+        writer.write(&node, "1").unwrap();
+        assert!(wait_until(TIMEOUT, || writer.stats().failed == 1));
+        let stats = writer.stats();
+        assert_eq!(stats.writes, u64::from(MAX_ATTEMPTS));
+        assert_eq!(stats.reverts, u64::from(MAX_ATTEMPTS));
+        assert_eq!(stats.fights, 0);
+    }
+
+    #[test]
+    fn repeated_failures_start_backoff() {
+        let sysfs = FakeSysfs::new();
+        let node = fighting_node(&sysfs);
+        let writer = VerifiedWriter::new("PD验证");
+
+        fight(&writer, &node, "1");
+        let stats = writer.stats();
+        assert_eq!(stats.fights, 1);
+        assert!(stats.is_fighting());
+    }
+
+    #[test]
+    fn fought_value_is_skipped_during_backoff() {
+        let sysfs = FakeSysfs::new();
+        let node = fighting_node(&sysfs);
+        let writer = VerifiedWriter::new("PD验证");
+        fight(&writer, &node, "1");
+        let writes = writer.stats().writes;
+
+        let skipped = writer.write(&node, "1").unwrap_err();
+        assert!(skipped.downcast_ref::<WriteSkipped>().is_some());
+        let stats = writer.stats();
+        assert_eq!(stats.skipped, 1);
+        assert_eq!(stats.writes, writes);
+    }
+
+    #[test]
+    fn other_values_are_written_during_backoff() {
+        let sysfs = FakeSysfs::qcom();
+        let writer = VerifiedWriter::new("PD验证");
+        fight(&writer, &fighting_node(&sysfs), "1");
+
+        // 重新上锁等其它值不受退避影响
+        let node = sysfs.paths().pd_verified();
+        writer.write(node, "0").unwrap();
+        assert_eq!(sysfs.read(node), "0");
+        assert!(wait_until(TIMEOUT, || writer.stats().verified == 1));
+        assert!(writer.stats().is_fighting());
+    }
+
+    #[test]
+    fn newer_write_supersedes_pending_verification() {
+        let sysfs = FakeSysfs::qcom();
+        let node = sysfs.paths().pd_verified();
+        let writer = VerifiedWriter::new("PD验证");
+
+        // 两次写入之间不等待：第一次的回读不应把第二次写入的值当作被改回
+        writer.write(node, "0").unwrap();
+        writer.write(node, "1").unwrap();
+        assert!(wait_until(TIMEOUT, || writer.stats().verified == 1));
+        assert_eq!(sysfs.read(node), "1");
+        let stats = writer.stats();
+        assert_eq!((stats.writes, stats.reverts), (2, 0));
+    }
+}
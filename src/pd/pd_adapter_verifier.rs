@@ -1,43 +1,43 @@
 use anyhow::Result;
-use log::{info, warn};
-
-#[cfg(unix)]
-use crate::common::constants::PD_ADAPTER_VERIFIED_PATH;
-#[cfg(unix)]
-use crate::monitoring::FileMonitor;
 #[cfg(unix)]
-use std::path::Path;
+use log::warn;
+use std::sync::Arc;
+
+use crate::common::Paths;
+use crate::pd::{NodeWriteStats, VerifiedWriter};
 
 /// PD适配器验证管理器
-pub struct PdAdapterVerifier;
+pub struct PdAdapterVerifier {
+    paths: Arc<Paths>,
+    writer: VerifiedWriter,
+}
 
 impl PdAdapterVerifier {
-    pub fn new() -> Result<Self> {
-        Ok(Self)
+    pub fn new(paths: Arc<Paths>) -> Result<Self> {
+        Ok(Self {
+            paths,
+            writer: VerifiedWriter::new("PD适配器验证"),
+        })
+    }
+
+    /// 节点写入统计
+    pub fn stats(&self) -> NodeWriteStats {
+        self.writer.stats()
     }
 
     /// 设置PD适配器验证状态
     #[cfg(unix)]
     pub fn set_pd_adapter_verified(&self, enable: bool) -> Result<()> {
         let value = if enable { "1" } else { "0" };
+        let node = self.paths.pd_adapter_verified();
 
         // 检查文件是否存在，不存在时记录警告但不报错
-        if !Path::new(PD_ADAPTER_VERIFIED_PATH).exists() {
-            warn!(
-                "PD适配器验证文件不存在，跳过设置: {}",
-                PD_ADAPTER_VERIFIED_PATH
-            );
+        if !node.exists() {
+            warn!("PD适配器验证文件不存在，跳过设置: {}", node.display());
             return Ok(());
         }
 
-        // 写入值到系统文件
-        FileMonitor::write_file_content(PD_ADAPTER_VERIFIED_PATH, value)?;
-
-        info!(
-            "已将PD适配器验证状态写入为{}: {}",
-            value, PD_ADAPTER_VERIFIED_PATH
-        );
-
-        Ok(())
+        // 写入并回读确认，被驱动改回时重试或退避
+        self.writer.write(node, value)
     }
 }
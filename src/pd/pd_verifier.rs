@@ -1,32 +1,40 @@
-use crate::common::PD_VERIFIED_PATH;
-use crate::monitoring::FileMonitor;
+use crate::common::Paths;
+use crate::pd::{NodeWriteStats, VerifiedWriter};
 use anyhow::Result;
-use log::{info, warn};
-use std::path::Path;
+use log::warn;
+use std::sync::Arc;
 
 /// PD验证管理器
-pub struct PdVerifier;
+pub struct PdVerifier {
+    paths: Arc<Paths>,
+    writer: VerifiedWriter,
+}
 
 impl PdVerifier {
-    pub fn new() -> Result<Self> {
-        Ok(Self)
+    pub fn new(paths: Arc<Paths>) -> Result<Self> {
+        Ok(Self {
+            paths,
+            writer: VerifiedWriter::new("PD验证"),
+        })
+    }
+
+    /// 节点写入统计
+    pub fn stats(&self) -> NodeWriteStats {
+        self.writer.stats()
     }
 
     /// 设置PD验证状态
     pub fn set_pd_verified(&self, enable: bool) -> Result<()> {
         let value = if enable { "1" } else { "0" };
+        let node = self.paths.pd_verified();
 
         // 检查文件是否存在，不存在时记录警告但不报错
-        if !Path::new(PD_VERIFIED_PATH).exists() {
-            warn!("PD验证文件不存在，跳过设置: {}", PD_VERIFIED_PATH);
+        if !node.exists() {
+            warn!("PD验证文件不存在，跳过设置: {}", node.display());
             return Ok(());
         }
 
-        // 写入值到系统文件
-        FileMonitor::write_file_content(PD_VERIFIED_PATH, value)?;
-
-        info!("已将PD验证状态写入为{}: {}", value, PD_VERIFIED_PATH);
-
-        Ok(())
+        // 写入并回读确认，被驱动改回时重试或退避
+        self.writer.write(node, value)
     }
 }
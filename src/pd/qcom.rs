@@ -0,0 +1,98 @@
+use anyhow::Result;
+use std::path::Path;
+use std::sync::Arc;
+#[cfg(feature = "broadcast-forger")]
+use std::thread;
+
+use crate::common::Paths;
+use crate::config::ConfigStore;
+#[cfg(feature = "broadcast-forger")]
+use crate::pd::{BroadcastForger, spawn_broadcast_forger_worker};
+use crate::pd::{ChargerBackend, NodeWriteStats, PdVerifier};
+#[cfg(feature = "broadcast-forger")]
+use crate::platform::ShutdownSignal;
+#[cfg(feature = "broadcast-forger")]
+use crate::session::SharedSession;
+
+/// 高通平台后端：`/sys/class/qcom-battery/pd_verifed`
+///
+/// 启用 `broadcast-forger` feature 时，会话开始时驱动金标动画广播伪造（broadcast-forger 线程负责发送）。
+pub struct QcomBackend {
+    paths: Arc<Paths>,
+    verifier: PdVerifier,
+    #[cfg(feature = "broadcast-forger")]
+    forger: Arc<BroadcastForger>,
+}
+
+impl QcomBackend {
+    #[cfg(feature = "broadcast-forger")]
+    pub fn new(paths: Arc<Paths>, config: Arc<ConfigStore>) -> Result<Self> {
+        let forger = BroadcastForger::new(Arc::clone(&paths), config)?;
+        Self::with_forger(paths, forger)
+    }
+
+    /// 未编译广播伪造时 `config` 不使用，签名与启用时保持一致
+    #[cfg(not(feature = "broadcast-forger"))]
+    pub fn new(paths: Arc<Paths>, _config: Arc<ConfigStore>) -> Result<Self> {
+        Ok(Self {
+            verifier: PdVerifier::new(Arc::clone(&paths))?,
+            paths,
+        })
+    }
+
+    /// 指定广播伪造器（测试中注入记录广播的发送通道）
+    #[cfg(feature = "broadcast-forger")]
+    pub fn with_forger(paths: Arc<Paths>, forger: BroadcastForger) -> Result<Self> {
+        Ok(Self {
+            verifier: PdVerifier::new(Arc::clone(&paths))?,
+            forger: Arc::new(forger),
+            paths,
+        })
+    }
+}
+
+impl ChargerBackend for QcomBackend {
+    fn name(&self) -> &'static str {
+        "qcom"
+    }
+
+    fn node_path(&self) -> &Path {
+        self.paths.pd_verified()
+    }
+
+    fn unlock(&self) -> Result<()> {
+        self.verifier.set_pd_verified(true)
+    }
+
+    fn write_stats(&self) -> Option<NodeWriteStats> {
+        Some(self.verifier.stats())
+    }
+
+    fn relock(&self) -> Result<()> {
+        self.verifier.set_pd_verified(false)
+    }
+
+    #[cfg(feature = "broadcast-forger")]
+    fn forge_now(&self, shutdown: &ShutdownSignal) -> Option<bool> {
+        Some(self.forger.send_burst(shutdown))
+    }
+
+    /// 每个新会话（会话 id 变化）唤醒 broadcast-forger 线程执行一次爆发序列
+    #[cfg(feature = "broadcast-forger")]
+    fn on_session_started(&self, session_id: u32) {
+        self.forger.session_started(session_id);
+    }
+
+    #[cfg(feature = "broadcast-forger")]
+    fn spawn_companion(
+        &self,
+        shutdown: Arc<ShutdownSignal>,
+        session: SharedSession,
+    ) -> Option<thread::JoinHandle<()>> {
+        Some(spawn_broadcast_forger_worker(
+            shutdown,
+            session,
+            Arc::clone(&self.forger),
+        ))
+    }
+}
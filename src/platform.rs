@@ -1,3 +1,13 @@
+#[cfg(unix)]
+pub mod notifier;
+pub mod shutdown;
 pub mod signal;
+#[cfg(unix)]
+pub mod timer;
 
+#[cfg(unix)]
+pub use notifier::Notifier;
+pub use shutdown::ShutdownSignal;
 pub use signal::install_signal_handlers;
+#[cfg(unix)]
+pub use timer::{IntervalTimer, WallClockTimer};
@@ -0,0 +1,96 @@
+use crate::common::FreePPSError;
+#[cfg(feature = "broadcast-forger")]
+use crate::platform::ShutdownSignal;
+use anyhow::Result;
+
+use libc::c_int;
+
+/// 线程间唤醒通知
+///
+/// 基于 eventfd：`notify` 使 fd 可读，等待方被唤醒后读取计数恢复为不可读。
+/// 等待前已发出的通知不会丢失，多次通知在一次唤醒中合并。
+pub struct Notifier {
+    event_fd: c_int,
+}
+
+impl Notifier {
+    pub fn new() -> Result<Self> {
+        let event_fd = unsafe { libc::eventfd(0, libc::EFD_CLOEXEC | libc::EFD_NONBLOCK) };
+        if event_fd == -1 {
+            return Err(FreePPSError::FileOperation(std::io::Error::last_os_error()).into());
+        }
+        Ok(Self { event_fd })
+    }
+
+    /// 供事件循环加入 epoll 的 fd
+    pub fn fd(&self) -> c_int {
+        self.event_fd
+    }
+
+    /// 唤醒等待方（只使用 `write`，重复调用无副作用）
+    pub fn notify(&self) {
+        let value: u64 = 1;
+        unsafe {
+            libc::write(
+                self.event_fd,
+                &value as *const u64 as *const libc::c_void,
+                std::mem::size_of::<u64>(),
+            );
+        }
+    }
+
+    /// 阻塞到收到通知或退出信号：返回 `true` 表示收到通知，`false` 表示已收到退出信号
+    // 目前只有 broadcast-forger 线程阻塞等待，事件循环经 epoll 等待
+    #[cfg(feature = "broadcast-forger")]
+    pub fn wait(&self, shutdown: &ShutdownSignal) -> bool {
+        let mut pollfds = [
+            libc::pollfd {
+                fd: self.event_fd,
+                events: libc::POLLIN,
+                revents: 0,
+            },
+            libc::pollfd {
+                fd: shutdown.fd(),
+                events: libc::POLLIN,
+                revents: 0,
+            },
+        ];
+        while shutdown.is_running() {
+            let result = unsafe { libc::poll(pollfds.as_mut_ptr(), pollfds.len() as _, -1) };
+            if result == -1 {
+                if std::io::Error::last_os_error().raw_os_error() == Some(libc::EINTR) {
+                    continue;
+                }
+                return false;
+            }
+            if !shutdown.is_running() {
+                break;
+            }
+            if pollfds[0].revents & libc::POLLIN != 0 {
+                self.clear();
+                return true;
+            }
+        }
+        false
+    }
+
+    /// 读取计数，恢复为不可读
+    pub fn clear(&self) {
+        let mut value: u64 = 0;
+        unsafe {
+            libc::read(
+                self.event_fd,
+                &mut value as *mut u64 as *mut libc::c_void,
+                std::mem::size_of::<u64>(),
+            );
+        }
+    }
+}
+
+impl Drop for Notifier {
+    fn drop(&mut self) {
+        unsafe {
+            libc::close(self.event_fd);
+        }
+    }
+}
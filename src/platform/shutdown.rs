@@ -0,0 +1,116 @@
+use crate::common::FreePPSError;
+use anyhow::Result;
+use std::sync::atomic::{AtomicBool, Ordering};
+#[cfg(feature = "broadcast-forger")]
+use std::time::Duration;
+
+#[cfg(unix)]
+use libc::c_int;
+
+/// 退出信号
+///
+/// 由运行标志和一个 eventfd 组成：`trigger` 先清除运行标志再写 eventfd，
+/// eventfd 注册在各线程的 epoll 集合中（见 `FileMonitor::add_shutdown_to_epoll`），
+/// 阻塞在 `epoll_wait(-1)` 的线程会立即被唤醒并检查运行标志后退出。
+/// eventfd 写入后不再读取，保持可读，所有监听它的 epoll 都能被唤醒。
+pub struct ShutdownSignal {
+    running: AtomicBool,
+    #[cfg(unix)]
+    event_fd: c_int,
+}
+
+impl ShutdownSignal {
+    pub fn new() -> Result<Self> {
+        #[cfg(unix)]
+        {
+            let event_fd = unsafe { libc::eventfd(0, libc::EFD_CLOEXEC | libc::EFD_NONBLOCK) };
+            if event_fd == -1 {
+                return Err(FreePPSError::FileOperation(std::io::Error::last_os_error()).into());
+            }
+            Ok(Self {
+                running: AtomicBool::new(true),
+                event_fd,
+            })
+        }
+
+        #[cfg(not(unix))]
+        Ok(Self {
+            running: AtomicBool::new(true),
+        })
+    }
+
+    /// 是否仍在运行（未收到退出信号）
+    pub fn is_running(&self) -> bool {
+        self.running.load(Ordering::SeqCst)
+    }
+
+    /// 触发退出并唤醒所有监听 eventfd 的线程
+    ///
+    /// 只使用原子操作和 `write`，可以在异步信号处理器中调用；重复调用无副作用。
+    pub fn trigger(&self) {
+        self.running.store(false, Ordering::SeqCst);
+
+        #[cfg(unix)]
+        {
+            let value: u64 = 1;
+            unsafe {
+                libc::write(
+                    self.event_fd,
+                    &value as *const u64 as *const libc::c_void,
+                    std::mem::size_of::<u64>(),
+                );
+            }
+        }
+    }
+
+    /// eventfd，用于注册到 epoll
+    #[cfg(unix)]
+    pub fn fd(&self) -> c_int {
+        self.event_fd
+    }
+
+    /// 可被退出信号打断的睡眠：返回 `true` 表示已收到退出信号
+    ///
+    /// 周期工作都由事件循环的 timerfd 驱动，目前只有 broadcast-forger 线程在广播之间等待。
+    #[cfg(feature = "broadcast-forger")]
+    pub fn wait_timeout(&self, timeout: Duration) -> bool {
+        if !self.is_running() {
+            return true;
+        }
+
+        #[cfg(unix)]
+        {
+            let mut pollfd = libc::pollfd {
+                fd: self.event_fd,
+                events: libc::POLLIN,
+                revents: 0,
+            };
+            let deadline = std::time::Instant::now() + timeout;
+            loop {
+                let remaining = deadline.saturating_duration_since(std::time::Instant::now());
+                let timeout_ms = remaining.as_millis().min(c_int::MAX as u128) as c_int;
+                let result = unsafe { libc::poll(&mut pollfd, 1, timeout_ms) };
+                // EINTR 时继续等待剩余时间
+                if result != -1
+                    || std::io::Error::last_os_error().raw_os_error() != Some(libc::EINTR)
+                {
+                    break;
+                }
+            }
+        }
+
+        #[cfg(not(unix))]
+        std::thread::sleep(timeout);
+
+        !self.is_running()
+    }
+}
+
+impl Drop for ShutdownSignal {
+    fn drop(&mut self) {
+        #[cfg(unix)]
+        unsafe {
+            libc::close(self.event_fd);
+        }
+    }
+}
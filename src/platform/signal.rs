@@ -1,33 +1,33 @@
+use crate::platform::ShutdownSignal;
 use log::error;
 use std::sync::Arc;
-use std::sync::atomic::AtomicBool;
 
 #[cfg(unix)]
 use std::sync::atomic::AtomicPtr;
 
 #[cfg(unix)]
-static RUNNING_FLAG_PTR: AtomicPtr<AtomicBool> = AtomicPtr::new(std::ptr::null_mut());
+static SHUTDOWN_SIGNAL_PTR: AtomicPtr<ShutdownSignal> = AtomicPtr::new(std::ptr::null_mut());
 
 #[cfg(unix)]
 /// # Safety
-/// 该函数作为异步信号处理器调用，需要保证 `RUNNING_FLAG_PTR` 指向的内存有效。
+/// 该函数作为异步信号处理器调用，需要保证 `SHUTDOWN_SIGNAL_PTR` 指向的内存有效。
 unsafe extern "C" fn termination_signal_handler(_sig: libc::c_int) {
-    let ptr = RUNNING_FLAG_PTR.load(std::sync::atomic::Ordering::SeqCst);
+    let ptr = SHUTDOWN_SIGNAL_PTR.load(std::sync::atomic::Ordering::SeqCst);
     if !ptr.is_null() {
         unsafe {
-            (*ptr).store(false, std::sync::atomic::Ordering::Relaxed);
+            (*ptr).trigger();
         }
     }
 }
 
 #[cfg(unix)]
-/// 注册用于捕获终止信号的处理函数。
+/// 注册用于捕获终止信号的处理函数，收到 SIGINT/SIGTERM 时触发 `shutdown`。
 ///
 /// # Safety
-/// 调用者必须保证传入的 `running` 在整个信号处理期间保持有效。
-pub fn install_signal_handlers(running: &Arc<AtomicBool>) {
-    RUNNING_FLAG_PTR.store(
-        Arc::as_ptr(running) as *mut AtomicBool,
+/// 调用者必须保证传入的 `shutdown` 在整个信号处理期间保持有效。
+pub fn install_signal_handlers(shutdown: &Arc<ShutdownSignal>) {
+    SHUTDOWN_SIGNAL_PTR.store(
+        Arc::as_ptr(shutdown) as *mut ShutdownSignal,
         std::sync::atomic::Ordering::SeqCst,
     );
 
@@ -48,4 +48,4 @@ pub fn install_signal_handlers(running: &Arc<AtomicBool>) {
 }
 
 #[cfg(not(unix))]
-pub fn install_signal_handlers(_: &Arc<AtomicBool>) {}
+pub fn install_signal_handlers(_: &Arc<ShutdownSignal>) {}
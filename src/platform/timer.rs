@@ -0,0 +1,144 @@
+use crate::common::FreePPSError;
+use anyhow::Result;
+use std::time::Duration;
+
+use libc::c_int;
+
+/// 基于 timerfd 的墙上时钟定时器
+///
+/// 以 `CLOCK_REALTIME` 绝对时间设置，系统时间被调整后仍在目标时刻触发；
+/// fd 注册到 epoll 后，到期时可读，由 `clear` 读取到期次数恢复为不可读。
+pub struct WallClockTimer {
+    timer_fd: c_int,
+}
+
+impl WallClockTimer {
+    pub fn new() -> Result<Self> {
+        Ok(Self {
+            timer_fd: create(libc::CLOCK_REALTIME)?,
+        })
+    }
+
+    /// timerfd，用于注册到 epoll
+    pub fn fd(&self) -> c_int {
+        self.timer_fd
+    }
+
+    /// 在 Unix 时间戳 `secs` 时触发一次（覆盖之前的设置）
+    pub fn arm_at(&self, secs: u64) -> Result<()> {
+        let value = libc::timespec {
+            tv_sec: secs as libc::time_t,
+            tv_nsec: 0,
+        };
+        settime(self.timer_fd, libc::TFD_TIMER_ABSTIME, value, ZERO)
+    }
+
+    /// 取消尚未到期的设置
+    pub fn disarm(&self) -> Result<()> {
+        settime(self.timer_fd, 0, ZERO, ZERO)
+    }
+
+    /// 读取到期次数，返回是否已到期
+    pub fn clear(&self) -> bool {
+        read_expirations(self.timer_fd)
+    }
+}
+
+impl Drop for WallClockTimer {
+    fn drop(&mut self) {
+        unsafe {
+            libc::close(self.timer_fd);
+        }
+    }
+}
+
+/// 基于 timerfd 的周期定时器（`CLOCK_MONOTONIC`）
+///
+/// 充电会话期间的周期工作（温控、遥测采样）由事件循环在会话开始时启动、结束时停止，
+/// 不在会话之外唤醒。
+pub struct IntervalTimer {
+    timer_fd: c_int,
+}
+
+impl IntervalTimer {
+    pub fn new() -> Result<Self> {
+        Ok(Self {
+            timer_fd: create(libc::CLOCK_MONOTONIC)?,
+        })
+    }
+
+    /// timerfd，用于注册到 epoll
+    pub fn fd(&self) -> c_int {
+        self.timer_fd
+    }
+
+    /// 从现在起每隔 `interval` 触发一次（覆盖之前的设置）
+    pub fn arm(&self, interval: Duration) -> Result<()> {
+        let interval = libc::timespec {
+            tv_sec: interval.as_secs() as libc::time_t,
+            tv_nsec: interval.subsec_nanos() as libc::c_long,
+        };
+        settime(self.timer_fd, 0, interval, interval)
+    }
+
+    /// 停止触发
+    pub fn disarm(&self) -> Result<()> {
+        settime(self.timer_fd, 0, ZERO, ZERO)
+    }
+
+    /// 读取到期次数，返回是否已到期
+    pub fn clear(&self) -> bool {
+        read_expirations(self.timer_fd)
+    }
+}
+
+impl Drop for IntervalTimer {
+    fn drop(&mut self) {
+        unsafe {
+            libc::close(self.timer_fd);
+        }
+    }
+}
+
+const ZERO: libc::timespec = libc::timespec {
+    tv_sec: 0,
+    tv_nsec: 0,
+};
+
+fn create(clock: libc::clockid_t) -> Result<c_int> {
+    let timer_fd = unsafe { libc::timerfd_create(clock, libc::TFD_CLOEXEC | libc::TFD_NONBLOCK) };
+    if timer_fd == -1 {
+        return Err(FreePPSError::FileOperation(std::io::Error::last_os_error()).into());
+    }
+    Ok(timer_fd)
+}
+
+/// `value` 为首次到期时间，`interval` 非零时此后周期触发；两者都为零时取消
+fn settime(
+    timer_fd: c_int,
+    flags: c_int,
+    value: libc::timespec,
+    interval: libc::timespec,
+) -> Result<()> {
+    let spec = libc::itimerspec {
+        it_interval: interval,
+        it_value: value,
+    };
+    let result = unsafe { libc::timerfd_settime(timer_fd, flags, &spec, std::ptr::null_mut()) };
+    if result == -1 {
+        return Err(FreePPSError::FileOperation(std::io::Error::last_os_error()).into());
+    }
+    Ok(())
+}
+
+fn read_expirations(timer_fd: c_int) -> bool {
+    let mut expirations: u64 = 0;
+    let bytes = unsafe {
+        libc::read(
+            timer_fd,
+            &mut expirations as *mut u64 as *mut libc::c_void,
+            std::mem::size_of::<u64>(),
+        )
+    };
+    bytes == std::mem::size_of::<u64>() as isize && expirations > 0
+}
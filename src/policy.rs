@@ -0,0 +1,161 @@
+//! 解锁策略
+//!
+//! 温控、电量阈值等策略可以暂时撤销解锁：策略触发时在 [`UnlockHolds`] 中登记一个暂停原因并把解锁节点写回 0，
+//! 暂停期间所有解锁写入（初始化、free=1、拔出写回、溢出重新同步）都由 [`GuardedBackend`] 拦截，
+//! 全部原因解除后再由策略恢复解锁。
+
+pub mod adapters;
+pub mod schedule;
+pub mod soc;
+pub mod thermal;
+
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+use anyhow::Result;
+use log::info;
+
+use crate::pd::{ChargerBackend, NodeWriteStats};
+use crate::platform::ShutdownSignal;
+use crate::session::SharedSession;
+
+/// 暂停解锁的原因
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum HoldReason {
+    Thermal,
+    Soc,
+}
+
+impl HoldReason {
+    pub const ALL: [Self; 2] = [Self::Thermal, Self::Soc];
+
+    /// 说明开头的图标（module.prop 描述前缀据此识别本模块写入的暂停说明）
+    pub const fn icon(self) -> &'static str {
+        match self {
+            Self::Thermal => "🌡️",
+            Self::Soc => "🔋",
+        }
+    }
+}
+
+/// 当前生效的解锁暂停（原因 → 显示在 module.prop / status 中的说明）
+#[derive(Debug, Default)]
+pub struct UnlockHolds {
+    holds: Mutex<Vec<(HoldReason, String)>>,
+}
+
+impl UnlockHolds {
+    pub fn shared() -> Arc<Self> {
+        Arc::new(Self::default())
+    }
+
+    /// 登记或更新一个暂停原因，返回是否为新登记
+    pub fn hold(&self, reason: HoldReason, label: impl Into<String>) -> bool {
+        let mut holds = self.holds.lock().unwrap();
+        let label = label.into();
+        match holds.iter_mut().find(|(r, _)| *r == reason) {
+            Some(hold) => {
+                hold.1 = label;
+                false
+            }
+            None => {
+                holds.push((reason, label));
+                holds.sort_by_key(|(r, _)| *r);
+                true
+            }
+        }
+    }
+
+    /// 解除一个暂停原因，返回此前是否存在
+    pub fn release(&self, reason: HoldReason) -> bool {
+        let mut holds = self.holds.lock().unwrap();
+        let before = holds.len();
+        holds.retain(|(r, _)| *r != reason);
+        holds.len() != before
+    }
+
+    pub fn is_held(&self) -> bool {
+        !self.holds.lock().unwrap().is_empty()
+    }
+
+    /// 各暂停原因的说明
+    pub fn labels(&self) -> Vec<String> {
+        self.holds
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|(_, label)| label.clone())
+            .collect()
+    }
+}
+
+/// 受策略约束的后端：存在暂停原因时跳过解锁写入，其余操作原样转发
+pub struct GuardedBackend {
+    inner: Arc<dyn ChargerBackend>,
+    holds: Arc<UnlockHolds>,
+}
+
+impl GuardedBackend {
+    pub fn new(inner: Arc<dyn ChargerBackend>, holds: Arc<UnlockHolds>) -> Self {
+        Self { inner, holds }
+    }
+}
+
+impl ChargerBackend for GuardedBackend {
+    fn name(&self) -> &'static str {
+        self.inner.name()
+    }
+
+    fn node_path(&self) -> &Path {
+        self.inner.node_path()
+    }
+
+    fn detect(&self) -> bool {
+        self.inner.detect()
+    }
+
+    fn unlock(&self) -> Result<()> {
+        if self.holds.is_held() {
+            info!(
+                "[{}] 解锁已被策略暂停（{}），跳过写入1",
+                self.name(),
+                self.holds.labels().join("，")
+            );
+            return Ok(());
+        }
+        self.inner.unlock()
+    }
+
+    fn relock(&self) -> Result<()> {
+        self.inner.relock()
+    }
+
+    fn read_state(&self) -> Result<Option<bool>> {
+        self.inner.read_state()
+    }
+
+    fn write_stats(&self) -> Option<NodeWriteStats> {
+        self.inner.write_stats()
+    }
+
+    fn rearm_on_power_supply_event(&self) -> bool {
+        self.inner.rearm_on_power_supply_event()
+    }
+
+    fn forge_now(&self, shutdown: &ShutdownSignal) -> Option<bool> {
+        self.inner.forge_now(shutdown)
+    }
+
+    fn on_session_started(&self, session_id: u32) {
+        self.inner.on_session_started(session_id)
+    }
+
+    fn spawn_companion(
+        &self,
+        shutdown: Arc<ShutdownSignal>,
+        session: SharedSession,
+    ) -> Option<thread::JoinHandle<()>> {
+        self.inner.spawn_companion(shutdown, session)
+    }
+}
@@ -0,0 +1,224 @@
+//! 按充电头记忆的解锁决定
+//!
+//! 用 `adapter_svid` / `apdo_max` / `real_type` 为充电头生成指纹，在模块目录的 `adapters.conf`
+//! 中为每个指纹记录用户的决定（始终解锁 / 从不解锁 / 不伪造金标动画），插入已知充电头时自动应用，
+//! 个别与解锁不兼容的充电头无需全局切换 free。
+//!
+//! ```text
+//! 0000-65W-PD=unlock
+//! 0000-33W-PD=lock
+//! 0000-100W-PD=unlock,no-forge
+//! ```
+
+use std::fs;
+use std::path::Path;
+
+use anyhow::Result;
+use log::warn;
+use serde::Serialize;
+
+use crate::common::{FreePPSError, utils};
+use crate::session::AdapterInfo;
+
+/// 充电头指纹 `<svid>-<apdo_max>W-<类型>`
+///
+/// 握手过程中 real_type 会在 USB_PD / PD_PPS 之间变化，且是否解锁本身也会影响能否进入 PD_PPS，
+/// 因此 PD 类充电头统一记为 `PD`，其余类型保留原值；SVID 未知时无法生成指纹。
+pub fn fingerprint(adapter: &AdapterInfo) -> Option<String> {
+    let svid = adapter.svid.as_deref()?;
+    let apdo_max = adapter
+        .apdo_max
+        .map_or_else(|| "-".to_string(), |w| format!("{}W", w));
+    let kind = if adapter.is_pd() {
+        "PD"
+    } else {
+        adapter.real_type.as_deref().unwrap_or("-")
+    };
+    Some(format!("{}-{}-{}", svid, apdo_max, kind))
+}
+
+/// 对某个充电头的决定
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct AdapterRule {
+    /// `Some(true)` 始终解锁，`Some(false)` 从不解锁，`None` 按 free 模式
+    pub unlock: Option<bool>,
+    /// 是否允许伪造金标动画广播
+    pub forge: bool,
+}
+
+impl Default for AdapterRule {
+    fn default() -> Self {
+        Self {
+            unlock: None,
+            forge: true,
+        }
+    }
+}
+
+impl AdapterRule {
+    /// 解析逗号分隔的决定：`unlock` / `lock` / `no-forge`
+    pub fn parse(value: &str) -> Option<Self> {
+        let mut rule = Self::default();
+        for token in value.split(',').map(str::trim) {
+            match token {
+                "unlock" => rule.unlock = Some(true),
+                "lock" => rule.unlock = Some(false),
+                "no-forge" => rule.forge = false,
+                _ => return None,
+            }
+        }
+        (rule != Self::default()).then_some(rule)
+    }
+
+    /// 写入规则文件的决定文本
+    pub fn decision(&self) -> String {
+        let mut tokens = Vec::new();
+        match self.unlock {
+            Some(true) => tokens.push("unlock"),
+            Some(false) => tokens.push("lock"),
+            None => {}
+        }
+        if !self.forge {
+            tokens.push("no-forge");
+        }
+        tokens.join(",")
+    }
+}
+
+/// `adapters.conf` 中的全部规则（按文件中的顺序）
+#[derive(Debug, Clone, Default)]
+pub struct AdapterRules {
+    rules: Vec<(String, AdapterRule)>,
+}
+
+impl AdapterRules {
+    /// 读取规则文件；文件不存在时为空，无效的行跳过
+    pub fn load(path: &Path) -> Result<Self> {
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+
+        let content = fs::read_to_string(path).map_err(FreePPSError::FileOperation)?;
+        let rules = utils::parse_key_values(&content)
+            .into_iter()
+            .filter_map(|(fingerprint, value)| match AdapterRule::parse(&value) {
+                Some(rule) => Some((fingerprint, rule)),
+                None => {
+                    warn!("无效的充电头规则 {}={}，已忽略", fingerprint, value);
+                    None
+                }
+            })
+            .collect();
+        Ok(Self { rules })
+    }
+
+    /// 查找指纹对应的规则（同一指纹出现多次时以最后一条为准）
+    pub fn get(&self, fingerprint: &str) -> Option<AdapterRule> {
+        self.rules
+            .iter()
+            .rev()
+            .find(|(fp, _)| fp == fingerprint)
+            .map(|(_, rule)| *rule)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &(String, AdapterRule)> {
+        self.rules.iter()
+    }
+
+    pub fn set(&mut self, fingerprint: &str, rule: AdapterRule) {
+        self.remove(fingerprint);
+        self.rules.push((fingerprint.to_string(), rule));
+    }
+
+    /// 删除指纹对应的规则，返回此前是否存在
+    pub fn remove(&mut self, fingerprint: &str) -> bool {
+        let before = self.rules.len();
+        self.rules.retain(|(fp, _)| fp != fingerprint);
+        self.rules.len() != before
+    }
+
+    /// 写回规则文件（先写临时文件再替换，守护进程读取时不会读到半个文件）
+    pub fn save(&self, path: &Path) -> Result<()> {
+        let mut content = String::from("# FreePPS 充电头规则：<指纹>=unlock|lock[,no-forge]\n");
+        for (fingerprint, rule) in &self.rules {
+            content.push_str(&format!("{}={}\n", fingerprint, rule.decision()));
+        }
+        let tmp = path.with_extension("conf.tmp");
+        fs::write(&tmp, content).map_err(FreePPSError::FileOperation)?;
+        fs::rename(&tmp, path).map_err(FreePPSError::FileOperation)?;
+        Ok(())
+    }
+}
+
+/// 查找当前充电头的规则；规则文件读取失败时视为没有规则
+pub fn lookup(path: &Path, adapter: &AdapterInfo) -> Option<AdapterRule> {
+    let fingerprint = fingerprint(adapter)?;
+    AdapterRules::load(path)
+        .inspect_err(|e| warn!("读取充电头规则失败: {}", e))
+        .ok()?
+        .get(&fingerprint)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::FakeSysfs;
+
+    fn adapter(real_type: &str) -> AdapterInfo {
+        AdapterInfo {
+            real_type: Some(real_type.to_string()),
+            svid: Some("0000".to_string()),
+            apdo_max: Some(65),
+        }
+    }
+
+    #[test]
+    fn fingerprint_ignores_real_type_change_during_handshake() {
+        assert_eq!(fingerprint(&adapter("USB_PD")).unwrap(), "0000-65W-PD");
+        assert_eq!(fingerprint(&adapter("PD_PPS")).unwrap(), "0000-65W-PD");
+    }
+
+    #[test]
+    fn unknown_adapter_has_no_fingerprint() {
+        assert_eq!(fingerprint(&AdapterInfo::default()), None);
+    }
+
+    #[test]
+    fn rule_parses_decisions() {
+        assert_eq!(AdapterRule::parse("bogus"), None);
+        let rule = AdapterRule::parse("unlock, no-forge").unwrap();
+        assert_eq!(rule.decision(), "unlock,no-forge");
+    }
+
+    #[test]
+    fn saved_rules_load_back() {
+        let sysfs = FakeSysfs::new();
+        let path = sysfs.paths().adapter_rules();
+        let rule = AdapterRule::parse("unlock,no-forge").unwrap();
+        let mut rules = AdapterRules::default();
+        rules.set("0000-65W-PD", rule);
+        rules.set("0000-33W-PD", AdapterRule::parse("lock").unwrap());
+        rules.save(path).unwrap();
+
+        let loaded = AdapterRules::load(path).unwrap();
+        assert_eq!(loaded.get("0000-65W-PD"), Some(rule));
+        assert_eq!(loaded.get("2717-120W-PD"), None);
+    }
+
+    #[test]
+    fn lookup_finds_rule_by_adapter_fingerprint() {
+        let sysfs = FakeSysfs::new();
+        let path = sysfs.paths().adapter_rules();
+        let mut rules = AdapterRules::default();
+        rules.set(
+            "0000-65W-PD",
+            AdapterRule::parse("unlock,no-forge").unwrap(),
+        );
+        rules.save(path).unwrap();
+
+        assert_eq!(
+            lookup(path, &adapter("PD_PPS")).map(|rule| rule.forge),
+            Some(false)
+        );
+    }
+}
@@ -0,0 +1,139 @@
+//! 定时切换 free 状态
+//!
+//! 在配置的时间段内解锁 PPS（free=1），时间段以外恢复原厂行为（free=0）。
+//! 只在时间段边界写入 free 文件，边界之间通过 action.sh / 控制 socket 的手动切换保持到下一个边界。
+
+use crate::config::ConfigSection;
+
+const SECS_PER_DAY: u32 = 24 * 3600;
+
+/// 一个解锁时间段 `[start, end)`（当天的第几秒），`end < start` 表示跨越午夜
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Window {
+    start: u32,
+    end: u32,
+}
+
+impl Window {
+    fn contains(&self, secs: u32) -> bool {
+        if self.start <= self.end {
+            (self.start..self.end).contains(&secs)
+        } else {
+            secs >= self.start || secs < self.end
+        }
+    }
+}
+
+/// 定时配置（freepps.conf 中的 `schedule`，多个时间段用逗号分隔，`off` 或缺省为关闭）
+///
+/// ```text
+/// schedule=07:00-23:00
+/// schedule=07:00-12:00,13:30-23:00
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ScheduleConfig {
+    pub windows: Vec<Window>,
+}
+
+impl ConfigSection for ScheduleConfig {
+    fn apply(&mut self, key: &str, value: &str) -> Option<Result<(), String>> {
+        if key != "schedule" {
+            return None;
+        }
+        let result = if value == "off" {
+            Ok(Vec::new())
+        } else {
+            parse_windows(value)
+                .ok_or_else(|| "需要 HH:MM-HH:MM（逗号分隔多个时间段）或off".to_string())
+        };
+        Some(result.map(|windows| self.windows = windows))
+    }
+}
+
+impl ScheduleConfig {
+    pub fn is_enabled(&self) -> bool {
+        !self.windows.is_empty()
+    }
+
+    /// 当天第 `secs` 秒是否处于解锁时间段
+    pub fn is_unlocked_at(&self, secs: u32) -> bool {
+        self.windows.iter().any(|window| window.contains(secs))
+    }
+
+    /// 距下一个时间段边界的秒数（至少 1 秒）
+    pub fn secs_until_boundary(&self, secs: u32) -> u32 {
+        self.windows
+            .iter()
+            .flat_map(|window| [window.start, window.end])
+            .map(|boundary| (boundary + SECS_PER_DAY - secs - 1) % SECS_PER_DAY + 1)
+            .min()
+            .unwrap_or(SECS_PER_DAY)
+    }
+}
+
+fn parse_windows(value: &str) -> Option<Vec<Window>> {
+    value
+        .split(',')
+        .map(str::trim)
+        .filter(|window| !window.is_empty())
+        .map(|window| {
+            let (start, end) = window.split_once('-')?;
+            let window = Window {
+                start: parse_time(start)?,
+                end: parse_time(end)?,
+            };
+            (window.start != window.end).then_some(window)
+        })
+        .collect::<Option<Vec<_>>>()
+        .filter(|windows| !windows.is_empty())
+}
+
+/// 解析 `HH:MM`（`24:00` 视为午夜）
+fn parse_time(value: &str) -> Option<u32> {
+    let (hour, minute) = value.trim().split_once(':')?;
+    let (hour, minute) = (hour.parse::<u32>().ok()?, minute.parse::<u32>().ok()?);
+    match (hour, minute) {
+        (24, 0) => Some(0),
+        (0..=23, 0..=59) => Some(hour * 3600 + minute * 60),
+        _ => None,
+    }
+}
+
+/// 把当天的第几秒格式化为 `HH:MM:SS`
+pub fn format_secs_of_day(secs: u32) -> String {
+    format!(
+        "{:02}:{:02}:{:02}",
+        secs / 3600 % 24,
+        secs / 60 % 60,
+        secs % 60
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn windows_and_boundaries_wrap_midnight() {
+        let config = ScheduleConfig {
+            windows: parse_windows("07:00-23:00, 23:30-01:00").unwrap(),
+        };
+        let at = |h: u32, m: u32| h * 3600 + m * 60;
+
+        assert!(!config.is_unlocked_at(at(6, 59)));
+        assert!(config.is_unlocked_at(at(7, 0)));
+        assert!(!config.is_unlocked_at(at(23, 0)));
+        assert!(config.is_unlocked_at(at(0, 30)));
+        assert!(!config.is_unlocked_at(at(1, 0)));
+
+        assert_eq!(config.secs_until_boundary(at(6, 0)), 3600);
+        // 正好落在边界上时等待下一个边界
+        assert_eq!(config.secs_until_boundary(at(7, 0)), 16 * 3600);
+        assert_eq!(config.secs_until_boundary(at(23, 45)), 75 * 60);
+        assert_eq!(config.secs_until_boundary(at(1, 0)), 6 * 3600);
+
+        assert_eq!(parse_windows("7-23"), None);
+        assert_eq!(parse_windows("07:00-07:00"), None);
+        assert_eq!(parse_windows("25:00-07:00"), None);
+    }
+}
@@ -0,0 +1,128 @@
+//! 电量阈值策略
+//!
+//! 充电会话中电量达到阈值（如 80%）后暂停解锁，回落到普通充电以减少夜间充电的发热与电池损耗；
+//! 会话结束（拔出）时解除，下次插电重新解锁。
+
+use crate::config::ConfigSection;
+
+/// 电量阈值配置（freepps.conf 中的 `soc_limit`，如 `soc_limit=80`，`off` 或缺省为关闭）
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SocConfig {
+    pub limit: Option<u8>,
+}
+
+impl ConfigSection for SocConfig {
+    fn apply(&mut self, key: &str, value: &str) -> Option<Result<(), String>> {
+        if key != "soc_limit" {
+            return None;
+        }
+        let result = match value {
+            "off" => Ok(None),
+            _ => match value.parse::<u8>() {
+                Ok(limit) if (1..=100).contains(&limit) => Ok(Some(limit)),
+                _ => Err("需要1~100的电量百分比或off".to_string()),
+            },
+        };
+        Some(result.map(|limit| self.limit = limit))
+    }
+}
+
+/// 电量阈值策略动作
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SocAction {
+    /// 电量达到阈值：暂停解锁
+    Hold(u8),
+    /// 会话结束：恢复解锁
+    Release,
+}
+
+/// 电量阈值状态机：每个会话最多暂停一次，会话结束即解除
+#[derive(Debug, Clone)]
+pub struct SocPolicy {
+    limit: u8,
+    /// 已暂停解锁的会话 id
+    held_for: Option<u32>,
+}
+
+impl SocPolicy {
+    pub fn new(limit: u8) -> Self {
+        Self {
+            limit,
+            held_for: None,
+        }
+    }
+
+    pub fn limit(&self) -> u8 {
+        self.limit
+    }
+
+    /// 修改阈值（已暂停的会话保持暂停，直到会话结束）
+    pub fn set_limit(&mut self, limit: u8) {
+        self.limit = limit;
+    }
+
+    /// 当前是否处于暂停解锁状态
+    pub fn is_held(&self) -> bool {
+        self.held_for.is_some()
+    }
+
+    /// 输入一次会话状态与电量，需要切换时返回对应动作
+    pub fn update(
+        &mut self,
+        active: bool,
+        session_id: u32,
+        capacity: Option<u8>,
+    ) -> Option<SocAction> {
+        if let Some(held_for) = self.held_for
+            && (!active || held_for != session_id)
+        {
+            self.held_for = None;
+            return Some(SocAction::Release);
+        }
+
+        match capacity {
+            Some(capacity) if active && self.held_for.is_none() && capacity >= self.limit => {
+                self.held_for = Some(session_id);
+                Some(SocAction::Hold(capacity))
+            }
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn holds_when_capacity_reaches_limit() {
+        let mut policy = SocPolicy::new(80);
+        assert_eq!(policy.update(true, 1, Some(79)), None);
+        assert_eq!(policy.update(true, 1, Some(80)), Some(SocAction::Hold(80)));
+    }
+
+    #[test]
+    fn hold_is_kept_when_capacity_drops_during_session() {
+        let mut policy = SocPolicy::new(80);
+        policy.update(true, 1, Some(80));
+        // 暂停后电量回落也不反复切换
+        assert_eq!(policy.update(true, 1, Some(78)), None);
+    }
+
+    #[test]
+    fn hold_is_released_once_when_session_ends() {
+        let mut policy = SocPolicy::new(80);
+        policy.update(true, 1, Some(80));
+        assert_eq!(policy.update(false, 1, Some(78)), Some(SocAction::Release));
+        assert_eq!(policy.update(false, 1, Some(90)), None);
+    }
+
+    #[test]
+    fn new_session_releases_then_reevaluates() {
+        let mut policy = SocPolicy::new(80);
+        policy.update(true, 2, Some(85));
+        // 两次检查之间已换成新会话：先解除，下一次检查再按新会话的电量判断
+        assert_eq!(policy.update(true, 3, Some(85)), Some(SocAction::Release));
+        assert_eq!(policy.update(true, 3, Some(85)), Some(SocAction::Hold(85)));
+    }
+}
@@ -0,0 +1,226 @@
+//! 电池温度保护
+//!
+//! 充电会话期间定期读取 `battery/temp`（及可选的 thermal zone），取其中最高温度：
+//! 超过上限时暂停解锁并把节点写回 0，降到恢复温度以下后恢复解锁（滞回，避免在阈值附近反复切换）。
+
+use std::path::PathBuf;
+use std::time::Duration;
+
+use crate::common::Paths;
+use crate::config::{self, ConfigSection};
+use crate::monitoring::FileMonitor;
+
+/// 温控配置（freepps.conf 中的 `thermal*` 项）
+///
+/// ```text
+/// thermal_guard=on                 # on / off（默认 off）
+/// thermal_limit_c=46               # 达到该温度暂停解锁
+/// thermal_resume_c=42              # 降到该温度以下恢复解锁
+/// thermal_zones=thermal_zone5      # 额外参与判断的 thermal zone（逗号分隔，可选）
+/// thermal_interval_ms=5000
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct ThermalConfig {
+    pub enabled: bool,
+    pub limit_c: f32,
+    pub resume_c: f32,
+    pub zones: Vec<String>,
+    pub interval: Duration,
+}
+
+impl Default for ThermalConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            limit_c: 46.0,
+            resume_c: 42.0,
+            zones: Vec::new(),
+            interval: Duration::from_secs(5),
+        }
+    }
+}
+
+impl ConfigSection for ThermalConfig {
+    fn apply(&mut self, key: &str, value: &str) -> Option<Result<(), String>> {
+        let result = match key {
+            "thermal_guard" => config::parse_switch(value).map(|enabled| self.enabled = enabled),
+            "thermal_limit_c" => parse_celsius(value).map(|limit| self.limit_c = limit),
+            "thermal_resume_c" => parse_celsius(value).map(|resume| self.resume_c = resume),
+            "thermal_zones" => parse_zones(value).map(|zones| self.zones = zones),
+            "thermal_interval_ms" => {
+                config::parse_millis(value, 100).map(|interval| self.interval = interval)
+            }
+            _ => return None,
+        };
+        Some(result)
+    }
+
+    fn finish(&mut self) -> Result<(), String> {
+        if self.resume_c >= self.limit_c {
+            return Err(format!(
+                "thermal_resume_c({})必须低于thermal_limit_c({})",
+                self.resume_c, self.limit_c
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// 解析逗号分隔的 thermal zone 名称，只接受 `thermal_zone<N>`（名称会拼接为 sysfs 路径）
+fn parse_zones(value: &str) -> Result<Vec<String>, String> {
+    value
+        .split(',')
+        .map(str::trim)
+        .filter(|zone| !zone.is_empty())
+        .map(|zone| {
+            let valid = zone.strip_prefix("thermal_zone").is_some_and(|index| {
+                !index.is_empty() && index.bytes().all(|b| b.is_ascii_digit())
+            });
+            if valid {
+                Ok(zone.to_string())
+            } else {
+                Err(format!("{} 不是 thermal_zone<N> 形式的名称", zone))
+            }
+        })
+        .collect()
+}
+
+fn parse_celsius(value: &str) -> Result<f32, String> {
+    match value.parse::<f32>() {
+        Ok(celsius) if celsius.is_finite() => Ok(celsius),
+        _ => Err("需要摄氏度数值".to_string()),
+    }
+}
+
+impl ThermalConfig {
+    /// 参与判断的 thermal zone 温度节点
+    pub fn zone_paths(&self, paths: &Paths) -> Vec<PathBuf> {
+        self.zones
+            .iter()
+            .map(|zone| paths.thermal_zone_temp(zone))
+            .collect()
+    }
+}
+
+/// 读取当前最高温度（℃）；所有节点都不可读时返回 `None`
+///
+/// battery/temp 单位为 0.1℃，thermal zone 单位为 0.001℃。
+pub fn read_max_temp(paths: &Paths, zones: &[PathBuf]) -> Option<f32> {
+    let read = |path: &std::path::Path, scale: f32| {
+        FileMonitor::read_file_content(path)
+            .ok()
+            .and_then(|value| value.parse::<i64>().ok())
+            .map(|raw| raw as f32 / scale)
+    };
+
+    std::iter::once(read(paths.battery_temp(), 10.0))
+        .chain(zones.iter().map(|zone| read(zone, 1000.0)))
+        .flatten()
+        .reduce(f32::max)
+}
+
+/// 温控动作
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ThermalAction {
+    /// 达到上限：暂停解锁
+    Trip(f32),
+    /// 降到恢复温度以下：恢复解锁
+    Recover(f32),
+}
+
+/// 带滞回的温控状态机
+#[derive(Debug, Clone)]
+pub struct ThermalGuard {
+    limit_c: f32,
+    resume_c: f32,
+    tripped: bool,
+}
+
+impl ThermalGuard {
+    pub fn new(config: &ThermalConfig) -> Self {
+        Self {
+            limit_c: config.limit_c,
+            resume_c: config.resume_c,
+            tripped: false,
+        }
+    }
+
+    /// 更新阈值（配置重新加载后调用，已暂停的状态保持，按新阈值判断恢复）
+    pub fn set_limits(&mut self, config: &ThermalConfig) {
+        self.limit_c = config.limit_c;
+        self.resume_c = config.resume_c;
+    }
+
+    /// 当前是否处于温控暂停状态
+    pub fn is_tripped(&self) -> bool {
+        self.tripped
+    }
+
+    /// 输入一次温度读数，状态切换时返回对应动作
+    pub fn update(&mut self, temp_c: f32) -> Option<ThermalAction> {
+        if !self.tripped && temp_c >= self.limit_c {
+            self.tripped = true;
+            Some(ThermalAction::Trip(temp_c))
+        } else if self.tripped && temp_c < self.resume_c {
+            self.tripped = false;
+            Some(ThermalAction::Recover(temp_c))
+        } else {
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 已达到上限（默认 46℃）的温控
+    fn tripped_guard() -> ThermalGuard {
+        let mut guard = ThermalGuard::new(&ThermalConfig::default());
+        guard.update(46.0);
+        guard
+    }
+
+    #[test]
+    fn guard_trips_once_at_limit() {
+        let mut guard = ThermalGuard::new(&ThermalConfig::default());
+        assert_eq!(guard.update(45.9), None);
+        assert_eq!(guard.update(46.0), Some(ThermalAction::Trip(46.0)));
+        assert_eq!(guard.update(47.5), None);
+    }
+
+    #[test]
+    fn guard_stays_tripped_between_resume_and_limit() {
+        let mut guard = tripped_guard();
+        assert_eq!(guard.update(43.0), None);
+        assert_eq!(guard.update(42.0), None);
+    }
+
+    #[test]
+    fn guard_recovers_below_resume_temperature() {
+        let mut guard = tripped_guard();
+        assert_eq!(guard.update(41.9), Some(ThermalAction::Recover(41.9)));
+        // 恢复后低于上限不再触发
+        assert_eq!(guard.update(45.0), None);
+    }
+
+    #[test]
+    fn zones_accept_thermal_zone_names() {
+        assert_eq!(
+            parse_zones("thermal_zone5, thermal_zone12").unwrap(),
+            ["thermal_zone5", "thermal_zone12"]
+        );
+    }
+
+    #[test]
+    fn zones_reject_other_names() {
+        for value in [
+            "../../../data/adb",
+            "thermal_zone",
+            "thermal_zone5x",
+            "cpu-thermal",
+        ] {
+            assert!(parse_zones(value).is_err(), "{}", value);
+        }
+    }
+}
@@ -0,0 +1,390 @@
+pub mod history;
+
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime};
+
+use serde::{Deserialize, Serialize};
+
+use crate::common::Paths;
+use crate::monitoring::FileMonitor;
+
+/// 充电会话状态
+///
+/// ```text
+/// Idle ──Charging──▶ Plugged ──PD──▶ Negotiating ──PD_PPS──▶ PpsActive
+///                       │                 │                     │
+///                       └────────────Discharging────────────────┴──▶ Unplugged ──Charging──▶ Plugged（新会话）
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SessionState {
+    /// 启动后尚未观察到任何充电会话
+    Idle,
+    /// 已插电（Charging），充电协议尚未识别
+    Plugged,
+    /// 已识别为 PD 充电头，等待 PPS 协商完成
+    Negotiating,
+    /// PPS 协商完成（real_type=PD_PPS）
+    PpsActive,
+    /// 已拔出（Discharging），会话结束
+    Unplugged,
+}
+
+impl SessionState {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Idle => "idle",
+            Self::Plugged => "plugged",
+            Self::Negotiating => "negotiating",
+            Self::PpsActive => "pps_active",
+            Self::Unplugged => "unplugged",
+        }
+    }
+
+    /// 是否处于充电会话中（插电到拔出之间）
+    pub fn is_active(&self) -> bool {
+        matches!(self, Self::Plugged | Self::Negotiating | Self::PpsActive)
+    }
+}
+
+/// 充电头信息（来自 xm_power 节点，节点缺失的平台各字段为 `None`）
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AdapterInfo {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub real_type: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub svid: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub apdo_max: Option<u32>,
+}
+
+impl AdapterInfo {
+    /// 从 sysfs 读取当前充电头信息
+    pub fn read(paths: &Paths) -> Self {
+        let read = |path| {
+            FileMonitor::read_file_content(path)
+                .ok()
+                .filter(|value| !value.is_empty())
+        };
+        Self {
+            real_type: read(paths.real_type()),
+            svid: read(paths.adapter_svid()),
+            apdo_max: read(paths.apdo_max()).and_then(|value| value.parse().ok()),
+        }
+    }
+
+    /// 是否为 PD 充电头（含 PPS）
+    pub fn is_pd(&self) -> bool {
+        matches!(self.real_type.as_deref(), Some("USB_PD" | "PD" | "PD_PPS"))
+    }
+
+    /// 是否已完成 PPS 协商
+    pub fn is_pps(&self) -> bool {
+        self.real_type.as_deref() == Some("PD_PPS")
+    }
+
+    /// 充电头来源：SVID 为 `0000` 的是公版充电头，其余为小米充电头；SVID 未知时返回 `None`
+    pub fn kind(&self) -> Option<AdapterKind> {
+        match self.svid.as_deref()? {
+            "0000" => Some(AdapterKind::Public),
+            _ => Some(AdapterKind::Xiaomi),
+        }
+    }
+}
+
+/// 充电头来源（free=auto 时据此选择公版 PPS 或 MIPPS）
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AdapterKind {
+    /// 公版 PD/PPS 充电头：需要解锁
+    Public,
+    /// 小米充电头：走原生 MIPPS 握手，不解锁
+    Xiaomi,
+}
+
+/// 驱动状态机的输入事件（由 uevent 与 sysfs 读数转换而来）
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SessionEvent {
+    /// POWER_SUPPLY_STATUS=Charging
+    Charging,
+    /// POWER_SUPPLY_STATUS=Discharging
+    Discharging,
+    /// 充电头信息更新
+    Adapter(AdapterInfo),
+}
+
+/// 一次状态跳变
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Transition {
+    pub from: SessionState,
+    pub to: SessionState,
+}
+
+/// 充电会话状态机
+///
+/// 每次从非会话态进入 `Plugged` 即开始一个新会话，会话 id 递增；
+/// 会话内状态只前进不回退（握手期间 real_type 的短暂回落不视为新的协商阶段）。
+#[derive(Debug, Clone)]
+pub struct ChargingSession {
+    id: u32,
+    state: SessionState,
+    adapter: AdapterInfo,
+    plugged_at: Option<SystemTime>,
+    negotiating_at: Option<SystemTime>,
+    pps_active_at: Option<SystemTime>,
+    unplugged_at: Option<SystemTime>,
+    unlock_applied: bool,
+    broadcast_forged: bool,
+}
+
+/// 事件循环与后端附属线程（广播伪造等）、策略线程共享的会话
+pub type SharedSession = Arc<Mutex<ChargingSession>>;
+
+impl Default for ChargingSession {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ChargingSession {
+    pub fn new() -> Self {
+        Self {
+            id: 0,
+            state: SessionState::Idle,
+            adapter: AdapterInfo::default(),
+            plugged_at: None,
+            negotiating_at: None,
+            pps_active_at: None,
+            unplugged_at: None,
+            unlock_applied: false,
+            broadcast_forged: false,
+        }
+    }
+
+    pub fn shared() -> SharedSession {
+        Arc::new(Mutex::new(Self::new()))
+    }
+
+    /// 会话 id：每开始一次新会话加一，0 表示尚无会话
+    pub fn id(&self) -> u32 {
+        self.id
+    }
+
+    pub fn state(&self) -> SessionState {
+        self.state
+    }
+
+    pub fn is_active(&self) -> bool {
+        self.state.is_active()
+    }
+
+    pub fn adapter(&self) -> &AdapterInfo {
+        &self.adapter
+    }
+
+    pub fn plugged_at(&self) -> Option<SystemTime> {
+        self.plugged_at
+    }
+
+    pub fn negotiating_at(&self) -> Option<SystemTime> {
+        self.negotiating_at
+    }
+
+    pub fn pps_active_at(&self) -> Option<SystemTime> {
+        self.pps_active_at
+    }
+
+    pub fn unplugged_at(&self) -> Option<SystemTime> {
+        self.unplugged_at
+    }
+
+    /// 本次会话中是否观察到解锁节点为 1
+    pub fn unlock_applied(&self) -> bool {
+        self.unlock_applied
+    }
+
+    /// 本次会话中伪造广播是否已发出
+    pub fn broadcast_forged(&self) -> bool {
+        self.broadcast_forged
+    }
+
+    pub fn mark_unlock_applied(&mut self) {
+        if self.is_active() {
+            self.unlock_applied = true;
+        }
+    }
+
+    pub fn mark_broadcast_forged(&mut self) {
+        if self.is_active() {
+            self.broadcast_forged = true;
+        }
+    }
+
+    /// 会话时长：会话中为截至 `now`，已结束为插电到拔出
+    pub fn duration(&self, now: SystemTime) -> Option<Duration> {
+        let plugged_at = self.plugged_at?;
+        self.unplugged_at
+            .unwrap_or(now)
+            .duration_since(plugged_at)
+            .ok()
+    }
+
+    /// 处理一个输入事件，发生状态跳变时返回该跳变
+    pub fn handle(&mut self, event: SessionEvent, now: SystemTime) -> Option<Transition> {
+        let from = self.state;
+        let to = match event {
+            SessionEvent::Charging if !from.is_active() => {
+                self.id = self.id.wrapping_add(1);
+                self.adapter = AdapterInfo::default();
+                self.plugged_at = Some(now);
+                self.negotiating_at = None;
+                self.pps_active_at = None;
+                self.unplugged_at = None;
+                self.unlock_applied = false;
+                self.broadcast_forged = false;
+                SessionState::Plugged
+            }
+            SessionEvent::Discharging if from.is_active() => {
+                self.unplugged_at = Some(now);
+                SessionState::Unplugged
+            }
+            SessionEvent::Adapter(adapter) if from.is_active() => {
+                let to = if adapter.is_pps() {
+                    SessionState::PpsActive
+                } else if adapter.is_pd() && from == SessionState::Plugged {
+                    SessionState::Negotiating
+                } else {
+                    from
+                };
+                self.adapter = adapter;
+                if to == SessionState::Negotiating
+                    || (to == SessionState::PpsActive && self.negotiating_at.is_none())
+                {
+                    self.negotiating_at = Some(now);
+                }
+                if to == SessionState::PpsActive && from != SessionState::PpsActive {
+                    self.pps_active_at = Some(now);
+                }
+                to
+            }
+            _ => from,
+        };
+
+        if to == from {
+            return None;
+        }
+        self.state = to;
+        Some(Transition { from, to })
+    }
+}
+
+/// 时间点转换为 Unix 时间戳（秒）
+pub fn unix_secs(time: SystemTime) -> u64 {
+    time.duration_since(SystemTime::UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(secs: u64) -> SystemTime {
+        SystemTime::UNIX_EPOCH + Duration::from_secs(secs)
+    }
+
+    fn adapter(real_type: &str) -> SessionEvent {
+        SessionEvent::Adapter(AdapterInfo {
+            real_type: Some(real_type.to_string()),
+            svid: Some("0000".to_string()),
+            apdo_max: Some(65),
+        })
+    }
+
+    #[test]
+    fn full_session_walks_every_state() {
+        let mut session = ChargingSession::new();
+        assert_eq!(session.state(), SessionState::Idle);
+
+        let t = session.handle(SessionEvent::Charging, at(10)).unwrap();
+        assert_eq!((t.from, t.to), (SessionState::Idle, SessionState::Plugged));
+        let t = session.handle(adapter("USB_PD"), at(11)).unwrap();
+        assert_eq!(t.to, SessionState::Negotiating);
+        let t = session.handle(adapter("PD_PPS"), at(12)).unwrap();
+        assert_eq!(t.to, SessionState::PpsActive);
+        let t = session.handle(SessionEvent::Discharging, at(70)).unwrap();
+        assert_eq!(
+            (t.from, t.to),
+            (SessionState::PpsActive, SessionState::Unplugged)
+        );
+
+        assert_eq!(session.id(), 1);
+        assert_eq!(session.plugged_at(), Some(at(10)));
+        assert_eq!(session.negotiating_at(), Some(at(11)));
+        assert_eq!(session.pps_active_at(), Some(at(12)));
+        assert_eq!(session.unplugged_at(), Some(at(70)));
+        assert_eq!(session.duration(at(100)), Some(Duration::from_secs(60)));
+        assert_eq!(session.adapter().apdo_max, Some(65));
+    }
+
+    #[test]
+    fn pps_without_intermediate_pd_report_skips_negotiating() {
+        let mut session = ChargingSession::new();
+        session.handle(SessionEvent::Charging, at(0));
+        let t = session.handle(adapter("PD_PPS"), at(1)).unwrap();
+        assert_eq!(
+            (t.from, t.to),
+            (SessionState::Plugged, SessionState::PpsActive)
+        );
+        assert_eq!(session.negotiating_at(), Some(at(1)));
+    }
+
+    #[test]
+    fn repeated_events_do_not_transition() {
+        let mut session = ChargingSession::new();
+        assert!(session.handle(SessionEvent::Discharging, at(0)).is_none());
+        assert!(session.handle(adapter("PD_PPS"), at(0)).is_none());
+        assert_eq!(session.state(), SessionState::Idle);
+
+        session.handle(SessionEvent::Charging, at(1));
+        assert!(session.handle(SessionEvent::Charging, at(2)).is_none());
+        assert_eq!(session.plugged_at(), Some(at(1)));
+
+        session.handle(adapter("PD_PPS"), at(3));
+        // 握手期间 real_type 短暂回落不回退状态
+        assert!(session.handle(adapter("USB_PD"), at(4)).is_none());
+        assert_eq!(session.state(), SessionState::PpsActive);
+        assert_eq!(session.adapter().real_type.as_deref(), Some("USB_PD"));
+    }
+
+    #[test]
+    fn non_pd_adapter_stays_plugged() {
+        let mut session = ChargingSession::new();
+        session.handle(SessionEvent::Charging, at(0));
+        assert!(session.handle(adapter("USB_DCP"), at(1)).is_none());
+        assert_eq!(session.state(), SessionState::Plugged);
+        assert!(
+            session
+                .handle(SessionEvent::Adapter(AdapterInfo::default()), at(2))
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn replug_starts_new_session_and_resets_timestamps() {
+        let mut session = ChargingSession::new();
+        session.handle(SessionEvent::Charging, at(0));
+        session.handle(adapter("PD_PPS"), at(1));
+        session.handle(SessionEvent::Discharging, at(5));
+
+        let t = session.handle(SessionEvent::Charging, at(9)).unwrap();
+        assert_eq!(
+            (t.from, t.to),
+            (SessionState::Unplugged, SessionState::Plugged)
+        );
+        assert_eq!(session.id(), 2);
+        assert_eq!(session.plugged_at(), Some(at(9)));
+        assert_eq!(session.pps_active_at(), None);
+        assert_eq!(session.unplugged_at(), None);
+        assert_eq!(session.adapter(), &AdapterInfo::default());
+        assert_eq!(session.duration(at(12)), Some(Duration::from_secs(3)));
+    }
+}
@@ -0,0 +1,408 @@
+//! 充电会话历史
+//!
+//! 每个会话结束时把汇总统计追加到模块目录的 `sessions.jsonl`（每行一个 [`SessionRecord`]），
+//! 文件超过 [`TRIM_THRESHOLD_BYTES`] 时裁剪为最近 [`MAX_HISTORY_RECORDS`] 条。`FreePPS history` 读取该文件列出最近的会话，
+//! 并按充电头（svid + apdo_max）汇总。
+
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::Path;
+use std::time::SystemTime;
+
+use anyhow::Result;
+use log::warn;
+use serde::{Deserialize, Serialize};
+
+use crate::common::FreePPSError;
+use crate::session::{AdapterInfo, ChargingSession, unix_secs};
+use crate::telemetry::Sample;
+
+/// 裁剪历史文件时保留的会话条数
+pub const MAX_HISTORY_RECORDS: usize = 500;
+/// 历史文件超过该大小时才裁剪（每条约 400 字节），平时每个会话只追加一行
+pub const TRIM_THRESHOLD_BYTES: u64 = 512 * 1024;
+
+/// 一次充电会话的汇总
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SessionRecord {
+    pub started_at: u64,
+    /// 守护进程在会话中途退出时为 `None`
+    pub ended_at: Option<u64>,
+    pub duration_secs: u64,
+    pub adapter: AdapterInfo,
+    /// 是否协商到 PPS
+    pub pps: bool,
+    pub unlock_applied: bool,
+    pub broadcast_forged: bool,
+    pub peak_w: f64,
+    pub avg_w: f64,
+    pub energy_wh: f64,
+    pub soc_start: Option<u8>,
+    pub soc_end: Option<u8>,
+    pub samples: u32,
+}
+
+impl SessionRecord {
+    /// 电量变化（百分点）
+    pub fn soc_gained(&self) -> Option<i16> {
+        Some(self.soc_end? as i16 - self.soc_start? as i16)
+    }
+}
+
+/// 会话进行中的功率 / 电量统计，由事件循环的遥测采样逐次累加
+#[derive(Debug, Clone, Default)]
+pub struct SessionStats {
+    samples: u32,
+    power_samples: u32,
+    peak_mw: u32,
+    power_sum_mw: u64,
+    // 相邻两次功率采样之间的总时长（积分区间）
+    power_secs: f64,
+    energy_mwh: f64,
+    last_power: Option<(SystemTime, u32)>,
+    soc_start: Option<u8>,
+    soc_end: Option<u8>,
+}
+
+impl SessionStats {
+    pub fn add(&mut self, sample: &Sample, now: SystemTime) {
+        self.samples += 1;
+        if let Some(soc) = sample.soc {
+            self.soc_start.get_or_insert(soc);
+            self.soc_end = Some(soc);
+        }
+
+        let Some(power_mw) = sample.power_mw else {
+            return;
+        };
+        self.power_samples += 1;
+        self.peak_mw = self.peak_mw.max(power_mw);
+        self.power_sum_mw += power_mw as u64;
+        // 梯形积分
+        if let Some((last_at, last_mw)) = self.last_power
+            && let Ok(elapsed) = now.duration_since(last_at)
+        {
+            self.energy_mwh += (last_mw + power_mw) as f64 / 2.0 * elapsed.as_secs_f64() / 3600.0;
+            self.power_secs += elapsed.as_secs_f64();
+        }
+        self.last_power = Some((now, power_mw));
+    }
+
+    /// 以会话最终状态生成记录；`session` 仍在进行中时（守护进程退出）结束时间留空
+    pub fn finish(&self, session: &ChargingSession, now: SystemTime) -> SessionRecord {
+        // 按采样间隔加权（电量 / 积分时长），与按会话时长加权的充电头汇总一致；
+        // 只有一次功率采样时取该次功率
+        let avg_mw = if self.power_secs > 0.0 {
+            self.energy_mwh * 3600.0 / self.power_secs
+        } else {
+            match self.power_samples {
+                0 => 0.0,
+                n => self.power_sum_mw as f64 / n as f64,
+            }
+        };
+        SessionRecord {
+            started_at: session.plugged_at().map(unix_secs).unwrap_or_default(),
+            ended_at: session.unplugged_at().map(unix_secs),
+            duration_secs: session.duration(now).unwrap_or_default().as_secs(),
+            adapter: session.adapter().clone(),
+            pps: session.pps_active_at().is_some(),
+            unlock_applied: session.unlock_applied(),
+            broadcast_forged: session.broadcast_forged(),
+            peak_w: round(self.peak_mw as f64 / 1000.0, 2),
+            avg_w: round(avg_mw / 1000.0, 2),
+            energy_wh: round(self.energy_mwh / 1000.0, 3),
+            soc_start: self.soc_start,
+            soc_end: self.soc_end,
+            samples: self.samples,
+        }
+    }
+}
+
+fn round(value: f64, digits: i32) -> f64 {
+    let scale = 10f64.powi(digits);
+    (value * scale).round() / scale
+}
+
+/// 追加一条记录；文件超过 [`TRIM_THRESHOLD_BYTES`] 时重写文件，只保留最近的记录
+pub fn append(path: &Path, record: &SessionRecord) -> Result<()> {
+    let line = serde_json::to_string(record)?;
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .map_err(FreePPSError::FileOperation)?;
+    writeln!(file, "{}", line).map_err(FreePPSError::FileOperation)?;
+    let size = file.metadata().map_err(FreePPSError::FileOperation)?.len();
+    drop(file);
+    if size <= TRIM_THRESHOLD_BYTES {
+        return Ok(());
+    }
+
+    let records = load(path)?;
+    if records.len() > MAX_HISTORY_RECORDS {
+        let mut content = String::new();
+        for record in &records[records.len() - MAX_HISTORY_RECORDS..] {
+            content.push_str(&serde_json::to_string(record)?);
+            content.push('\n');
+        }
+        let tmp = path.with_extension("jsonl.tmp");
+        fs::write(&tmp, content).map_err(FreePPSError::FileOperation)?;
+        fs::rename(&tmp, path).map_err(FreePPSError::FileOperation)?;
+    }
+    Ok(())
+}
+
+/// 读取全部记录（按写入顺序）；文件不存在时为空，无法解析的行跳过
+pub fn load(path: &Path) -> Result<Vec<SessionRecord>> {
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+    let content = fs::read_to_string(path).map_err(FreePPSError::FileOperation)?;
+    Ok(content
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .filter_map(|line| match serde_json::from_str(line) {
+            Ok(record) => Some(record),
+            Err(e) => {
+                warn!("跳过无法解析的会话历史记录: {}", e);
+                None
+            }
+        })
+        .collect())
+}
+
+/// 单个充电头（svid + apdo_max）的汇总
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct AdapterSummary {
+    pub svid: Option<String>,
+    pub apdo_max: Option<u32>,
+    /// 最近一次会话的 real_type
+    pub real_type: Option<String>,
+    pub sessions: u32,
+    pub pps_sessions: u32,
+    pub unlock_applied: u32,
+    pub broadcast_forged: u32,
+    pub peak_w: f64,
+    /// 按会话时长加权的平均功率
+    pub avg_w: f64,
+    pub energy_wh: f64,
+}
+
+/// 按充电头汇总，顺序为首次出现的先后
+pub fn summarize(records: &[SessionRecord]) -> Vec<AdapterSummary> {
+    let mut summaries: Vec<AdapterSummary> = Vec::new();
+    // 各充电头的 Σ(平均功率 × 时长) 与总时长
+    let mut weighted: Vec<(f64, u64)> = Vec::new();
+    for record in records {
+        let adapter = &record.adapter;
+        let index = match summaries
+            .iter()
+            .position(|s| s.svid == adapter.svid && s.apdo_max == adapter.apdo_max)
+        {
+            Some(index) => index,
+            None => {
+                summaries.push(AdapterSummary {
+                    svid: adapter.svid.clone(),
+                    apdo_max: adapter.apdo_max,
+                    ..Default::default()
+                });
+                weighted.push((0.0, 0));
+                summaries.len() - 1
+            }
+        };
+
+        let summary = &mut summaries[index];
+        // 各会话平均功率的平均，仅在全部会话时长为 0 时使用
+        summary.avg_w = (summary.avg_w * summary.sessions as f64 + record.avg_w)
+            / (summary.sessions + 1) as f64;
+        weighted[index].0 += record.avg_w * record.duration_secs as f64;
+        weighted[index].1 += record.duration_secs;
+        summary.sessions += 1;
+        summary.pps_sessions += record.pps as u32;
+        summary.unlock_applied += record.unlock_applied as u32;
+        summary.broadcast_forged += record.broadcast_forged as u32;
+        summary.peak_w = summary.peak_w.max(record.peak_w);
+        summary.energy_wh += record.energy_wh;
+        if adapter.real_type.is_some() {
+            summary.real_type = adapter.real_type.clone();
+        }
+    }
+
+    // 平均功率按会话时长加权：长时间充电的会话占更大比重
+    for (summary, (watt_secs, secs)) in summaries.iter_mut().zip(weighted) {
+        if secs > 0 {
+            summary.avg_w = watt_secs / secs as f64;
+        }
+        summary.avg_w = round(summary.avg_w, 2);
+        summary.energy_wh = round(summary.energy_wh, 3);
+    }
+    summaries
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+    use crate::session::SessionEvent;
+    use crate::testing::FakeSysfs;
+
+    fn at(secs: u64) -> SystemTime {
+        SystemTime::UNIX_EPOCH + Duration::from_secs(secs)
+    }
+
+    fn sample(power_mw: u32, soc: u8) -> Sample {
+        Sample {
+            timestamp: 0,
+            session: 1,
+            state: "pps_active",
+            charger_type: Some("PD_PPS".to_string()),
+            apdo_max_w: Some(65),
+            voltage_mv: None,
+            current_ma: None,
+            power_mw: Some(power_mw),
+            battery_current_ma: None,
+            temp_c: None,
+            soc: Some(soc),
+        }
+    }
+
+    /// 60W 持续 30 分钟后降到 20W 再持续 30 分钟的 PPS 会话
+    fn pps_session_record() -> SessionRecord {
+        let mut session = ChargingSession::new();
+        session.handle(SessionEvent::Charging, at(1000));
+        session.handle(
+            SessionEvent::Adapter(AdapterInfo {
+                real_type: Some("PD_PPS".to_string()),
+                svid: Some("0000".to_string()),
+                apdo_max: Some(65),
+            }),
+            at(1001),
+        );
+        session.mark_unlock_applied();
+
+        let mut stats = SessionStats::default();
+        stats.add(&sample(60_000, 20), at(1000));
+        stats.add(&sample(60_000, 50), at(1000 + 1800));
+        stats.add(&sample(20_000, 70), at(1000 + 3600));
+        session.handle(SessionEvent::Discharging, at(1000 + 3600));
+        stats.finish(&session, at(1000 + 3600))
+    }
+
+    #[test]
+    fn record_captures_session_outcome() {
+        let record = pps_session_record();
+        assert_eq!(record.ended_at, Some(4600));
+        assert_eq!(record.duration_secs, 3600);
+        assert_eq!(record.soc_gained(), Some(50));
+        assert!(record.pps && record.unlock_applied && !record.broadcast_forged);
+    }
+
+    #[test]
+    fn stats_integrate_energy_and_peak_power() {
+        let record = pps_session_record();
+        assert_eq!(record.peak_w, 60.0);
+        assert_eq!(record.energy_wh, 50.0);
+    }
+
+    #[test]
+    fn average_power_is_weighted_by_sample_interval() {
+        // 60W 段与 60W→20W 段各 30 分钟：50Wh / 1h，而不是三次采样的平均 46.67W
+        assert_eq!(pps_session_record().avg_w, 50.0);
+
+        // 采样间隔不均匀时，长间隔占更大比重
+        let session = ChargingSession::new();
+        let mut stats = SessionStats::default();
+        stats.add(&sample(10_000, 50), at(0));
+        stats.add(&sample(10_000, 50), at(3000));
+        stats.add(&sample(10_000, 50), at(3010));
+        stats.add(&sample(40_000, 50), at(3020));
+        assert_eq!(stats.finish(&session, at(3020)).avg_w, 10.05);
+    }
+
+    #[test]
+    fn single_power_sample_is_the_average() {
+        let session = ChargingSession::new();
+        let mut stats = SessionStats::default();
+        stats.add(&sample(30_000, 50), at(0));
+        assert_eq!(stats.finish(&session, at(0)).avg_w, 30.0);
+    }
+
+    #[test]
+    fn appended_records_load_in_order() {
+        let sysfs = FakeSysfs::new();
+        let path = sysfs.paths().session_history();
+        let first = pps_session_record();
+        let second = SessionRecord {
+            avg_w: 20.0,
+            ..first.clone()
+        };
+        append(path, &first).unwrap();
+        append(path, &second).unwrap();
+        assert_eq!(load(path).unwrap(), [first, second]);
+    }
+
+    #[test]
+    fn large_history_is_trimmed_to_recent_records() {
+        let sysfs = FakeSysfs::new();
+        let path = sysfs.paths().session_history();
+        let record = pps_session_record();
+        let line = serde_json::to_string(&record).unwrap() + "\n";
+        let count = TRIM_THRESHOLD_BYTES as usize / line.len() + 1;
+        fs::write(path, line.repeat(count)).unwrap();
+
+        let last = SessionRecord {
+            samples: 99,
+            ..record
+        };
+        append(path, &last).unwrap();
+        let records = load(path).unwrap();
+        assert_eq!(records.len(), MAX_HISTORY_RECORDS);
+        assert_eq!(records.last(), Some(&last));
+    }
+
+    #[test]
+    fn small_history_is_not_trimmed() {
+        let sysfs = FakeSysfs::new();
+        let path = sysfs.paths().session_history();
+        let record = pps_session_record();
+        let line = serde_json::to_string(&record).unwrap() + "\n";
+        fs::write(path, line.repeat(MAX_HISTORY_RECORDS)).unwrap();
+
+        append(path, &record).unwrap();
+        assert_eq!(load(path).unwrap().len(), MAX_HISTORY_RECORDS + 1);
+    }
+
+    #[test]
+    fn summary_groups_sessions_by_adapter() {
+        let record = pps_session_record();
+        let other = SessionRecord {
+            adapter: AdapterInfo {
+                svid: Some("2717".to_string()),
+                ..record.adapter.clone()
+            },
+            ..record.clone()
+        };
+        let summaries = summarize(&[record.clone(), other, record]);
+        assert_eq!(summaries.len(), 2);
+        assert_eq!(summaries[0].sessions, 2);
+        assert_eq!(summaries[0].energy_wh, 100.0);
+        assert_eq!(summaries[1].sessions, 1);
+    }
+
+    #[test]
+    fn summary_average_is_weighted_by_duration() {
+        // 1 小时 40W 与 20 分钟 20W
+        let record = pps_session_record();
+        let long = SessionRecord {
+            avg_w: 40.0,
+            ..record.clone()
+        };
+        let short = SessionRecord {
+            avg_w: 20.0,
+            duration_secs: 1200,
+            ..record
+        };
+        assert_eq!(summarize(&[long, short])[0].avg_w, 35.0);
+    }
+}
@@ -0,0 +1,171 @@
+//! 充电遥测
+//!
+//! 充电会话期间按固定间隔采样 USB 输入电压 / 电流 / 功率、电池电流、温度、电量与充电类型，
+//! 每个会话写入模块目录 `telemetry/` 下的一个 JSONL 或 CSV 文件，用于核对第三方 PPS 充电头的实际功率。
+//! 单个文件超过大小上限时滚动到下一个分段文件，目录总大小超过上限时删除最旧的文件。
+//!
+//! 遥测文件由 `telemetry` feature 控制；未编译时仍然采样，只用于会话历史。
+
+#[cfg(feature = "telemetry")]
+mod writer;
+
+#[cfg(feature = "telemetry")]
+pub use writer::SessionLog;
+
+use std::time::{Duration, SystemTime};
+
+use serde::Serialize;
+
+use crate::common::Paths;
+use crate::config::{self, ConfigSection};
+use crate::monitoring::FileMonitor;
+use crate::session::{ChargingSession, unix_secs};
+
+/// 遥测文件格式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TelemetryFormat {
+    Jsonl,
+    Csv,
+}
+
+impl TelemetryFormat {
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Self::Jsonl => "jsonl",
+            Self::Csv => "csv",
+        }
+    }
+}
+
+/// 遥测配置（freepps.conf 中的 `telemetry*` 项）
+///
+/// ```text
+/// telemetry=jsonl            # jsonl / csv / off
+/// telemetry_interval_ms=2000
+/// telemetry_max_file_kb=1024 # 单个文件上限，超过后滚动到下一分段
+/// telemetry_max_total_kb=8192 # telemetry/ 目录总上限，超过后删除最旧的文件
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TelemetryConfig {
+    /// `None` 表示关闭
+    pub format: Option<TelemetryFormat>,
+    pub interval: Duration,
+    pub max_file_bytes: u64,
+    pub max_total_bytes: u64,
+}
+
+impl Default for TelemetryConfig {
+    fn default() -> Self {
+        Self {
+            format: cfg!(feature = "telemetry").then_some(TelemetryFormat::Jsonl),
+            interval: Duration::from_secs(2),
+            max_file_bytes: 1024 * 1024,
+            max_total_bytes: 8 * 1024 * 1024,
+        }
+    }
+}
+
+impl ConfigSection for TelemetryConfig {
+    fn apply(&mut self, key: &str, value: &str) -> Option<Result<(), String>> {
+        let result = match key {
+            "telemetry" => match value {
+                "off" => Ok(None),
+                _ if !cfg!(feature = "telemetry") => {
+                    Err("编译时未启用 telemetry 功能，只能为off".to_string())
+                }
+                "jsonl" => Ok(Some(TelemetryFormat::Jsonl)),
+                "csv" => Ok(Some(TelemetryFormat::Csv)),
+                _ => Err("可选 jsonl / csv / off".to_string()),
+            }
+            .map(|format| self.format = format),
+            "telemetry_interval_ms" => {
+                config::parse_millis(value, 100).map(|interval| self.interval = interval)
+            }
+            "telemetry_max_file_kb" => {
+                config::parse_positive::<u64>(value).map(|kb| self.max_file_bytes = kb * 1024)
+            }
+            "telemetry_max_total_kb" => {
+                config::parse_positive::<u64>(value).map(|kb| self.max_total_bytes = kb * 1024)
+            }
+            _ => return None,
+        };
+        Some(result)
+    }
+}
+
+/// 一次采样；节点不存在或无法解析的字段为 `None`
+///
+/// 电流取绝对值：不同平台 current_now 的符号约定不一致（高通电池放电为正、充电为负）。
+#[derive(Debug, Clone, Serialize)]
+pub struct Sample {
+    pub timestamp: u64,
+    pub session: u32,
+    pub state: &'static str,
+    pub charger_type: Option<String>,
+    pub apdo_max_w: Option<u32>,
+    pub voltage_mv: Option<u32>,
+    pub current_ma: Option<u32>,
+    pub power_mw: Option<u32>,
+    pub battery_current_ma: Option<u32>,
+    pub temp_c: Option<f32>,
+    pub soc: Option<u8>,
+}
+
+/// CSV 表头，与 [`Sample::csv_row`] 的列顺序一致
+#[cfg(feature = "telemetry")]
+pub const CSV_HEADER: &str = "timestamp,session,state,charger_type,apdo_max_w,voltage_mv,current_ma,power_mw,battery_current_ma,temp_c,soc";
+
+impl Sample {
+    pub fn read(paths: &Paths, session: &ChargingSession, now: SystemTime) -> Self {
+        let read_i64 = |path| {
+            FileMonitor::read_file_content(path)
+                .ok()
+                .and_then(|value| value.parse::<i64>().ok())
+        };
+        // µV / µA → mV / mA
+        let voltage_mv =
+            read_i64(paths.usb_voltage_now()).map(|uv| (uv.unsigned_abs() / 1000) as u32);
+        let current_ma =
+            read_i64(paths.usb_current_now()).map(|ua| (ua.unsigned_abs() / 1000) as u32);
+        let power_mw = voltage_mv
+            .zip(current_ma)
+            .map(|(mv, ma)| (mv as u64 * ma as u64 / 1000) as u32);
+
+        Self {
+            timestamp: unix_secs(now),
+            session: session.id(),
+            state: session.state().as_str(),
+            charger_type: session.adapter().real_type.clone(),
+            apdo_max_w: session.adapter().apdo_max,
+            voltage_mv,
+            current_ma,
+            power_mw,
+            battery_current_ma: read_i64(paths.battery_current_now())
+                .map(|ua| (ua.unsigned_abs() / 1000) as u32),
+            // 单位为 0.1℃
+            temp_c: read_i64(paths.battery_temp()).map(|tenths| tenths as f32 / 10.0),
+            soc: read_i64(paths.battery_capacity()).map(|soc| soc.clamp(0, 100) as u8),
+        }
+    }
+
+    #[cfg(feature = "telemetry")]
+    pub fn csv_row(&self) -> String {
+        fn cell<T: ToString>(value: &Option<T>) -> String {
+            value.as_ref().map(T::to_string).unwrap_or_default()
+        }
+        format!(
+            "{},{},{},{},{},{},{},{},{},{},{}",
+            self.timestamp,
+            self.session,
+            self.state,
+            cell(&self.charger_type),
+            cell(&self.apdo_max_w),
+            cell(&self.voltage_mv),
+            cell(&self.current_ma),
+            cell(&self.power_mw),
+            cell(&self.battery_current_ma),
+            cell(&self.temp_c),
+            cell(&self.soc)
+        )
+    }
+}
@@ -0,0 +1,227 @@
+use std::fs::{self, File, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use anyhow::Result;
+use log::{info, warn};
+
+use crate::common::FreePPSError;
+use crate::telemetry::{CSV_HEADER, Sample, TelemetryFormat};
+
+/// 单个充电会话的遥测文件
+///
+/// 文件名为 `session-<插入时间>-<会话号>.<扩展名>`，超过单文件上限后滚动到
+/// `session-<插入时间>-<会话号>.part<分段>.<扩展名>`；每次新建文件前按总大小上限清理最旧的文件，
+/// 为新文件预留一个单文件上限的空间，因此目录总大小不会超过上限。
+pub struct SessionLog {
+    dir: PathBuf,
+    format: TelemetryFormat,
+    stem: String,
+    part: u32,
+    path: PathBuf,
+    file: File,
+    written: u64,
+    max_file_bytes: u64,
+    max_total_bytes: u64,
+}
+
+impl SessionLog {
+    pub fn create(
+        dir: &Path,
+        format: TelemetryFormat,
+        session_id: u32,
+        started_at: u64,
+        max_file_bytes: u64,
+        max_total_bytes: u64,
+    ) -> Result<Self> {
+        fs::create_dir_all(dir).map_err(FreePPSError::FileOperation)?;
+        let stem = format!("session-{}-{}", started_at, session_id);
+        let path = dir.join(format!("{}.{}", stem, format.extension()));
+        let (file, written) = open(&path, format)?;
+
+        let log = Self {
+            dir: dir.to_path_buf(),
+            format,
+            stem,
+            part: 1,
+            path,
+            file,
+            written,
+            max_file_bytes,
+            max_total_bytes,
+        };
+        log.prune();
+        info!("开始写入充电遥测: {}", log.path.display());
+        Ok(log)
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn append(&mut self, sample: &Sample) -> Result<()> {
+        let line = match self.format {
+            TelemetryFormat::Jsonl => serde_json::to_string(sample)?,
+            TelemetryFormat::Csv => sample.csv_row(),
+        };
+        let len = line.len() as u64 + 1;
+        if self.written > 0 && self.written + len > self.max_file_bytes {
+            self.roll()?;
+        }
+
+        writeln!(self.file, "{}", line).map_err(FreePPSError::FileOperation)?;
+        self.written += len;
+        Ok(())
+    }
+
+    fn roll(&mut self) -> Result<()> {
+        self.part += 1;
+        self.path = self.dir.join(format!(
+            "{}.part{}.{}",
+            self.stem,
+            self.part,
+            self.format.extension()
+        ));
+        (self.file, self.written) = open(&self.path, self.format)?;
+        info!("充电遥测文件达到上限，滚动到: {}", self.path.display());
+        self.prune();
+        Ok(())
+    }
+
+    /// 从最旧的文件开始删除，直到其它文件总大小加上当前文件上限不超过目录上限
+    fn prune(&self) {
+        let Ok(entries) = fs::read_dir(&self.dir) else {
+            return;
+        };
+        let mut files: Vec<(SystemTime, PathBuf, u64)> = entries
+            .filter_map(|entry| entry.ok())
+            .filter(|entry| entry.path() != self.path)
+            .filter_map(|entry| {
+                let metadata = entry.metadata().ok()?;
+                metadata.is_file().then(|| {
+                    (
+                        metadata.modified().unwrap_or(SystemTime::UNIX_EPOCH),
+                        entry.path(),
+                        metadata.len(),
+                    )
+                })
+            })
+            .collect();
+        files.sort();
+
+        let budget = self.max_total_bytes.saturating_sub(self.max_file_bytes);
+        let mut total: u64 = files.iter().map(|(_, _, len)| len).sum();
+        for (_, path, len) in files {
+            if total <= budget {
+                break;
+            }
+            match fs::remove_file(&path) {
+                Ok(()) => {
+                    info!("充电遥测目录超过上限，删除: {}", path.display());
+                    total -= len;
+                }
+                Err(e) => warn!("删除充电遥测文件失败: {} ({})", path.display(), e),
+            }
+        }
+    }
+}
+
+/// 以追加方式打开（守护进程重启后同一会话文件继续写入），新文件写入 CSV 表头
+fn open(path: &Path, format: TelemetryFormat) -> Result<(File, u64)> {
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .map_err(FreePPSError::FileOperation)?;
+    let mut written = file.metadata().map_err(FreePPSError::FileOperation)?.len();
+    if written == 0 && format == TelemetryFormat::Csv {
+        writeln!(file, "{}", CSV_HEADER).map_err(FreePPSError::FileOperation)?;
+        written = CSV_HEADER.len() as u64 + 1;
+    }
+    Ok((file, written))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::FakeSysfs;
+
+    fn sample(session: u32) -> Sample {
+        Sample {
+            timestamp: 1_700_000_000,
+            session,
+            state: "pps_active",
+            charger_type: Some("PD_PPS".to_string()),
+            apdo_max_w: Some(65),
+            voltage_mv: Some(9800),
+            current_ma: Some(6100),
+            power_mw: Some(59780),
+            battery_current_ma: Some(11800),
+            temp_c: Some(36.5),
+            soc: Some(42),
+        }
+    }
+
+    /// 每个文件放得下表头 + 2 行的上限
+    fn max_file_bytes() -> u64 {
+        let row_len = sample(1).csv_row().len() as u64 + 1;
+        let header_len = CSV_HEADER.len() as u64 + 1;
+        header_len + 2 * row_len
+    }
+
+    /// 会话 1 写入 5 行（分为 3 个文件），目录只放得下 3 个满文件
+    fn filled_log(sysfs: &FakeSysfs) -> SessionLog {
+        let max_file = max_file_bytes();
+        let mut log = SessionLog::create(
+            sysfs.paths().telemetry_dir(),
+            TelemetryFormat::Csv,
+            1,
+            100,
+            max_file,
+            3 * max_file,
+        )
+        .unwrap();
+        for _ in 0..5 {
+            log.append(&sample(1)).unwrap();
+        }
+        log
+    }
+
+    #[test]
+    fn full_file_rolls_over_to_next_part() {
+        let sysfs = FakeSysfs::new();
+        let log = filled_log(&sysfs);
+        assert_eq!(
+            log.path(),
+            sysfs
+                .paths()
+                .telemetry_dir()
+                .join("session-100-1.part3.csv")
+        );
+    }
+
+    #[test]
+    fn each_part_holds_header_and_rows_up_to_limit() {
+        let sysfs = FakeSysfs::new();
+        filled_log(&sysfs);
+        let first =
+            fs::read_to_string(sysfs.paths().telemetry_dir().join("session-100-1.csv")).unwrap();
+        assert_eq!(first.lines().count(), 3);
+        assert_eq!(first.lines().next(), Some(CSV_HEADER));
+    }
+
+    #[test]
+    fn new_session_evicts_oldest_part_over_directory_cap() {
+        let sysfs = FakeSysfs::new();
+        let dir = sysfs.paths().telemetry_dir();
+        filled_log(&sysfs);
+
+        // 新会话需要预留一个文件的空间，最旧的分段被删除
+        let max_file = max_file_bytes();
+        SessionLog::create(dir, TelemetryFormat::Csv, 2, 200, max_file, 3 * max_file).unwrap();
+        assert!(!dir.join("session-100-1.csv").exists());
+        assert!(dir.join("session-100-1.part2.csv").exists());
+        assert!(dir.join("session-200-2.csv").exists());
+    }
+}
@@ -0,0 +1,285 @@
+//! 测试工具：临时目录中的假 sysfs 树、可注入的 uevent 源、记录广播的发送通道
+//!
+//! 守护进程的全部路径都来自 [`Paths`]，uevent 来自 [`UeventSource`]，广播经由
+//! [`BroadcastSender`] 发出，因此替换这三者即可在普通 Linux 机器上驱动监控线程。
+//!
+//! 驱动监控线程的工具只被 `scenarios` 使用，该模块仅在默认后端全部编译时启用。
+
+#![cfg_attr(
+    not(all(feature = "broadcast-forger", feature = "mtk")),
+    allow(dead_code)
+)]
+
+// 场景覆盖高通（含广播伪造）与联发科两个后端
+#[cfg(all(feature = "broadcast-forger", feature = "mtk"))]
+mod scenarios;
+
+use std::fs;
+use std::os::fd::{AsRawFd, FromRawFd, OwnedFd};
+use std::os::raw::c_int;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use anyhow::{Result, anyhow};
+
+use crate::common::{PathOverrides, Paths};
+use crate::monitoring::UeventSource;
+#[cfg(feature = "broadcast-forger")]
+use crate::pd::{Broadcast, BroadcastSender};
+
+/// 临时目录中的模块目录 + sysfs 树，drop 时删除
+pub struct FakeSysfs {
+    root: PathBuf,
+    paths: Arc<Paths>,
+}
+
+impl FakeSysfs {
+    pub fn new() -> Self {
+        static COUNTER: AtomicU32 = AtomicU32::new(0);
+        let root = std::env::temp_dir().join(format!(
+            "freepps-test-{}-{}",
+            std::process::id(),
+            COUNTER.fetch_add(1, Ordering::Relaxed)
+        ));
+        let module_dir = root.join("module");
+        let sysfs_root = root.join("sys");
+        fs::create_dir_all(&module_dir).expect("创建临时模块目录失败");
+        fs::create_dir_all(&sysfs_root).expect("创建临时sysfs目录失败");
+
+        // 三个根路径全部显式指定，不受环境变量与默认配置文件影响
+        let overrides = PathOverrides {
+            module_dir: Some(module_dir.clone()),
+            sysfs_root: Some(sysfs_root),
+            config_file: Some(module_dir.join("freepps.conf")),
+        };
+        let paths = Arc::new(Paths::resolve(&overrides).expect("解析测试路径失败"));
+
+        Self { root, paths }
+    }
+
+    /// 高通设备：pd_verifed + battery/status，free=1
+    pub fn qcom() -> Self {
+        let sysfs = Self::new();
+        sysfs.write(sysfs.paths.pd_verified(), "1");
+        sysfs.write(sysfs.paths.battery_status(), "Discharging");
+        sysfs.write(sysfs.paths.free_file(), "1");
+        sysfs
+    }
+
+    /// 联发科设备：usbpd_verifed + battery/status，free=1
+    pub fn mtk() -> Self {
+        let sysfs = Self::new();
+        sysfs.write(sysfs.paths.pd_adapter_verified(), "1");
+        sysfs.write(sysfs.paths.battery_status(), "Discharging");
+        sysfs.write(sysfs.paths.free_file(), "1");
+        sysfs
+    }
+
+    pub fn paths(&self) -> &Arc<Paths> {
+        &self.paths
+    }
+
+    /// 写入节点（自动创建父目录）
+    pub fn write(&self, path: &Path, value: &str) {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).expect("创建节点父目录失败");
+        }
+        fs::write(path, value).expect("写入节点失败");
+    }
+
+    pub fn read(&self, path: &Path) -> String {
+        fs::read_to_string(path)
+            .map(|value| value.trim().to_string())
+            .unwrap_or_default()
+    }
+
+    /// 模拟插入公版 PPS 头：充电协议、能力、Vbus 电压与电池状态
+    pub fn plug_pps(&self, apdo_max: u32) {
+        self.write(self.paths.real_type(), "PD_PPS");
+        self.write(self.paths.adapter_svid(), "0000");
+        self.write(self.paths.apdo_max(), &apdo_max.to_string());
+        self.write(self.paths.usb_voltage_now(), "15000000");
+        self.write(self.paths.battery_status(), "Charging");
+    }
+
+    /// 模拟拔出
+    pub fn unplug(&self) {
+        self.write(self.paths.real_type(), "Unknown");
+        self.write(self.paths.usb_voltage_now(), "0");
+        self.write(self.paths.battery_status(), "Discharging");
+    }
+}
+
+impl Drop for FakeSysfs {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.root);
+    }
+}
+
+/// 可注入合成 uevent 的报文来源：每个订阅者一对 unix 数据报 socket，注入时广播给全部订阅者
+#[derive(Default)]
+pub struct UeventInjector {
+    subscribers: Mutex<Vec<Subscriber>>,
+}
+
+struct Subscriber {
+    sender: OwnedFd,
+    // 订阅方接收端的副本，只用于查询是否还有未读取的报文
+    receiver: OwnedFd,
+}
+
+impl Subscriber {
+    fn has_pending(&self) -> bool {
+        let mut pending: c_int = 0;
+        let result =
+            unsafe { libc::ioctl(self.receiver.as_raw_fd(), libc::FIONREAD, &mut pending) };
+        assert_ne!(result, -1, "查询uevent接收队列失败");
+        pending > 0
+    }
+}
+
+impl UeventInjector {
+    pub fn new() -> Arc<Self> {
+        Arc::new(Self::default())
+    }
+
+    pub fn subscriber_count(&self) -> usize {
+        self.subscribers.lock().unwrap().len()
+    }
+
+    /// 向全部订阅者发送一条原始报文
+    pub fn inject(&self, datagram: &[u8]) {
+        for subscriber in self.subscribers.lock().unwrap().iter() {
+            let sent = unsafe {
+                libc::send(
+                    subscriber.sender.as_raw_fd(),
+                    datagram.as_ptr() as *const libc::c_void,
+                    datagram.len(),
+                    libc::MSG_DONTWAIT,
+                )
+            };
+            assert_eq!(sent, datagram.len() as isize, "注入uevent失败");
+        }
+    }
+
+    /// 注入一条 power_supply change 事件
+    pub fn power_supply(&self, name: &str, status: &str) {
+        self.inject(&power_supply_uevent(name, status));
+    }
+
+    /// 等待之前注入的报文全部处理完，超时返回 false
+    ///
+    /// 事件循环每轮只读取一条 uevent，且先处理同一轮的文件事件：注入一条不会被处理的屏障报文，
+    /// 屏障被读走即表示之前注入的 uevent 与此前发生的文件修改都已处理完。
+    pub fn settle(&self, timeout: Duration) -> bool {
+        self.inject(BARRIER_UEVENT);
+        wait_until(timeout, || {
+            !self
+                .subscribers
+                .lock()
+                .unwrap()
+                .iter()
+                .any(Subscriber::has_pending)
+        })
+    }
+}
+
+impl UeventSource for UeventInjector {
+    fn open(&self) -> Result<c_int> {
+        let mut fds = [0 as c_int; 2];
+        let result = unsafe {
+            libc::socketpair(
+                libc::AF_UNIX,
+                libc::SOCK_DGRAM | libc::SOCK_CLOEXEC,
+                0,
+                fds.as_mut_ptr(),
+            )
+        };
+        if result == -1 {
+            return Err(anyhow!(
+                "创建socketpair失败: {}",
+                std::io::Error::last_os_error()
+            ));
+        }
+        let sender = unsafe { OwnedFd::from_raw_fd(fds[1]) };
+        let receiver = unsafe { libc::fcntl(fds[0], libc::F_DUPFD_CLOEXEC, 0) };
+        if receiver == -1 {
+            let error = std::io::Error::last_os_error();
+            unsafe {
+                libc::close(fds[0]);
+            }
+            return Err(anyhow!("复制uevent接收端失败: {}", error));
+        }
+        self.subscribers.lock().unwrap().push(Subscriber {
+            sender,
+            receiver: unsafe { OwnedFd::from_raw_fd(receiver) },
+        });
+        Ok(fds[0])
+    }
+}
+
+// 屏障报文：既不是 power_supply 事件也不是 add/remove，事件循环读取后直接丢弃
+const BARRIER_UEVENT: &[u8] = b"change@/devices/virtual/misc/freepps-barrier\0ACTION=change\0\
+    DEVPATH=/devices/virtual/misc/freepps-barrier\0SUBSYSTEM=misc\0";
+
+/// 构造内核格式的 power_supply change 报文
+pub fn power_supply_uevent(name: &str, status: &str) -> Vec<u8> {
+    let devpath = format!("/devices/platform/charger/power_supply/{}", name);
+    let fields = [
+        format!("change@{}", devpath),
+        "ACTION=change".to_string(),
+        format!("DEVPATH={}", devpath),
+        "SUBSYSTEM=power_supply".to_string(),
+        format!("POWER_SUPPLY_NAME={}", name),
+        format!("POWER_SUPPLY_STATUS={}", status),
+    ];
+    let mut datagram = Vec::new();
+    for field in fields {
+        datagram.extend_from_slice(field.as_bytes());
+        datagram.push(0);
+    }
+    datagram
+}
+
+/// 记录全部广播而不真正发送
+#[cfg(feature = "broadcast-forger")]
+#[derive(Default)]
+pub struct RecordingBroadcastSender {
+    sent: Mutex<Vec<Broadcast>>,
+}
+
+#[cfg(feature = "broadcast-forger")]
+impl RecordingBroadcastSender {
+    pub fn new() -> Arc<Self> {
+        Arc::new(Self::default())
+    }
+
+    pub fn sent(&self) -> Vec<Broadcast> {
+        self.sent.lock().unwrap().clone()
+    }
+}
+
+#[cfg(feature = "broadcast-forger")]
+impl BroadcastSender for RecordingBroadcastSender {
+    fn send(&self, broadcast: &Broadcast) -> Result<()> {
+        self.sent.lock().unwrap().push(broadcast.clone());
+        Ok(())
+    }
+}
+
+/// 轮询等待条件成立，超时返回 false
+pub fn wait_until(timeout: Duration, mut condition: impl FnMut() -> bool) -> bool {
+    let deadline = Instant::now() + timeout;
+    loop {
+        if condition() {
+            return true;
+        }
+        if Instant::now() >= deadline {
+            return false;
+        }
+        thread::sleep(Duration::from_millis(10));
+    }
+}
@@ -0,0 +1,326 @@
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+use crate::config::ConfigStore;
+use crate::monitoring::{ModuleManager, Reactor};
+use crate::pd::{BroadcastForger, ChargerBackend, MtkBackend, QcomBackend};
+use crate::platform::ShutdownSignal;
+use crate::session::{SessionState, SharedSession, history};
+use crate::testing::{FakeSysfs, RecordingBroadcastSender, UeventInjector, wait_until};
+
+const TIMEOUT: Duration = Duration::from_secs(5);
+
+const ACTION_QUICK_CHARGE_TYPE: &str = "miui.intent.action.ACTION_QUICK_CHARGE_TYPE";
+const ACTION_SOC_DECIMAL: &str = "miui.intent.action.ACTION_SOC_DECIMAL";
+
+/// 在假 sysfs 上运行的事件循环（单个后端），drop 时停止并 join
+struct Worker {
+    sysfs: FakeSysfs,
+    uevents: Arc<UeventInjector>,
+    backend: Arc<dyn ChargerBackend>,
+    session: SharedSession,
+    shutdown: Arc<ShutdownSignal>,
+    handle: Option<thread::JoinHandle<()>>,
+}
+
+impl Worker {
+    fn start(sysfs: FakeSysfs, backend: Arc<dyn ChargerBackend>) -> Self {
+        let uevents = UeventInjector::new();
+        let shutdown = Arc::new(ShutdownSignal::new().unwrap());
+        let store = Arc::new(ConfigStore::load(sysfs.paths()).unwrap());
+        let module_manager = Arc::new(
+            ModuleManager::new(Arc::clone(sysfs.paths()), vec![Arc::clone(&backend)]).unwrap(),
+        );
+        let session = Arc::clone(module_manager.session());
+
+        let reactor = Reactor::new(
+            Arc::clone(&shutdown),
+            module_manager,
+            store,
+            Arc::clone(&uevents) as _,
+        )
+        .unwrap();
+        let handle = thread::spawn(move || reactor.run().unwrap());
+        // uevent socket 在 inotify watch 之后打开：订阅建立即表示文件监控已就绪
+        assert!(wait_until(TIMEOUT, || uevents.subscriber_count() == 1));
+
+        Self {
+            sysfs,
+            uevents,
+            backend,
+            session,
+            shutdown,
+            handle: Some(handle),
+        }
+    }
+
+    fn state(&self) -> SessionState {
+        self.session.lock().unwrap().state()
+    }
+
+    fn wait_state(&self, state: SessionState) -> bool {
+        wait_until(TIMEOUT, || self.state() == state)
+    }
+
+    /// 解锁节点已回读确认生效的写入请求数
+    fn verified_writes(&self) -> u64 {
+        self.backend.write_stats().map_or(0, |stats| stats.verified)
+    }
+
+    /// 等待事件循环处理完此前的文件修改与注入的 uevent
+    fn settle(&self) -> bool {
+        self.uevents.settle(TIMEOUT)
+    }
+}
+
+impl Drop for Worker {
+    fn drop(&mut self) {
+        self.shutdown.trigger();
+        if let Some(handle) = self.handle.take() {
+            handle.join().unwrap();
+        }
+    }
+}
+
+fn qcom_worker() -> (Worker, Arc<RecordingBroadcastSender>) {
+    qcom_worker_with_config("")
+}
+
+/// 以指定的 freepps.conf 内容启动高通事件循环
+fn qcom_worker_with_config(config: &str) -> (Worker, Arc<RecordingBroadcastSender>) {
+    let sysfs = FakeSysfs::qcom();
+    sysfs.write(sysfs.paths().config_file(), config);
+    let sender = RecordingBroadcastSender::new();
+    let config = Arc::new(ConfigStore::load(sysfs.paths()).unwrap());
+    let forger =
+        BroadcastForger::with_sender(Arc::clone(sysfs.paths()), config, sender.clone()).unwrap();
+    let backend = QcomBackend::with_forger(Arc::clone(sysfs.paths()), forger).unwrap();
+    (Worker::start(sysfs, Arc::new(backend)), sender)
+}
+
+/// 已发送广播的 (action, quick_charge_type) 序列
+fn broadcast_sequence(sender: &RecordingBroadcastSender) -> Vec<(String, Option<i32>)> {
+    sender
+        .sent()
+        .iter()
+        .map(|broadcast| {
+            let quick_charge_type = broadcast
+                .extras
+                .iter()
+                .find(|(key, _)| key == "miui.intent.extra.quick_charge_type")
+                .map(|(_, value)| *value);
+            (broadcast.action.clone(), quick_charge_type)
+        })
+        .collect()
+}
+
+#[test]
+fn qcom_plug_forges_burst_and_unplug_rearms_node() {
+    let (worker, sender) = qcom_worker();
+    let paths = Arc::clone(worker.sysfs.paths());
+
+    worker.sysfs.plug_pps(65);
+    worker.uevents.power_supply("battery", "Charging");
+    assert!(worker.wait_state(SessionState::PpsActive));
+
+    assert!(wait_until(TIMEOUT, || sender.sent().len() == 5));
+    let quick = |t| (ACTION_QUICK_CHARGE_TYPE.to_string(), Some(t));
+    assert_eq!(
+        broadcast_sequence(&sender),
+        [
+            quick(1),
+            (ACTION_SOC_DECIMAL.to_string(), None),
+            quick(4),
+            quick(4),
+            quick(4),
+        ]
+    );
+    assert!(
+        sender.sent()[0]
+            .extras
+            .contains(&("miui.intent.extra.POWER_MAX".to_string(), 65))
+    );
+
+    // 握手结束后内核复位节点，拔出时应写回 1 为下次插电准备
+    worker.sysfs.write(paths.pd_verified(), "0");
+    worker.sysfs.unplug();
+    worker.uevents.power_supply("battery", "Discharging");
+    assert!(worker.wait_state(SessionState::Unplugged));
+    let rearmed = || worker.sysfs.read(paths.pd_verified()) == "1";
+    assert!(wait_until(TIMEOUT, rearmed));
+
+    // 再次插入是新会话，重新执行一次爆发序列
+    worker.sysfs.plug_pps(100);
+    worker.uevents.power_supply("battery", "Charging");
+    assert!(worker.wait_state(SessionState::PpsActive));
+    assert_eq!(worker.session.lock().unwrap().id(), 2);
+    assert!(wait_until(TIMEOUT, || sender.sent().len() == 10));
+    assert!(
+        sender.sent()[5]
+            .extras
+            .contains(&("miui.intent.extra.POWER_MAX".to_string(), 100))
+    );
+}
+
+#[test]
+fn qcom_does_not_forge_for_non_pps_adapter() {
+    // 门控不等待：非 PPS 会话立即放弃
+    let (worker, sender) = qcom_worker_with_config("forge_gate_timeout_ms=0\n");
+    let paths = Arc::clone(worker.sysfs.paths());
+
+    worker.sysfs.write(paths.real_type(), "USB_DCP");
+    worker.sysfs.write(paths.battery_status(), "Charging");
+    worker.uevents.power_supply("battery", "Charging");
+    assert!(worker.wait_state(SessionState::Plugged));
+
+    worker.sysfs.unplug();
+    worker.uevents.power_supply("battery", "Discharging");
+    assert!(worker.wait_state(SessionState::Unplugged));
+
+    // 伪造线程按顺序处理会话：PPS 会话标记已伪造时，前一个会话已处理完
+    worker.sysfs.plug_pps(65);
+    worker.uevents.power_supply("battery", "Charging");
+    assert!(worker.wait_state(SessionState::PpsActive));
+    assert!(wait_until(TIMEOUT, || worker
+        .session
+        .lock()
+        .unwrap()
+        .broadcast_forged()));
+    assert_eq!(worker.session.lock().unwrap().id(), 2);
+    assert_eq!(sender.sent().len(), 5);
+}
+
+#[test]
+fn free_toggle_pauses_and_resumes_session_tracking() {
+    let (worker, _sender) = qcom_worker();
+    let paths = Arc::clone(worker.sysfs.paths());
+
+    worker.sysfs.write(paths.free_file(), "0");
+    assert!(worker.settle());
+
+    // 暂停期间的 uevent 不处理
+    worker.sysfs.plug_pps(65);
+    worker.uevents.power_supply("battery", "Charging");
+    assert!(worker.settle());
+    assert_eq!(worker.state(), SessionState::Idle);
+
+    // 恢复时已在充电：补开始会话
+    worker.sysfs.write(paths.free_file(), "1");
+    assert!(worker.wait_state(SessionState::PpsActive));
+}
+
+#[test]
+fn unplug_during_free_pause_ends_session_on_resume() {
+    let (worker, _sender) = qcom_worker();
+    let paths = Arc::clone(worker.sysfs.paths());
+
+    worker.sysfs.plug_pps(65);
+    worker.uevents.power_supply("battery", "Charging");
+    assert!(worker.wait_state(SessionState::PpsActive));
+
+    worker.sysfs.write(paths.free_file(), "0");
+    assert!(worker.settle());
+    worker.sysfs.unplug();
+    worker.uevents.power_supply("battery", "Discharging");
+    assert!(worker.settle());
+    assert_eq!(worker.state(), SessionState::PpsActive);
+
+    // 恢复时已拔出：补结束会话
+    worker.sysfs.write(paths.free_file(), "1");
+    assert!(worker.wait_state(SessionState::Unplugged));
+}
+
+#[test]
+fn mtk_rearms_node_on_battery_and_usb_events_only() {
+    let sysfs = FakeSysfs::mtk();
+    let backend = MtkBackend::new(Arc::clone(sysfs.paths())).unwrap();
+    let worker = Worker::start(sysfs, Arc::new(backend));
+    let node = worker.sysfs.paths().pd_adapter_verified().to_path_buf();
+
+    let verified = worker.verified_writes();
+    worker.sysfs.write(&node, "0");
+    worker.uevents.power_supply("usb", "Charging");
+    assert!(wait_until(TIMEOUT, || worker.sysfs.read(&node) == "1"));
+    // 等写入回读校验结束再模拟驱动复位，否则会被当作写入后立即改回而重试
+    assert!(wait_until(TIMEOUT, || worker.verified_writes() > verified));
+
+    // 其它 power_supply（如 bms）不在订阅范围内
+    worker.sysfs.write(&node, "0");
+    worker.uevents.power_supply("bms", "Charging");
+    assert!(worker.settle());
+    assert_eq!(worker.sysfs.read(&node), "0");
+
+    worker.uevents.power_supply("battery", "Charging");
+    assert!(wait_until(TIMEOUT, || worker.sysfs.read(&node) == "1"));
+}
+
+#[test]
+fn thermal_guard_holds_unlock_during_session() {
+    let sysfs = FakeSysfs::mtk();
+    let paths = Arc::clone(sysfs.paths());
+    sysfs.write(
+        paths.config_file(),
+        "thermal_guard=on\nthermal_interval_ms=100\n",
+    );
+    sysfs.write(paths.battery_temp(), "500");
+    let backend = MtkBackend::new(Arc::clone(&paths)).unwrap();
+    let worker = Worker::start(sysfs, Arc::new(backend));
+    let node = paths.pd_adapter_verified();
+
+    // 会话开始时立即采样：50℃ 超过上限，写回 0
+    worker.sysfs.plug_pps(65);
+    worker.uevents.power_supply("battery", "Charging");
+    assert!(wait_until(TIMEOUT, || worker.sysfs.read(node) == "0"));
+
+    // 会话期间按间隔采样：降温后恢复解锁
+    worker.sysfs.write(paths.battery_temp(), "400");
+    assert!(wait_until(TIMEOUT, || worker.sysfs.read(node) == "1"));
+}
+
+#[test]
+fn soc_limit_is_evaluated_on_power_supply_uevents() {
+    let sysfs = FakeSysfs::mtk();
+    let paths = Arc::clone(sysfs.paths());
+    sysfs.write(paths.config_file(), "soc_limit=80\n");
+    sysfs.write(paths.battery_capacity(), "79");
+    let backend = MtkBackend::new(Arc::clone(&paths)).unwrap();
+    let worker = Worker::start(sysfs, Arc::new(backend));
+    let node = paths.pd_adapter_verified();
+
+    worker.sysfs.plug_pps(65);
+    worker.uevents.power_supply("battery", "Charging");
+    assert!(worker.wait_state(SessionState::PpsActive));
+    assert_eq!(worker.sysfs.read(node), "1");
+
+    // 电量变化的 battery uevent：达到阈值后写回 0
+    worker.sysfs.write(paths.battery_capacity(), "80");
+    worker.uevents.power_supply("battery", "Charging");
+    assert!(wait_until(TIMEOUT, || worker.sysfs.read(node) == "0"));
+
+    // 拔出时解除暂停，解锁节点写回 1 为下次插电准备
+    worker.sysfs.unplug();
+    worker.uevents.power_supply("battery", "Discharging");
+    assert!(worker.wait_state(SessionState::Unplugged));
+    assert!(wait_until(TIMEOUT, || worker.sysfs.read(node) == "1"));
+}
+
+#[test]
+fn unplug_writes_session_history_record() {
+    let sysfs = FakeSysfs::mtk();
+    let backend = MtkBackend::new(Arc::clone(sysfs.paths())).unwrap();
+    let worker = Worker::start(sysfs, Arc::new(backend));
+    let paths = Arc::clone(worker.sysfs.paths());
+    let records = || history::load(paths.session_history()).unwrap_or_default();
+
+    worker.sysfs.plug_pps(65);
+    worker.uevents.power_supply("battery", "Charging");
+    assert!(worker.wait_state(SessionState::PpsActive));
+
+    worker.sysfs.unplug();
+    worker.uevents.power_supply("battery", "Discharging");
+    assert!(wait_until(TIMEOUT, || records().len() == 1));
+    let record = &records()[0];
+    assert!(record.pps && record.ended_at.is_some());
+    assert_eq!(record.adapter.apdo_max, Some(65));
+}
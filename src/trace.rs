@@ -0,0 +1,125 @@
+//! uevent 轨迹录制与回放
+//!
+//! 轨迹文件为 JSONL：每行一个 [`TraceEntry`]，记录相对录制开始的时间、原始 uevent 报文字段，
+//! 以及收到报文时监控线程会读取的全部节点值。回放时在临时目录中重建这些节点，
+//! 按顺序把报文送入与守护进程相同的处理逻辑，输出确定性的决策日志。
+
+#[cfg(unix)]
+pub mod recorder;
+#[cfg(unix)]
+pub mod replay;
+
+#[cfg(unix)]
+pub use recorder::record;
+#[cfg(unix)]
+pub use replay::replay;
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+
+use anyhow::{Result, anyhow};
+use serde::{Deserialize, Serialize};
+
+use crate::common::{FreePPSError, Paths};
+
+/// 一次快照中的节点值（键为 [`TRACE_NODES`] 中的名称，节点不存在时不出现）
+pub type NodeValues = BTreeMap<String, String>;
+
+/// 轨迹文件中的一行
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum TraceEntry {
+    /// 录制开始时的初始状态
+    Start {
+        t_ms: u64,
+        version: String,
+        started_at: u64,
+        values: NodeValues,
+    },
+    /// 收到一条 power_supply uevent（`fields` 为按 `\0` 拆分的原始报文）
+    Uevent {
+        t_ms: u64,
+        fields: Vec<String>,
+        values: NodeValues,
+    },
+    /// netlink 接收缓冲区溢出，此前的事件可能已丢失
+    Overflow { t_ms: u64, values: NodeValues },
+}
+
+impl TraceEntry {
+    pub fn t_ms(&self) -> u64 {
+        match self {
+            Self::Start { t_ms, .. } | Self::Uevent { t_ms, .. } | Self::Overflow { t_ms, .. } => {
+                *t_ms
+            }
+        }
+    }
+
+    pub fn values(&self) -> &NodeValues {
+        match self {
+            Self::Start { values, .. }
+            | Self::Uevent { values, .. }
+            | Self::Overflow { values, .. } => values,
+        }
+    }
+}
+
+/// 从 [`Paths`] 取节点路径
+pub type NodePath = fn(&Paths) -> &Path;
+
+/// 录制的节点：名称 → 路径
+pub const TRACE_NODES: [(&str, NodePath); 8] = [
+    ("free", Paths::free_file),
+    ("pd_verifed", Paths::pd_verified),
+    ("usbpd_verifed", Paths::pd_adapter_verified),
+    ("battery_status", Paths::battery_status),
+    ("real_type", Paths::real_type),
+    ("apdo_max", Paths::apdo_max),
+    ("adapter_svid", Paths::adapter_svid),
+    ("usb_voltage_now", Paths::usb_voltage_now),
+];
+
+/// 读取全部录制节点的当前值
+pub fn capture_values(paths: &Paths) -> NodeValues {
+    TRACE_NODES
+        .iter()
+        .filter_map(|(name, node)| {
+            fs::read_to_string(node(paths))
+                .ok()
+                .map(|value| (name.to_string(), value.trim().to_string()))
+        })
+        .collect()
+}
+
+/// 把快照写回节点；快照中没有的节点删除，使后端检测结果与录制时一致
+pub fn apply_values(paths: &Paths, values: &NodeValues) -> Result<()> {
+    for (name, node) in TRACE_NODES {
+        let path = node(paths);
+        match values.get(name) {
+            Some(value) => {
+                if let Some(parent) = path.parent() {
+                    fs::create_dir_all(parent).map_err(FreePPSError::FileOperation)?;
+                }
+                fs::write(path, value).map_err(FreePPSError::FileOperation)?;
+            }
+            None if path.exists() => fs::remove_file(path).map_err(FreePPSError::FileOperation)?,
+            None => {}
+        }
+    }
+    Ok(())
+}
+
+/// 读取轨迹文件
+pub fn read_trace(path: &Path) -> Result<Vec<TraceEntry>> {
+    let content = fs::read_to_string(path).map_err(FreePPSError::FileOperation)?;
+    content
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(index, line)| {
+            serde_json::from_str(line)
+                .map_err(|e| anyhow!("轨迹文件第{}行解析失败: {}", index + 1, e))
+        })
+        .collect()
+}
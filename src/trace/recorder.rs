@@ -0,0 +1,115 @@
+use std::fs::File;
+use std::io::Write;
+use std::path::Path;
+use std::time::{Instant, SystemTime};
+
+use anyhow::Result;
+use log::{error, info};
+
+use crate::common::{FreePPSError, Paths};
+use crate::monitoring::uevent::{is_overflow, recv_datagram};
+use crate::monitoring::{FileMonitor, UeventSource};
+use crate::platform::ShutdownSignal;
+use crate::session::unix_secs;
+use crate::trace::{TraceEntry, capture_values};
+
+/// 录制 power_supply uevent 轨迹，直到收到退出信号；返回录制的 uevent 条数
+///
+/// 每条报文到达后立即读取全部录制节点，与监控线程处理该报文时读到的值最接近。
+pub fn record(
+    paths: &Paths,
+    uevents: &dyn UeventSource,
+    output: &Path,
+    shutdown: &ShutdownSignal,
+) -> Result<u64> {
+    let mut file = File::create(output).map_err(FreePPSError::FileOperation)?;
+    let started = Instant::now();
+    let elapsed_ms = || started.elapsed().as_millis() as u64;
+
+    write_entry(
+        &mut file,
+        &TraceEntry::Start {
+            t_ms: 0,
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            started_at: unix_secs(SystemTime::now()),
+            values: capture_values(paths),
+        },
+    )?;
+
+    let file_monitor = FileMonitor::new()?;
+    file_monitor.add_shutdown_to_epoll(shutdown)?;
+    let uevent_sock = uevents.open()?;
+    if let Err(e) = file_monitor.add_fd_to_epoll(
+        uevent_sock,
+        (libc::EPOLLIN | libc::EPOLLPRI) as u32,
+        uevent_sock as u64,
+    ) {
+        unsafe {
+            libc::close(uevent_sock);
+        }
+        return Err(e);
+    }
+    info!("开始录制uevent轨迹: {}", output.display());
+
+    let mut count = 0u64;
+    let mut events = [libc::epoll_event { events: 0, u64: 0 }; 4];
+    while shutdown.is_running() {
+        if let Err(err) = file_monitor.wait_events(&mut events, -1) {
+            match err.raw_os_error() {
+                Some(code) if code == libc::EINTR || code == libc::EAGAIN => continue,
+                _ => {
+                    error!("等待uevent事件失败: {}", err);
+                    break;
+                }
+            }
+        }
+
+        // 一次唤醒读空 socket 中全部报文
+        loop {
+            let entry = match recv_datagram(uevent_sock) {
+                Ok(Some(datagram)) => {
+                    let text = String::from_utf8_lossy(&datagram);
+                    if !text
+                        .split('\0')
+                        .any(|field| field == "SUBSYSTEM=power_supply")
+                    {
+                        continue;
+                    }
+                    count += 1;
+                    TraceEntry::Uevent {
+                        t_ms: elapsed_ms(),
+                        fields: text
+                            .split('\0')
+                            .filter(|field| !field.is_empty())
+                            .map(str::to_string)
+                            .collect(),
+                        values: capture_values(paths),
+                    }
+                }
+                Ok(None) => break,
+                Err(e) if is_overflow(&e) => TraceEntry::Overflow {
+                    t_ms: elapsed_ms(),
+                    values: capture_values(paths),
+                },
+                Err(e) => {
+                    error!("读取uevent失败: {}", e);
+                    break;
+                }
+            };
+            write_entry(&mut file, &entry)?;
+        }
+    }
+
+    unsafe {
+        libc::close(uevent_sock);
+    }
+    info!("uevent轨迹录制结束，共{}条", count);
+    Ok(count)
+}
+
+fn write_entry(file: &mut File, entry: &TraceEntry) -> Result<()> {
+    let line = serde_json::to_string(entry)?;
+    // 逐行落盘：录制通常以 Ctrl+C 结束
+    writeln!(file, "{}", line).map_err(FreePPSError::FileOperation)?;
+    Ok(())
+}
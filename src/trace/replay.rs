@@ -0,0 +1,492 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::{Arc, Mutex};
+
+use anyhow::Result;
+
+use crate::common::{FreeMode, FreePPSError, PathOverrides, Paths};
+#[cfg(feature = "qcom")]
+use crate::config::{Config, ConfigStore};
+use crate::monitoring::charging::{
+    feed_charging, handle_power_supply_uevent, power_supply_filter, resync,
+};
+use crate::monitoring::uevent::Uevent;
+use crate::pd::ChargerBackend;
+#[cfg(feature = "mtk")]
+use crate::pd::MtkBackend;
+#[cfg(feature = "qcom")]
+use crate::pd::QcomBackend;
+#[cfg(feature = "broadcast-forger")]
+use crate::pd::{Broadcast, BroadcastForger, BroadcastSender};
+use crate::platform::ShutdownSignal;
+use crate::session::{ChargingSession, SessionState, SharedSession};
+use crate::trace::{TraceEntry, apply_values};
+
+/// 决策日志：每行以轨迹中的相对时间开头，不含任何墙钟时间，同一轨迹多次回放结果相同
+#[derive(Default)]
+struct DecisionLog {
+    t_ms: Mutex<u64>,
+    lines: Mutex<Vec<String>>,
+}
+
+impl DecisionLog {
+    fn set_time(&self, t_ms: u64) {
+        *self.t_ms.lock().unwrap() = t_ms;
+    }
+
+    fn record(&self, text: impl AsRef<str>) {
+        let t_ms = *self.t_ms.lock().unwrap();
+        self.lines
+            .lock()
+            .unwrap()
+            .push(format!("[{:>8}ms] {}", t_ms, text.as_ref()));
+    }
+}
+
+/// 回放用的广播发送通道：只记录，不调用 am
+#[cfg(feature = "broadcast-forger")]
+struct LoggingSender(Arc<DecisionLog>);
+
+#[cfg(feature = "broadcast-forger")]
+impl BroadcastSender for LoggingSender {
+    fn send(&self, broadcast: &Broadcast) -> Result<()> {
+        let extras: Vec<String> = broadcast
+            .extras
+            .iter()
+            .map(|(key, value)| {
+                format!(
+                    "{}={}",
+                    key.strip_prefix("miui.intent.extra.").unwrap_or(key),
+                    value
+                )
+            })
+            .collect();
+        self.0.record(format!(
+            "广播 {} {}",
+            broadcast
+                .action
+                .strip_prefix("miui.intent.action.")
+                .unwrap_or(&broadcast.action),
+            extras.join(" ")
+        ));
+        Ok(())
+    }
+}
+
+/// 记录节点写入的后端包装
+struct LoggingBackend {
+    inner: Arc<dyn ChargerBackend>,
+    log: Arc<DecisionLog>,
+}
+
+impl ChargerBackend for LoggingBackend {
+    fn name(&self) -> &'static str {
+        self.inner.name()
+    }
+
+    fn node_path(&self) -> &Path {
+        self.inner.node_path()
+    }
+
+    fn detect(&self) -> bool {
+        self.inner.detect()
+    }
+
+    fn unlock(&self) -> Result<()> {
+        self.log.record(format!("{} 写入解锁节点=1", self.name()));
+        self.inner.unlock()
+    }
+
+    fn relock(&self) -> Result<()> {
+        self.log.record(format!("{} 写入解锁节点=0", self.name()));
+        self.inner.relock()
+    }
+
+    fn read_state(&self) -> Result<Option<bool>> {
+        self.inner.read_state()
+    }
+
+    fn rearm_on_power_supply_event(&self) -> bool {
+        self.inner.rearm_on_power_supply_event()
+    }
+
+    fn forge_now(&self, shutdown: &ShutdownSignal) -> Option<bool> {
+        self.inner.forge_now(shutdown)
+    }
+}
+
+/// 回放用的临时目录（模块目录 + sysfs 树），drop 时删除
+struct ReplayTree {
+    root: PathBuf,
+    paths: Arc<Paths>,
+}
+
+impl ReplayTree {
+    fn new() -> Result<Self> {
+        static COUNTER: AtomicU32 = AtomicU32::new(0);
+        let root = std::env::temp_dir().join(format!(
+            "freepps-replay-{}-{}",
+            std::process::id(),
+            COUNTER.fetch_add(1, Ordering::Relaxed)
+        ));
+        let module_dir = root.join("module");
+        let sysfs_root = root.join("sys");
+        fs::create_dir_all(&module_dir).map_err(FreePPSError::FileOperation)?;
+        fs::create_dir_all(&sysfs_root).map_err(FreePPSError::FileOperation)?;
+
+        let overrides = PathOverrides {
+            module_dir: Some(module_dir.clone()),
+            sysfs_root: Some(sysfs_root),
+            config_file: Some(module_dir.join("freepps.conf")),
+        };
+        let paths = Arc::new(Paths::resolve(&overrides)?);
+        Ok(Self { root, paths })
+    }
+}
+
+impl Drop for ReplayTree {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.root);
+    }
+}
+
+/// 回放轨迹，返回决策日志
+///
+/// 与守护进程的区别：广播伪造在每条事件处理完后同步执行（守护进程中由事件循环唤醒
+/// broadcast-forger 线程并发执行），门控读取的是该事件录制时的节点值，因此结果与线程调度无关。
+pub fn replay(entries: &[TraceEntry]) -> Result<Vec<String>> {
+    let tree = ReplayTree::new()?;
+    let paths = &tree.paths;
+    let log = Arc::new(DecisionLog::default());
+    let backends = logging_backends(paths, &log)?;
+
+    let session = ChargingSession::shared();
+    let shutdown = ShutdownSignal::new()?;
+    let filter = power_supply_filter();
+    let mut mode = FreeMode::Off;
+    let mut last_state = (0, session.lock().unwrap().state());
+    let mut burst_done_for = None;
+
+    for entry in entries {
+        log.set_time(entry.t_ms());
+        apply_values(paths, entry.values())?;
+        let detected: Vec<&Arc<dyn ChargerBackend>> =
+            backends.iter().filter(|backend| backend.detect()).collect();
+
+        if let TraceEntry::Start { version, .. } = entry {
+            log.record(format!(
+                "开始回放（录制版本 v{}，后端: {}）",
+                version,
+                detected
+                    .iter()
+                    .map(|backend| backend.name())
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ));
+        }
+
+        // free 缺失时守护进程初始化为 1
+        let now_mode = entry
+            .values()
+            .get("free")
+            .map_or(FreeMode::On, |free| FreeMode::parse(free));
+        let resumed = now_mode.is_enabled() && !mode.is_enabled();
+        if now_mode != mode {
+            log.record(match now_mode {
+                FreeMode::On => "free=1，监控运行",
+                FreeMode::Auto => "free=auto，监控运行，按充电头自动选择",
+                FreeMode::Off => "free=0，监控暂停",
+            });
+            mode = now_mode;
+        }
+        let enabled = mode.is_enabled();
+
+        // 与事件循环启动 / free 恢复时相同：已在充电则直接开始会话
+        let charging = entry.values().get("battery_status").map(String::as_str) == Some("Charging");
+        if resumed && charging && !session.lock().unwrap().is_active() {
+            for backend in &detected {
+                feed_charging(&session, paths, backend.as_ref(), mode)?;
+            }
+        }
+
+        match entry {
+            TraceEntry::Start { .. } => {}
+            TraceEntry::Uevent { fields, .. } if enabled => {
+                let mut datagram = fields.join("\0").into_bytes();
+                datagram.push(0);
+                match Uevent::parse(&datagram) {
+                    Some(uevent) if filter.matches(&uevent) => {
+                        log.record(format!(
+                            "uevent {} status={}",
+                            uevent.name(),
+                            uevent.power_supply_status().unwrap_or("-")
+                        ));
+                        for backend in &detected {
+                            handle_power_supply_uevent(
+                                &uevent,
+                                &session,
+                                paths,
+                                backend.as_ref(),
+                                mode,
+                            )?;
+                        }
+                    }
+                    Some(uevent) => log.record(format!("忽略 uevent {}", uevent.name())),
+                    None => log.record("忽略无法解析的 uevent"),
+                }
+            }
+            TraceEntry::Uevent { .. } => log.record("free=0，忽略 uevent"),
+            TraceEntry::Overflow { .. } if enabled => {
+                log.record("uevent接收缓冲区溢出，重新同步");
+                for backend in &detected {
+                    resync(&session, paths, backend.as_ref(), mode)?;
+                }
+            }
+            TraceEntry::Overflow { .. } => {}
+        }
+
+        last_state = record_transition(&log, &session, last_state);
+        forge_if_new_session(&log, &session, &detected, &shutdown, &mut burst_done_for);
+    }
+
+    drop(tree);
+    let lines = log.lines.lock().unwrap().clone();
+    Ok(lines)
+}
+
+/// 对比处理前后的会话状态，记录跳变（同一事件内的多级跳变合并为一行）
+fn record_transition(
+    log: &DecisionLog,
+    session: &SharedSession,
+    last: (u32, SessionState),
+) -> (u32, SessionState) {
+    let session = session.lock().unwrap();
+    let current = (session.id(), session.state());
+    if current != last {
+        let adapter = session.adapter();
+        log.record(format!(
+            "会话#{} {} → {}（real_type={} svid={} apdo_max={}）",
+            session.id(),
+            last.1.as_str(),
+            current.1.as_str(),
+            adapter.real_type.as_deref().unwrap_or("-"),
+            adapter.svid.as_deref().unwrap_or("-"),
+            adapter
+                .apdo_max
+                .map(|w| w.to_string())
+                .unwrap_or_else(|| "-".to_string())
+        ));
+    }
+    current
+}
+
+/// 编译时启用的全部后端，节点写入与广播都记入决策日志
+fn logging_backends(
+    paths: &Arc<Paths>,
+    log: &Arc<DecisionLog>,
+) -> Result<Vec<Arc<dyn ChargerBackend>>> {
+    #[cfg(not(any(feature = "qcom", feature = "mtk")))]
+    let _ = paths;
+    #[cfg_attr(not(any(feature = "qcom", feature = "mtk")), allow(unused_mut))]
+    let mut backends: Vec<Arc<dyn ChargerBackend>> = Vec::new();
+    #[cfg(feature = "qcom")]
+    {
+        // 回放不读取配置文件，使用默认阈值，结果与设备上的配置无关
+        let config = Arc::new(ConfigStore::with_config(
+            paths.config_file().to_path_buf(),
+            Config::default(),
+        )?);
+        #[cfg(feature = "broadcast-forger")]
+        let qcom = QcomBackend::with_forger(
+            Arc::clone(paths),
+            BroadcastForger::with_sender(
+                Arc::clone(paths),
+                config,
+                Arc::new(LoggingSender(Arc::clone(log))),
+            )?,
+        )?;
+        #[cfg(not(feature = "broadcast-forger"))]
+        let qcom = QcomBackend::new(Arc::clone(paths), config)?;
+        backends.push(Arc::new(qcom));
+    }
+    #[cfg(feature = "mtk")]
+    backends.push(Arc::new(MtkBackend::new(Arc::clone(paths))?));
+
+    Ok(backends
+        .into_iter()
+        .map(|inner| {
+            Arc::new(LoggingBackend {
+                inner,
+                log: Arc::clone(log),
+            }) as Arc<dyn ChargerBackend>
+        })
+        .collect())
+}
+
+/// 与事件循环通知 broadcast-forger 线程相同：每个新会话执行一次爆发序列
+fn forge_if_new_session(
+    log: &DecisionLog,
+    session: &SharedSession,
+    backends: &[&Arc<dyn ChargerBackend>],
+    shutdown: &ShutdownSignal,
+    burst_done_for: &mut Option<u32>,
+) {
+    let (active, session_id) = {
+        let session = session.lock().unwrap();
+        (session.is_active(), session.id())
+    };
+    if !active {
+        *burst_done_for = None;
+        return;
+    }
+    if *burst_done_for == Some(session_id) {
+        return;
+    }
+    *burst_done_for = Some(session_id);
+
+    for backend in backends {
+        match backend.forge_now(shutdown) {
+            Some(true) => session.lock().unwrap().mark_broadcast_forged(),
+            Some(false) => log.record(format!("{} 伪造门控未通过，不发送广播", backend.name())),
+            None => {}
+        }
+    }
+}
+
+// 轨迹均录制自高通设备，期望的决策日志包含广播伪造
+#[cfg(all(test, feature = "broadcast-forger"))]
+mod tests {
+    use super::*;
+    use crate::trace::NodeValues;
+
+    fn values(pairs: &[(&str, &str)]) -> NodeValues {
+        pairs
+            .iter()
+            .map(|(name, value)| (name.to_string(), value.to_string()))
+            .collect()
+    }
+
+    fn battery_uevent(t_ms: u64, status: &str, values: NodeValues) -> TraceEntry {
+        TraceEntry::Uevent {
+            t_ms,
+            fields: vec![
+                "change@/devices/platform/soc/qpnp-smb5/power_supply/battery".to_string(),
+                "ACTION=change".to_string(),
+                "SUBSYSTEM=power_supply".to_string(),
+                "POWER_SUPPLY_NAME=battery".to_string(),
+                format!("POWER_SUPPLY_STATUS={}", status),
+            ],
+            values,
+        }
+    }
+
+    /// 插入 65W PPS 头后拔出，握手结束时内核已把节点复位为 0
+    fn plug_unplug_trace() -> Vec<TraceEntry> {
+        let plugged = values(&[
+            ("free", "1"),
+            ("pd_verifed", "1"),
+            ("battery_status", "Charging"),
+            ("real_type", "PD_PPS"),
+            ("apdo_max", "65"),
+            ("adapter_svid", "0000"),
+            ("usb_voltage_now", "15000000"),
+        ]);
+        let unplugged = values(&[
+            ("free", "1"),
+            ("pd_verifed", "0"),
+            ("battery_status", "Discharging"),
+            ("real_type", "Unknown"),
+            ("usb_voltage_now", "0"),
+        ]);
+        vec![
+            TraceEntry::Start {
+                t_ms: 0,
+                version: "test".to_string(),
+                started_at: 0,
+                values: values(&[
+                    ("free", "1"),
+                    ("pd_verifed", "1"),
+                    ("battery_status", "Discharging"),
+                ]),
+            },
+            battery_uevent(1200, "Charging", plugged),
+            battery_uevent(8400, "Discharging", unplugged),
+        ]
+    }
+
+    #[test]
+    fn replay_produces_deterministic_decision_log() {
+        let trace = plug_unplug_trace();
+        let log = replay(&trace).unwrap();
+        let contains = |needle: &str| log.iter().any(|line| line.contains(needle));
+
+        assert!(contains("后端: qcom"));
+        assert!(contains(
+            "会话#1 idle → pps_active（real_type=PD_PPS svid=0000 apdo_max=65）"
+        ));
+        assert!(contains("广播 ACTION_QUICK_CHARGE_TYPE"));
+        assert!(contains("会话#1 pps_active → unplugged"));
+        assert!(contains("[    8400ms] qcom 写入解锁节点=1"));
+
+        assert_eq!(replay(&trace).unwrap(), log);
+    }
+
+    #[test]
+    fn auto_mode_unlocks_only_public_adapters() {
+        let plug = |t_ms, svid: &str| {
+            battery_uevent(
+                t_ms,
+                "Charging",
+                values(&[
+                    ("free", "auto"),
+                    ("pd_verifed", "0"),
+                    ("battery_status", "Charging"),
+                    ("real_type", "PD_PPS"),
+                    ("adapter_svid", svid),
+                ]),
+            )
+        };
+        // 小米充电头验证通过后内核自行把节点置 1
+        let unplug = |t_ms| {
+            battery_uevent(
+                t_ms,
+                "Discharging",
+                values(&[
+                    ("free", "auto"),
+                    ("pd_verifed", "1"),
+                    ("battery_status", "Discharging"),
+                ]),
+            )
+        };
+        let trace = vec![
+            TraceEntry::Start {
+                t_ms: 0,
+                version: "test".to_string(),
+                started_at: 0,
+                values: values(&[
+                    ("free", "auto"),
+                    ("pd_verifed", "0"),
+                    ("battery_status", "Discharging"),
+                ]),
+            },
+            plug(1000, "2717"),
+            unplug(5000),
+            plug(9000, "0000"),
+        ];
+        let log = replay(&trace).unwrap();
+        let writes: Vec<&str> = log
+            .iter()
+            .filter(|line| line.contains("写入解锁节点"))
+            .map(String::as_str)
+            .collect();
+
+        assert_eq!(
+            writes,
+            [
+                "[    5000ms] qcom 写入解锁节点=0",
+                "[    9000ms] qcom 写入解锁节点=1"
+            ]
+        );
+    }
+}